@@ -0,0 +1,214 @@
+use std::{
+    fs,
+    io::{self, BufRead, Write},
+    process::ExitCode,
+};
+
+use ftables_lib::{
+    core::schema::{
+        SpreadsheetSchema,
+        diff::diff_schemas,
+        export::{ExportFormat, export_schema},
+    },
+    json::ToJson,
+    parse_valid_schema_from_file,
+    ql::{format::format_schema_with_loader, import::FsLoader},
+    repl::Repl,
+};
+
+const USAGE: &str = "Usage:
+    ftables check <file>...
+    ftables fmt [--check] <file>...
+    ftables json <file>
+    ftables export --to <sql|jsonschema|ts> <file>
+    ftables diff [--json] <old> <new>
+    ftables repl";
+
+fn parse_file(path: &str) -> anyhow::Result<SpreadsheetSchema> {
+    parse_valid_schema_from_file(path)
+}
+
+/// Validates every file, printing a diagnostic for each one that fails.
+fn check(paths: &[String]) -> ExitCode {
+    let mut ok = true;
+    for path in paths {
+        match parse_file(path) {
+            Ok(_) => println!("{path}: ok"),
+            Err(e) => {
+                eprintln!("{path}: error: {e:#}");
+                ok = false;
+            }
+        }
+    }
+    if ok {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+/// Rewrites every file in canonical form, or with `check_only` reports the
+/// files that aren't already in canonical form.
+fn fmt(paths: &[String], check_only: bool) -> ExitCode {
+    let mut ok = true;
+    for path in paths {
+        let result = fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Couldn't read {path}: {e}"))
+            .and_then(|s| {
+                parse_file(path)?;
+                let formatted = format_schema_with_loader(path, &FsLoader)?;
+                Ok((s, formatted))
+            });
+
+        match result {
+            Ok((original, formatted)) if original == formatted => {}
+            Ok(_) if check_only => {
+                eprintln!("{path}: not formatted");
+                ok = false;
+            }
+            Ok((_, formatted)) => {
+                if let Err(e) = fs::write(path, formatted) {
+                    eprintln!("{path}: error: Couldn't write file: {e}");
+                    ok = false;
+                }
+            }
+            Err(e) => {
+                eprintln!("{path}: error: {e:#}");
+                ok = false;
+            }
+        }
+    }
+    if ok {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+/// Prints the output of `f` for the schema in `path`.
+fn emit(path: &str, f: impl Fn(&SpreadsheetSchema) -> String) -> ExitCode {
+    match parse_file(path) {
+        Ok(schema) => {
+            println!("{}", f(&schema));
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("{path}: error: {e:#}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Prints the changes between the schemas in `old_path` and `new_path`.
+fn diff(old_path: &str, new_path: &str, json: bool) -> ExitCode {
+    let schemas = parse_file(old_path)
+        .map_err(|e| e.context(old_path.to_string()))
+        .and_then(|old| {
+            let new = parse_file(new_path)
+                .map_err(|e| e.context(new_path.to_string()))?;
+            Ok((old, new))
+        });
+    match schemas {
+        Ok((old, new)) => {
+            let diff = diff_schemas(&old, &new);
+            if json {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&diff.to_json()).unwrap()
+                );
+            } else {
+                println!("{diff}");
+            }
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("error: {e:#}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Runs an interactive session on stdin until end of input or `.quit`.
+fn repl() -> anyhow::Result<ExitCode> {
+    let mut repl = Repl::new();
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+
+    loop {
+        print!(
+            "{}",
+            if repl.is_continuation() {
+                "   ...> "
+            } else {
+                "ftables> "
+            }
+        );
+        io::stdout().flush()?;
+
+        let Some(line) = lines.next() else {
+            break;
+        };
+        let line = line?;
+        if matches!(line.trim(), ".quit" | ".exit") {
+            break;
+        }
+
+        match repl.feed_line(&line) {
+            Some(Ok(output)) if !output.is_empty() => println!("{output}"),
+            Some(Err(e)) => eprintln!("error: {e:#}"),
+            _ => {}
+        }
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn run(args: &[String]) -> anyhow::Result<ExitCode> {
+    let (cmd, rest) = args
+        .split_first()
+        .ok_or(anyhow::anyhow!("Expected a subcommand!"))?;
+
+    match cmd.as_str() {
+        "check" if !rest.is_empty() => Ok(check(rest)),
+        "fmt" => {
+            let check_only = rest.iter().any(|arg| arg == "--check");
+            let paths: Vec<String> = rest
+                .iter()
+                .filter(|arg| *arg != "--check")
+                .cloned()
+                .collect();
+            if paths.is_empty() {
+                return Err(anyhow::anyhow!("Expected at least one file!"));
+            }
+            Ok(fmt(&paths, check_only))
+        }
+        "json" if rest.len() == 1 => Ok(emit(&rest[0], |schema| {
+            serde_json::to_string_pretty(&schema.to_json()).unwrap()
+        })),
+        "export" => match rest {
+            [flag, format, path] if flag == "--to" => {
+                let format = ExportFormat::try_from(format.as_str())?;
+                Ok(emit(path, |schema| export_schema(schema, format)))
+            }
+            _ => Err(anyhow::anyhow!("Expected --to <format> <file>!")),
+        },
+        "diff" => match rest {
+            [flag, old, new] if flag == "--json" => Ok(diff(old, new, true)),
+            [old, new] => Ok(diff(old, new, false)),
+            _ => Err(anyhow::anyhow!("Expected [--json] <old> <new>!")),
+        },
+        "repl" if rest.is_empty() => repl(),
+        _ => Err(anyhow::anyhow!("Unrecognised command {}", args.join(" "))),
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match run(&args) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("error: {e:#}\n\n{USAGE}");
+            ExitCode::from(2)
+        }
+    }
+}
@@ -0,0 +1,21 @@
+use std::{io, process::ExitCode};
+
+use ftables_lib::lsp::Server;
+
+fn main() -> ExitCode {
+    let mut server = Server::new();
+    if let Err(e) =
+        server.run(&mut io::stdin().lock(), &mut io::stdout().lock())
+    {
+        eprintln!("error: {e:#}");
+        return ExitCode::FAILURE;
+    }
+
+    // the LSP spec asks for a non-zero exit code if the client exits without
+    // requesting a shutdown first
+    if server.is_shutdown() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
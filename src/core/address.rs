@@ -0,0 +1,366 @@
+use std::{fmt::Display, rc::Rc};
+
+use crate::{
+    core::{
+        data::TableData,
+        schema::{ColumnSchema, TableSchema},
+    },
+    ql::lex::Literal,
+};
+
+/// Returns the letters naming the column at `idx` in A1 notation: `A` to `Z`,
+/// then `AA` to `ZZ`, then `AAA` onwards.
+pub fn column_letters(idx: usize) -> String {
+    let mut letters = vec![];
+    let mut n = idx + 1;
+    while n > 0 {
+        n -= 1;
+        letters.push(b'A' + (n % 26) as u8);
+        n /= 26;
+    }
+    letters.reverse();
+    String::from_utf8(letters).unwrap()
+}
+
+/// Returns the index of the column named by `letters` in A1 notation, in
+/// either case, or `None` if they aren't column letters.
+pub fn parse_column_letters(letters: &str) -> Option<usize> {
+    if letters.is_empty() {
+        return None;
+    }
+    let mut n: usize = 0;
+    for c in letters.chars() {
+        if !c.is_ascii_alphabetic() {
+            return None;
+        }
+        let digit = (c.to_ascii_uppercase() as u8 - b'A') as usize + 1;
+        n = n.checked_mul(26)?.checked_add(digit)?;
+    }
+    Some(n - 1)
+}
+
+// Parses a 1-based row number into a row index.
+fn parse_row_number(s: &str) -> Option<usize> {
+    if !s.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    s.parse::<usize>().ok()?.checked_sub(1)
+}
+
+/// The address of a cell in A1 notation, such as `B2`: the letters name its
+/// column and the number its row. Row 1 is the first row of the table's
+/// data.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CellAddress {
+    pub column_idx: usize,
+    pub row_idx: usize,
+}
+
+impl CellAddress {
+    pub fn new(column_idx: usize, row_idx: usize) -> Self {
+        Self {
+            column_idx,
+            row_idx,
+        }
+    }
+
+    pub fn parse(s: &str) -> anyhow::Result<Self> {
+        let s = s.trim();
+        let split = s.find(|c: char| c.is_ascii_digit()).unwrap_or(s.len());
+        let (letters, digits) = s.split_at(split);
+        match (parse_column_letters(letters), parse_row_number(digits)) {
+            (Some(column_idx), Some(row_idx)) => {
+                Ok(Self::new(column_idx, row_idx))
+            }
+            _ => Err(anyhow::anyhow!("Invalid cell address {s}!")),
+        }
+    }
+
+    /// Returns the column of `table` the address refers to, or an error if
+    /// the table doesn't have that many columns.
+    pub fn get_column<'a>(
+        &self,
+        table: &'a TableSchema,
+    ) -> anyhow::Result<&'a ColumnSchema> {
+        table.get_column(self.column_idx).ok_or(anyhow::anyhow!(
+            "Table {} has no column {}!",
+            table.get_name(),
+            column_letters(self.column_idx)
+        ))
+    }
+
+    /// Returns the value of the cell in `data`, or an error if the table
+    /// doesn't have the cell's column or row.
+    pub fn get_value<'a>(
+        &self,
+        data: &'a TableData,
+    ) -> anyhow::Result<Option<&'a Literal>> {
+        let schema = data.get_schema();
+        self.get_column(&schema)?;
+        let row = data.get_rows().get(self.row_idx).ok_or(anyhow::anyhow!(
+            "Table {} has no row {}!",
+            schema.get_name(),
+            self.row_idx + 1
+        ))?;
+        Ok(row[self.column_idx].as_ref())
+    }
+}
+
+impl Display for CellAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", column_letters(self.column_idx), self.row_idx + 1)
+    }
+}
+
+/// A rectangular range of cells in A1 notation, such as `B2:D10`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct RangeAddress {
+    start: CellAddress,
+    end: CellAddress,
+}
+
+impl RangeAddress {
+    /// Creates the range with opposite corners `a` and `b`, given in any
+    /// order.
+    pub fn new(a: CellAddress, b: CellAddress) -> Self {
+        Self {
+            start: CellAddress::new(
+                a.column_idx.min(b.column_idx),
+                a.row_idx.min(b.row_idx),
+            ),
+            end: CellAddress::new(
+                a.column_idx.max(b.column_idx),
+                a.row_idx.max(b.row_idx),
+            ),
+        }
+    }
+
+    /// Parses a range such as `B2:D10`. A single cell such as `B2` is the
+    /// range holding only that cell.
+    pub fn parse(s: &str) -> anyhow::Result<Self> {
+        match s.split_once(':') {
+            Some((a, b)) => {
+                Ok(Self::new(CellAddress::parse(a)?, CellAddress::parse(b)?))
+            }
+            None => {
+                let cell = CellAddress::parse(s)?;
+                Ok(Self::new(cell, cell))
+            }
+        }
+    }
+
+    /// Returns the top left cell of the range.
+    pub fn get_start(&self) -> CellAddress {
+        self.start
+    }
+
+    /// Returns the bottom right cell of the range.
+    pub fn get_end(&self) -> CellAddress {
+        self.end
+    }
+
+    pub fn get_num_columns(&self) -> usize {
+        self.end.column_idx - self.start.column_idx + 1
+    }
+
+    pub fn get_num_rows(&self) -> usize {
+        self.end.row_idx - self.start.row_idx + 1
+    }
+
+    pub fn contains(&self, cell: &CellAddress) -> bool {
+        (self.start.column_idx..=self.end.column_idx).contains(&cell.column_idx)
+            && (self.start.row_idx..=self.end.row_idx).contains(&cell.row_idx)
+    }
+
+    /// Returns the cells of the range row by row, left to right.
+    pub fn cells(&self) -> impl Iterator<Item = CellAddress> {
+        let (start, end) = (self.start, self.end);
+        (start.row_idx..=end.row_idx).flat_map(move |row_idx| {
+            (start.column_idx..=end.column_idx)
+                .map(move |column_idx| CellAddress::new(column_idx, row_idx))
+        })
+    }
+
+    /// Returns the columns of `table` the range spans, or an error if the
+    /// table doesn't have them all.
+    pub fn get_columns<'a>(
+        &self,
+        table: &'a TableSchema,
+    ) -> anyhow::Result<&'a [ColumnSchema]> {
+        self.end.get_column(table)?;
+        Ok(&table.get_columns()[self.start.column_idx..=self.end.column_idx])
+    }
+}
+
+impl Display for RangeAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.start == self.end {
+            write!(f, "{}", self.start)
+        } else {
+            write!(f, "{}:{}", self.start, self.end)
+        }
+    }
+}
+
+/// A reference to a column of a table by name, such as `Users[userName]`,
+/// or to one of its cells, such as `Users[userName][2]`. Rows are numbered
+/// from 1, as in A1 notation.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct StructuredRef {
+    table_name: Rc<str>,
+    column_name: Rc<str>,
+    row_idx: Option<usize>,
+}
+
+impl StructuredRef {
+    pub fn new(
+        table_name: Rc<str>,
+        column_name: Rc<str>,
+        row_idx: Option<usize>,
+    ) -> Self {
+        Self {
+            table_name,
+            column_name,
+            row_idx,
+        }
+    }
+
+    pub fn parse(s: &str) -> anyhow::Result<Self> {
+        let s = s.trim();
+        let invalid = || anyhow::anyhow!("Invalid structured reference {s}!");
+
+        let (table_name, rest) = s.split_once('[').ok_or_else(invalid)?;
+        let (column_name, rest) = rest.split_once(']').ok_or_else(invalid)?;
+        if table_name.is_empty() || column_name.is_empty() {
+            return Err(invalid());
+        }
+        let row_idx = match rest {
+            "" => None,
+            _ => Some(
+                rest.strip_prefix('[')
+                    .and_then(|rest| rest.strip_suffix(']'))
+                    .and_then(parse_row_number)
+                    .ok_or_else(invalid)?,
+            ),
+        };
+        Ok(Self::new(table_name.into(), column_name.into(), row_idx))
+    }
+
+    pub fn get_table_name(&self) -> Rc<str> {
+        self.table_name.clone()
+    }
+
+    pub fn get_column_name(&self) -> Rc<str> {
+        self.column_name.clone()
+    }
+
+    /// Returns the index of the referenced row, or `None` if the reference
+    /// is to the whole column.
+    pub fn get_row_idx(&self) -> Option<usize> {
+        self.row_idx
+    }
+
+    /// Returns the index of the referenced column in `table`, or an error if
+    /// the reference is to another table or a column `table` doesn't have.
+    pub fn get_column_idx(&self, table: &TableSchema) -> anyhow::Result<usize> {
+        if table.get_name() != self.table_name {
+            return Err(anyhow::anyhow!(
+                "Reference {self} is not to table {}!",
+                table.get_name()
+            ));
+        }
+        table
+            .get_column_idx(&self.column_name)
+            .ok_or(anyhow::anyhow!(
+                "Table {} has no column {}!",
+                self.table_name,
+                self.column_name
+            ))
+    }
+
+    /// Returns the A1 range of the cells the reference covers in `data`:
+    /// the referenced cell, or every row of the referenced column. Returns
+    /// `None` for a whole column of a table with no rows.
+    pub fn to_range(
+        &self,
+        data: &TableData,
+    ) -> anyhow::Result<Option<RangeAddress>> {
+        let column_idx = self.get_column_idx(&data.get_schema())?;
+        let range = match self.row_idx {
+            Some(row_idx) => {
+                let cell = CellAddress::new(column_idx, row_idx);
+                Some(RangeAddress::new(cell, cell))
+            }
+            None if data.get_num_rows() == 0 => None,
+            None => Some(RangeAddress::new(
+                CellAddress::new(column_idx, 0),
+                CellAddress::new(column_idx, data.get_num_rows() - 1),
+            )),
+        };
+        Ok(range)
+    }
+}
+
+impl Display for StructuredRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}[{}]", self.table_name, self.column_name)?;
+        if let Some(row_idx) = self.row_idx {
+            write!(f, "[{}]", row_idx + 1)?;
+        }
+        Ok(())
+    }
+}
+
+/// A reference to cells of a table, in A1 notation or as a structured
+/// reference.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Reference {
+    Range(RangeAddress),
+    Structured(StructuredRef),
+}
+
+impl Reference {
+    /// Parses a reference such as `B2`, `B2:D10`, `Users[userName]` or
+    /// `Users[userName][2]`.
+    pub fn parse(s: &str) -> anyhow::Result<Self> {
+        if s.contains('[') {
+            Ok(Self::Structured(StructuredRef::parse(s)?))
+        } else {
+            Ok(Self::Range(RangeAddress::parse(s)?))
+        }
+    }
+
+    /// Checks the columns the reference refers to exist in `table`.
+    pub fn validate(&self, table: &TableSchema) -> anyhow::Result<()> {
+        match self {
+            Self::Range(range) => {
+                range.get_columns(table)?;
+            }
+            Self::Structured(structured) => {
+                structured.get_column_idx(table)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the cells of `data` the reference covers, row by row. Cells
+    /// of an A1 range beyond the table's last row are included, so a range
+    /// can address rows that are yet to be written.
+    pub fn cells(&self, data: &TableData) -> anyhow::Result<Vec<CellAddress>> {
+        self.validate(&data.get_schema())?;
+        let range = match self {
+            Self::Range(range) => Some(*range),
+            Self::Structured(structured) => structured.to_range(data)?,
+        };
+        Ok(range.map_or(vec![], |range| range.cells().collect()))
+    }
+}
+
+impl Display for Reference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Range(range) => write!(f, "{range}"),
+            Self::Structured(structured) => write!(f, "{structured}"),
+        }
+    }
+}
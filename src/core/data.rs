@@ -0,0 +1,95 @@
+use std::{collections::HashMap, rc::Rc};
+
+use crate::{core::schema::SharedTableSchema, ql::lex::Literal};
+
+/// A row of a table, holding one cell per column in schema order. Null cells
+/// are `None`.
+pub type Row = Vec<Option<Literal>>;
+
+/// The rows stored in a table, along with the schema they conform to.
+#[derive(Clone)]
+pub struct TableData {
+    schema: SharedTableSchema,
+    rows: Vec<Row>,
+}
+
+impl TableData {
+    pub fn new(schema: SharedTableSchema) -> Self {
+        Self {
+            schema,
+            rows: vec![],
+        }
+    }
+
+    /// Creates a table holding `rows`, validating each against `schema`.
+    pub fn with_rows(
+        schema: SharedTableSchema,
+        rows: Vec<Row>,
+    ) -> anyhow::Result<Self> {
+        let mut data = Self::new(schema);
+        for row in rows {
+            data.push_row(row)?;
+        }
+        Ok(data)
+    }
+
+    pub fn get_schema(&self) -> SharedTableSchema {
+        self.schema.clone()
+    }
+
+    pub fn get_rows(&self) -> &[Row] {
+        &self.rows
+    }
+
+    pub fn get_num_rows(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Validates `row` against the table's schema and appends it. Missing
+    /// trailing cells, and null cells in columns that aren't nullable, are
+    /// filled with the column's default, and computed columns are filled with
+    /// the result of their formula.
+    pub fn push_row(&mut self, row: Row) -> anyhow::Result<()> {
+        if let Some(violation) = self.schema.validate_row(&row).first() {
+            return Err(anyhow::anyhow!("{violation}"));
+        }
+
+        let (row, _) = self.schema.complete_row(&row);
+        self.rows.push(row);
+        Ok(())
+    }
+
+    // Replaces the row at `row_idx` with one the caller has already validated
+    // against the table's schema.
+    pub(crate) fn set_row(&mut self, row_idx: usize, row: Row) {
+        self.rows[row_idx] = row;
+    }
+
+    // Appends rows the caller has already validated and completed against the
+    // table's schema.
+    pub(crate) fn append_rows(&mut self, rows: Vec<Row>) {
+        self.rows.extend(rows);
+    }
+
+    // Removes the rows at `row_idxs`, which must be in ascending order.
+    pub(crate) fn remove_rows(&mut self, row_idxs: &[usize]) {
+        let mut idx = 0;
+        self.rows.retain(|_| {
+            let keep = row_idxs.binary_search(&idx).is_err();
+            idx += 1;
+            keep
+        });
+    }
+
+    // Creates a table from rows the caller has already validated against
+    // `schema`.
+    pub(crate) fn from_validated(
+        schema: SharedTableSchema,
+        rows: Vec<Row>,
+    ) -> Self {
+        Self { schema, rows }
+    }
+}
+
+/// The data stored for every table of a spreadsheet, keyed by table name.
+pub type Dataset = HashMap<Rc<str>, TableData>;
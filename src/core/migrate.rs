@@ -0,0 +1,760 @@
+use std::{fmt::Display, rc::Rc};
+
+use serde_json::{Value, json};
+
+use crate::{
+    core::{
+        data::{Dataset, Row, TableData},
+        schema::{
+            ColumnSchema, DataType, DblDataType, DecimalDataType, IntDataType,
+            SharedDataType, SharedTableSchema, SpreadsheetSchema, StrDataType,
+            TableSchema,
+            coerce::{Strictness, coerce_text},
+            computed::rename_formula_refs,
+            diff::{ColumnChange, TableChange, diff_schemas},
+        },
+    },
+    json::ToJson,
+    ql::lex::Literal,
+};
+
+/// What a migration does with values that don't fit their new column type.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ViolationPolicy {
+    /// Fail the migration.
+    Reject,
+    /// Clamp numbers to the new bounds, truncate strings to the new maximum
+    /// length and drop the fractional part of doubles converted to integers.
+    /// Values that still don't fit fail the migration.
+    Truncate,
+}
+
+/// A single step of a migration.
+#[derive(Clone)]
+pub enum MigrationOp {
+    AddTable(SharedTableSchema),
+    DropTable(Rc<str>),
+    RenameTable {
+        old_name: Rc<str>,
+        new_name: Rc<str>,
+    },
+    /// Appends a column, filling existing rows with its default value.
+    AddColumn {
+        table_name: Rc<str>,
+        column: ColumnSchema,
+    },
+    DropColumn {
+        table_name: Rc<str>,
+        column_name: Rc<str>,
+    },
+    RenameColumn {
+        table_name: Rc<str>,
+        old_name: Rc<str>,
+        new_name: Rc<str>,
+    },
+    /// Converts every value in a column to a new type.
+    ChangeType {
+        table_name: Rc<str>,
+        column_name: Rc<str>,
+        new_type: SharedDataType,
+    },
+    ChangeDefault {
+        table_name: Rc<str>,
+        column_name: Rc<str>,
+        default_value: Option<Literal>,
+    },
+    /// Replaces a column with `column`, of the same name, converting every
+    /// value to its type and filling nulls from its default.
+    AlterColumn {
+        table_name: Rc<str>,
+        column: ColumnSchema,
+    },
+    /// Reorders the columns of a table to the order given.
+    ReorderColumns {
+        table_name: Rc<str>,
+        column_names: Vec<Rc<str>>,
+    },
+}
+
+/// A value the migration couldn't convert, or had to alter to fit.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RowIssue {
+    pub table_name: Rc<str>,
+    pub row_idx: usize,
+    pub column_name: Rc<str>,
+    pub message: String,
+}
+
+/// The outcome of running a migration over a dataset.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MigrationReport {
+    failures: Vec<RowIssue>,
+    truncations: Vec<RowIssue>,
+}
+
+impl MigrationReport {
+    /// Returns the values that couldn't be migrated.
+    pub fn get_failures(&self) -> &[RowIssue] {
+        &self.failures
+    }
+
+    /// Returns the values that were altered to fit their new type.
+    pub fn get_truncations(&self) -> &[RowIssue] {
+        &self.truncations
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// A sequence of operations that rewrites a dataset to follow a schema change.
+#[derive(Clone)]
+pub struct Migration {
+    ops: Vec<MigrationOp>,
+    policy: ViolationPolicy,
+}
+
+// The result of fitting a single value to a column.
+enum Fit {
+    Exact(Option<Literal>),
+    Truncated(Option<Literal>, String),
+    Failed(String),
+}
+
+// Converts a whole double to an integer, or returns `None` if it's out of
+// range. The bounds are powers of two, so they're exact as doubles.
+fn dbl_to_int(val: f64) -> Option<i64> {
+    (val >= i64::MIN as f64 && val < -(i64::MIN as f64)).then_some(val as i64)
+}
+
+// Converts `lit` to the kind of value `dtype` holds, returning whether the
+// conversion lost information.
+fn convert_literal(
+    lit: Literal,
+    dtype: &dyn DataType,
+    policy: ViolationPolicy,
+) -> anyhow::Result<(Literal, bool)> {
+    let any = dtype.as_any();
+    if let Literal::Str(s) = &lit
+        && !any.is::<StrDataType>()
+    {
+        return match coerce_text(any, s, Strictness::Lenient)? {
+            Some(lit) => Ok((lit, false)),
+            None => Err(anyhow::anyhow!("Can't convert \"{s}\" to {dtype}")),
+        };
+    }
+    if any.is::<IntDataType>() {
+        return match lit {
+            Literal::Dbl(val)
+                if val.fract() == 0.0
+                    || policy == ViolationPolicy::Truncate =>
+            {
+                let lossy = val.fract() != 0.0;
+                match dbl_to_int(val.trunc()) {
+                    Some(int) => Ok((Literal::Int(int), lossy)),
+                    // casts saturate, clamping the value to the integer range
+                    None if policy == ViolationPolicy::Truncate => {
+                        Ok((Literal::Int(val as i64), true))
+                    }
+                    None => Err(anyhow::anyhow!(
+                        "{val} is too large for an integer"
+                    )),
+                }
+            }
+            Literal::Decimal(val) => {
+                let lossy = !val.fract().is_zero();
+                if lossy && policy != ViolationPolicy::Truncate {
+                    return Err(anyhow::anyhow!(
+                        "Can't convert {val} to an integer without losing \
+                        precision"
+                    ));
+                }
+                let int = i64::try_from(val.trunc()).map_err(|_| {
+                    anyhow::anyhow!("{val} is too large for an integer")
+                })?;
+                Ok((Literal::Int(int), lossy))
+            }
+            Literal::Dbl(val) => Err(anyhow::anyhow!(
+                "Can't convert {val} to an integer without losing precision"
+            )),
+            lit => Ok((lit, false)),
+        };
+    }
+    if any.is::<DblDataType>() {
+        return match lit {
+            Literal::Int(val) => Ok((Literal::Dbl(val as f64), false)),
+            Literal::Decimal(_) => match lit.get_f64() {
+                Some(val) => Ok((Literal::Dbl(val), false)),
+                None => Err(anyhow::anyhow!("Can't convert {lit} to a double")),
+            },
+            lit => Ok((lit, false)),
+        };
+    }
+    if any.is::<DecimalDataType>() {
+        return match lit {
+            Literal::Int(_) | Literal::Dbl(_) => match lit.get_decimal() {
+                Some(val) => Ok((Literal::Decimal(val), false)),
+                None => {
+                    Err(anyhow::anyhow!("Can't convert {lit} to a decimal"))
+                }
+            },
+            lit => Ok((lit, false)),
+        };
+    }
+    if any.is::<StrDataType>() {
+        return match lit {
+            Literal::Int(_) | Literal::Dbl(_) | Literal::Decimal(_) => {
+                Ok((Literal::Str(lit.to_string().into()), false))
+            }
+            lit => Ok((lit, false)),
+        };
+    }
+    Ok((lit, false))
+}
+
+fn clamp<T: PartialOrd>(val: T, min: Option<T>, max: Option<T>) -> T {
+    match (min, max) {
+        (Some(min), _) if val < min => min,
+        (_, Some(max)) if val > max => max,
+        _ => val,
+    }
+}
+
+// Alters `lit` to lie within the bounds of `dtype`, where possible.
+fn clamp_literal(lit: &Literal, dtype: &dyn DataType) -> Option<Literal> {
+    let any = dtype.as_any();
+    match lit {
+        Literal::Int(val) => {
+            let dtype = any.downcast_ref::<IntDataType>()?;
+            let (min, max) = dtype.get_range();
+            Some(Literal::Int(clamp(*val, Some(min), Some(max))))
+        }
+        Literal::Dbl(val) => {
+            let dtype = any.downcast_ref::<DblDataType>()?;
+            Some(Literal::Dbl(clamp(*val, dtype.get_min(), dtype.get_max())))
+        }
+        Literal::Decimal(val) => {
+            // round away extra digits after the point, then clamp the rest
+            let dtype = any.downcast_ref::<DecimalDataType>()?;
+            let val = match dtype.get_scale() {
+                Some(scale) => val.round_dp(scale),
+                None => *val,
+            };
+            let max = dtype.get_max_abs();
+            Some(Literal::Decimal(clamp(val, max.map(|max| -max), max)))
+        }
+        Literal::Str(s) => {
+            let dtype = any.downcast_ref::<StrDataType>()?;
+            let max = dtype.get_max()?;
+            Some(Literal::Str(dtype.get_unit().truncate(s, max).into()))
+        }
+    }
+}
+
+// Fits `value` to `column`, filling nulls from the column's default.
+fn fit_value(
+    value: Option<Literal>,
+    column: &ColumnSchema,
+    policy: ViolationPolicy,
+) -> Fit {
+    let dtype = column.get_type();
+    let value = match value {
+        None if !dtype.get_nullable() => column.get_default_value().cloned(),
+        value => value,
+    };
+    let Some(lit) = value else {
+        return match dtype.validate_literal(None) {
+            Ok(()) => Fit::Exact(None),
+            Err(e) => Fit::Failed(e.to_string()),
+        };
+    };
+
+    let original = lit.to_string();
+    let (lit, lossy) = match convert_literal(lit, &*dtype, policy) {
+        Ok(converted) => converted,
+        Err(e) => return Fit::Failed(e.to_string()),
+    };
+    match dtype.validate_literal(Some(&lit)) {
+        Ok(()) if lossy => Fit::Truncated(
+            Some(lit.clone()),
+            format!("Truncated {original} to {lit}"),
+        ),
+        Ok(()) => Fit::Exact(Some(lit)),
+        Err(e) if policy == ViolationPolicy::Truncate => {
+            match clamp_literal(&lit, &*dtype) {
+                Some(clamped)
+                    if dtype.validate_literal(Some(&clamped)).is_ok() =>
+                {
+                    Fit::Truncated(
+                        Some(clamped.clone()),
+                        format!("{e}, changed {original} to {clamped}"),
+                    )
+                }
+                _ => Fit::Failed(e.to_string()),
+            }
+        }
+        Err(e) => Fit::Failed(e.to_string()),
+    }
+}
+
+fn column_idx(
+    columns: &[ColumnSchema],
+    table_name: &str,
+    column_name: &str,
+) -> anyhow::Result<usize> {
+    columns
+        .iter()
+        .position(|col| &*col.get_name() == column_name)
+        .ok_or(anyhow::anyhow!(
+            "Table {table_name} has no column {column_name}!"
+        ))
+}
+
+fn check_new_column(
+    columns: &[ColumnSchema],
+    table_name: &str,
+    column_name: &str,
+) -> anyhow::Result<()> {
+    if columns.iter().any(|col| &*col.get_name() == column_name) {
+        return Err(anyhow::anyhow!(
+            "Column {column_name} is already defined in table {table_name}!"
+        ));
+    }
+    Ok(())
+}
+
+// A table being rewritten by a migration.
+struct TableState {
+    name: Rc<str>,
+    columns: Vec<ColumnSchema>,
+    rows: Vec<Row>,
+}
+
+impl TableState {
+    fn from_data(data: &TableData) -> Self {
+        let schema = data.get_schema();
+        Self {
+            name: schema.get_name(),
+            columns: schema.get_columns().to_vec(),
+            rows: data.get_rows().to_vec(),
+        }
+    }
+
+    fn into_data(self) -> TableData {
+        let schema = Rc::new(TableSchema::new(self.name, self.columns));
+        TableData::from_validated(schema, self.rows)
+    }
+
+    // Fits every value in column `idx` to its type, recording issues in
+    // `report`.
+    fn fit_column(
+        &mut self,
+        idx: usize,
+        policy: ViolationPolicy,
+        report: &mut MigrationReport,
+    ) {
+        let column = &self.columns[idx];
+        for (row_idx, row) in self.rows.iter_mut().enumerate() {
+            let issue = |message| RowIssue {
+                table_name: self.name.clone(),
+                row_idx,
+                column_name: column.get_name(),
+                message,
+            };
+            match fit_value(row[idx].take(), column, policy) {
+                Fit::Exact(value) => row[idx] = value,
+                Fit::Truncated(value, message) => {
+                    row[idx] = value;
+                    report.truncations.push(issue(message));
+                }
+                Fit::Failed(message) => report.failures.push(issue(message)),
+            }
+        }
+    }
+
+    // Recomputes the table's computed columns from the migrated rows,
+    // recording cells whose formula can't be evaluated in `report`.
+    fn compute_rows(
+        &mut self,
+        report: &mut MigrationReport,
+    ) -> anyhow::Result<()> {
+        if !self.columns.iter().any(|col| col.is_computed()) {
+            return Ok(());
+        }
+        let schema = TableSchema::new(self.name.clone(), self.columns.clone());
+        schema.check_computed_columns()?;
+        for (row_idx, row) in self.rows.iter_mut().enumerate() {
+            let (completed, violations) = schema.complete_row(row);
+            *row = completed;
+            report
+                .failures
+                .extend(violations.into_iter().map(|violation| RowIssue {
+                    table_name: self.name.clone(),
+                    row_idx,
+                    column_name: violation.get_column_name().unwrap(),
+                    message: violation.get_message().to_string(),
+                }));
+        }
+        Ok(())
+    }
+}
+
+impl Migration {
+    pub fn new(ops: Vec<MigrationOp>) -> Self {
+        Self {
+            ops,
+            policy: ViolationPolicy::Reject,
+        }
+    }
+
+    /// Plans the migration from `old` to `new`. Renamed tables are detected as
+    /// in `diff_schemas`; columns are matched by name, and the cells of
+    /// stored rows are moved to the new column order.
+    pub fn between(old: &SpreadsheetSchema, new: &SpreadsheetSchema) -> Self {
+        let mut ops = vec![];
+        for change in diff_schemas(old, new).get_changes() {
+            match change {
+                TableChange::Added(name) => {
+                    if let Some(table) = new.get_table_by_name(name) {
+                        ops.push(MigrationOp::AddTable(table.clone()));
+                    }
+                }
+                TableChange::Removed(name) => {
+                    ops.push(MigrationOp::DropTable(name.clone()))
+                }
+                TableChange::Renamed { old_name, new_name } => {
+                    ops.push(MigrationOp::RenameTable {
+                        old_name: old_name.clone(),
+                        new_name: new_name.clone(),
+                    })
+                }
+                TableChange::Altered(table_name, changes) => {
+                    let Some(new_table) = new.get_table_by_name(table_name)
+                    else {
+                        continue;
+                    };
+                    let column_ops = changes.iter().filter_map(|change| {
+                        column_change_op(table_name, new_table, change)
+                    });
+                    for op in column_ops {
+                        // a column whose type, default or formula change
+                        // together is altered once, to fit its rows to the new
+                        // column
+                        if let MigrationOp::AlterColumn { column, .. } = &op
+                            && ops.iter().any(|altered| {
+                                is_column_altered(altered, table_name, column)
+                            })
+                        {
+                            continue;
+                        }
+                        ops.push(op);
+                    }
+                    ops.push(MigrationOp::ReorderColumns {
+                        table_name: table_name.clone(),
+                        column_names: new_table
+                            .get_columns()
+                            .iter()
+                            .map(|col| col.get_name())
+                            .collect(),
+                    });
+                }
+            }
+        }
+        Self::new(ops)
+    }
+
+    /// Sets what the migration does with values that don't fit their new
+    /// type.
+    pub fn with_policy(mut self, policy: ViolationPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn get_ops(&self) -> &[MigrationOp] {
+        &self.ops
+    }
+
+    /// Runs the migration on a copy of `data`, reporting the values that would
+    /// fail or be altered. Errors if an operation refers to a missing table or
+    /// column.
+    pub fn dry_run(&self, data: &Dataset) -> anyhow::Result<MigrationReport> {
+        Ok(self.run(data)?.1)
+    }
+
+    /// Runs the migration, replacing `data` with the migrated rows. `data` is
+    /// left unchanged if any value fails to migrate.
+    pub fn apply(&self, data: &mut Dataset) -> anyhow::Result<MigrationReport> {
+        let (migrated, report) = self.run(data)?;
+        if let Some(failure) = report.failures.first() {
+            return Err(anyhow::anyhow!(
+                "Migration failed for {} values, starting with {failure}",
+                report.failures.len()
+            ));
+        }
+        *data = migrated;
+        Ok(report)
+    }
+
+    fn run(
+        &self,
+        data: &Dataset,
+    ) -> anyhow::Result<(Dataset, MigrationReport)> {
+        let mut data = data.clone();
+        let mut report = MigrationReport::default();
+        for op in &self.ops {
+            self.apply_op(op, &mut data, &mut report)?;
+        }
+        Ok((data, report))
+    }
+
+    fn apply_op(
+        &self,
+        op: &MigrationOp,
+        data: &mut Dataset,
+        report: &mut MigrationReport,
+    ) -> anyhow::Result<()> {
+        let table_name = match op {
+            MigrationOp::AddTable(schema) => {
+                let name = schema.get_name();
+                if data.contains_key(&name) {
+                    return Err(anyhow::anyhow!(
+                        "Table {name} is already defined!"
+                    ));
+                }
+                data.insert(name, TableData::new(schema.clone()));
+                return Ok(());
+            }
+            MigrationOp::DropTable(name) => {
+                data.remove(name)
+                    .ok_or(anyhow::anyhow!("Table {name} is not defined!"))?;
+                return Ok(());
+            }
+            MigrationOp::RenameTable { old_name, new_name } => {
+                if data.contains_key(new_name) {
+                    return Err(anyhow::anyhow!(
+                        "Table {new_name} is already defined!"
+                    ));
+                }
+                let table = data.remove(old_name).ok_or(anyhow::anyhow!(
+                    "Table {old_name} is not defined!"
+                ))?;
+                let mut state = TableState::from_data(&table);
+                state.name = new_name.clone();
+                data.insert(new_name.clone(), state.into_data());
+                return Ok(());
+            }
+            MigrationOp::AddColumn { table_name, .. }
+            | MigrationOp::DropColumn { table_name, .. }
+            | MigrationOp::RenameColumn { table_name, .. }
+            | MigrationOp::ChangeType { table_name, .. }
+            | MigrationOp::ChangeDefault { table_name, .. }
+            | MigrationOp::AlterColumn { table_name, .. }
+            | MigrationOp::ReorderColumns { table_name, .. } => table_name,
+        };
+
+        let table = data
+            .get(table_name)
+            .ok_or(anyhow::anyhow!("Table {table_name} is not defined!"))?;
+        let mut state = TableState::from_data(table);
+        let columns = &state.columns;
+
+        match op {
+            MigrationOp::AddColumn { column, .. } => {
+                check_new_column(columns, table_name, &column.get_name())?;
+                state.columns.push(column.clone());
+                for row in &mut state.rows {
+                    row.push(None);
+                }
+                if !column.is_computed() {
+                    state.fit_column(
+                        state.columns.len() - 1,
+                        self.policy,
+                        report,
+                    );
+                }
+            }
+            MigrationOp::DropColumn { column_name, .. } => {
+                let idx = column_idx(columns, table_name, column_name)?;
+                state.columns.remove(idx);
+                for row in &mut state.rows {
+                    row.remove(idx);
+                }
+            }
+            MigrationOp::RenameColumn {
+                old_name, new_name, ..
+            } => {
+                let idx = column_idx(columns, table_name, old_name)?;
+                check_new_column(columns, table_name, new_name)?;
+                let col = &state.columns[idx];
+                state.columns[idx] = ColumnSchema::new(
+                    new_name.clone(),
+                    col.get_type(),
+                    col.get_default_value().cloned(),
+                )
+                .with_formula(col.get_formula().cloned());
+                rename_formula_refs(&mut state.columns, old_name, new_name);
+            }
+            MigrationOp::ChangeType {
+                column_name,
+                new_type,
+                ..
+            } => {
+                let idx = column_idx(columns, table_name, column_name)?;
+                let col = &state.columns[idx];
+                state.columns[idx] = ColumnSchema::new(
+                    col.get_name(),
+                    new_type.clone(),
+                    col.get_default_value().cloned(),
+                )
+                .with_formula(col.get_formula().cloned());
+                state.fit_column(idx, self.policy, report);
+            }
+            MigrationOp::ChangeDefault {
+                column_name,
+                default_value,
+                ..
+            } => {
+                let idx = column_idx(columns, table_name, column_name)?;
+                let col = &state.columns[idx];
+                col.get_type().validate_literal(default_value.as_ref())?;
+                state.columns[idx] = ColumnSchema::new(
+                    col.get_name(),
+                    col.get_type(),
+                    default_value.clone(),
+                )
+                .with_formula(col.get_formula().cloned());
+            }
+            MigrationOp::AlterColumn { column, .. } => {
+                let idx = column_idx(columns, table_name, &column.get_name())?;
+                if let Some(default) = column.get_default_value() {
+                    column.get_type().validate_literal(Some(default))?;
+                }
+                state.columns[idx] = column.clone();
+                if !column.is_computed() {
+                    state.fit_column(idx, self.policy, report);
+                }
+            }
+            MigrationOp::ReorderColumns { column_names, .. } => {
+                if column_names.len() != columns.len() {
+                    return Err(anyhow::anyhow!(
+                        "Expected {} columns to reorder table {table_name}, got {}!",
+                        columns.len(),
+                        column_names.len()
+                    ));
+                }
+                let order = column_names
+                    .iter()
+                    .map(|name| column_idx(columns, table_name, name))
+                    .collect::<anyhow::Result<Vec<usize>>>()?;
+                state.columns =
+                    order.iter().map(|idx| columns[*idx].clone()).collect();
+                for row in &mut state.rows {
+                    *row = order.iter().map(|idx| row[*idx].clone()).collect();
+                }
+            }
+            // operations on whole tables were applied above
+            MigrationOp::AddTable(_)
+            | MigrationOp::DropTable(_)
+            | MigrationOp::RenameTable { .. } => unreachable!(),
+        }
+
+        state.compute_rows(report)?;
+        data.insert(table_name.clone(), state.into_data());
+        Ok(())
+    }
+}
+
+// Returns the operation that makes a column of `new_table` follow `change`.
+fn column_change_op(
+    table_name: &Rc<str>,
+    new_table: &TableSchema,
+    change: &ColumnChange,
+) -> Option<MigrationOp> {
+    let table_name = table_name.clone();
+    match change {
+        ColumnChange::Added(name, _) => Some(MigrationOp::AddColumn {
+            table_name,
+            column: new_table.get_column_by_name(name)?.clone(),
+        }),
+        ColumnChange::Removed(name) => Some(MigrationOp::DropColumn {
+            table_name,
+            column_name: name.clone(),
+        }),
+        ColumnChange::TypeChanged { column_name, .. }
+        | ColumnChange::NullabilityChanged { column_name, .. }
+        | ColumnChange::DefaultChanged { column_name, .. }
+        | ColumnChange::FormulaChanged { column_name, .. } => {
+            Some(MigrationOp::AlterColumn {
+                table_name,
+                column: new_table.get_column_by_name(column_name)?.clone(),
+            })
+        }
+        // altered tables are always reordered to the new column order
+        ColumnChange::Reordered(_) => None,
+    }
+}
+
+// Returns whether `op` alters `column` of the table `table_name`.
+fn is_column_altered(
+    op: &MigrationOp,
+    table_name: &str,
+    column: &ColumnSchema,
+) -> bool {
+    matches!(
+        op,
+        MigrationOp::AlterColumn {
+            table_name: altered_table,
+            column: altered,
+        } if **altered_table == *table_name
+            && altered.get_name() == column.get_name()
+    )
+}
+
+impl Display for RowIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} row {}, column {}: {}",
+            self.table_name, self.row_idx, self.column_name, self.message
+        )
+    }
+}
+
+impl Display for MigrationReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} values would fail, {} would be truncated",
+            self.failures.len(),
+            self.truncations.len()
+        )?;
+        for failure in &self.failures {
+            write!(f, "\nfailed: {failure}")?;
+        }
+        for truncation in &self.truncations {
+            write!(f, "\ntruncated: {truncation}")?;
+        }
+        std::fmt::Result::Ok(())
+    }
+}
+
+impl ToJson for RowIssue {
+    fn to_json(&self) -> Value {
+        json!({
+            "table_name": &*self.table_name,
+            "row": self.row_idx,
+            "column_name": &*self.column_name,
+            "message": self.message
+        })
+    }
+}
+
+impl ToJson for MigrationReport {
+    fn to_json(&self) -> Value {
+        json!({
+            "failures": self.failures.iter().map(|issue| issue.to_json()).collect::<Vec<Value>>(),
+            "truncations": self.truncations.iter().map(|issue| issue.to_json()).collect::<Vec<Value>>()
+        })
+    }
+}
@@ -1 +1,5 @@
+pub mod address;
+pub mod data;
+pub mod migrate;
+pub mod recalc;
 pub mod schema;
@@ -0,0 +1,317 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    rc::Rc,
+};
+
+use crate::{
+    core::{
+        data::Dataset,
+        schema::{
+            SpreadsheetSchema,
+            computed::{convert_result, order_computed},
+        },
+    },
+    ql::lex::Literal,
+};
+
+/// A column of one of a spreadsheet's tables.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ColumnRef {
+    pub table_name: Rc<str>,
+    pub column_name: Rc<str>,
+}
+
+impl ColumnRef {
+    pub fn new(table_name: Rc<str>, column_name: Rc<str>) -> Self {
+        Self {
+            table_name,
+            column_name,
+        }
+    }
+}
+
+impl Display for ColumnRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.table_name, self.column_name)
+    }
+}
+
+/// A cell of one of a spreadsheet's tables, identified by its row index and
+/// column.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CellRef {
+    pub table_name: Rc<str>,
+    pub row_idx: usize,
+    pub column_name: Rc<str>,
+}
+
+impl CellRef {
+    pub fn new(
+        table_name: Rc<str>,
+        row_idx: usize,
+        column_name: Rc<str>,
+    ) -> Self {
+        Self {
+            table_name,
+            row_idx,
+            column_name,
+        }
+    }
+
+    pub fn get_column(&self) -> ColumnRef {
+        ColumnRef::new(self.table_name.clone(), self.column_name.clone())
+    }
+}
+
+impl Display for CellRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}.{}[{}]",
+            self.table_name, self.column_name, self.row_idx
+        )
+    }
+}
+
+/// The dependencies between the computed columns of a spreadsheet and the
+/// columns their formulas refer to, across all of its tables. Formulas can
+/// only refer to columns of their own table, so lookups across tables aren't
+/// tracked.
+#[derive(Clone, Debug, Default)]
+pub struct DependencyGraph {
+    // the columns each computed column's formula refers to
+    dependencies: HashMap<ColumnRef, Vec<ColumnRef>>,
+    // the computed columns, each after the columns it depends on
+    order: Vec<ColumnRef>,
+}
+
+impl DependencyGraph {
+    /// Builds the graph of `schema`'s computed columns, or returns an error
+    /// naming the columns involved if formulas refer to unknown columns or
+    /// to each other in a cycle.
+    pub fn new(schema: &SpreadsheetSchema) -> anyhow::Result<Self> {
+        let mut dependencies = HashMap::new();
+        let mut computed = vec![];
+        for table in schema.get_tables() {
+            for col in table.get_columns() {
+                let Some(formula) = col.get_formula() else {
+                    continue;
+                };
+                let col = ColumnRef::new(table.get_name(), col.get_name());
+                let mut refs = vec![];
+                for name in formula.get_column_refs() {
+                    if table.get_column_by_name(&name).is_none() {
+                        return Err(anyhow::anyhow!(
+                            "Computed column {col} refers to unknown column \
+                            {name}!"
+                        ));
+                    }
+                    refs.push(ColumnRef::new(table.get_name(), name));
+                }
+                dependencies.insert(col.clone(), refs);
+                computed.push(col);
+            }
+        }
+
+        let order = order_computed(
+            computed,
+            &|col| dependencies.get(col).cloned(),
+            &|col| col.to_string(),
+        )?;
+        Ok(Self {
+            dependencies,
+            order,
+        })
+    }
+
+    /// Returns the computed columns, each after the columns it depends on.
+    pub fn get_order(&self) -> &[ColumnRef] {
+        &self.order
+    }
+
+    /// Returns the columns the formula of `col` refers to, or nothing if it
+    /// isn't computed.
+    pub fn get_dependencies(&self, col: &ColumnRef) -> &[ColumnRef] {
+        self.dependencies
+            .get(col)
+            .map(|refs| refs.as_slice())
+            .unwrap_or(&[])
+    }
+
+    pub fn is_computed(&self, col: &ColumnRef) -> bool {
+        self.dependencies.contains_key(col)
+    }
+
+    /// Returns the computed columns that depend on any of `changed`, directly
+    /// or through other computed columns, in the order they must be
+    /// recalculated.
+    pub fn get_affected(&self, changed: &[ColumnRef]) -> Vec<ColumnRef> {
+        let mut affected: HashSet<&ColumnRef> = changed.iter().collect();
+        let mut order = vec![];
+        for col in &self.order {
+            if self
+                .get_dependencies(col)
+                .iter()
+                .any(|dep| affected.contains(dep))
+            {
+                affected.insert(col);
+                order.push(col.clone());
+            }
+        }
+        order
+    }
+}
+
+/// The cells a recalculation touched, so a view can repaint only those.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Recalculation {
+    recomputed: Vec<CellRef>,
+    changed: Vec<CellRef>,
+}
+
+impl Recalculation {
+    /// Returns the computed cells whose formulas were evaluated again, in
+    /// the order they were evaluated.
+    pub fn get_recomputed(&self) -> &[CellRef] {
+        &self.recomputed
+    }
+
+    /// Returns the cells whose values changed, starting with the edited cell.
+    pub fn get_changed(&self) -> &[CellRef] {
+        &self.changed
+    }
+}
+
+/// Keeps the computed cells of a dataset up to date as its input cells are
+/// edited, recalculating only the cells that depend on the edit.
+pub struct Recalculator {
+    graph: DependencyGraph,
+}
+
+impl Recalculator {
+    pub fn new(schema: &SpreadsheetSchema) -> anyhow::Result<Self> {
+        Ok(Self {
+            graph: DependencyGraph::new(schema)?,
+        })
+    }
+
+    pub fn get_graph(&self) -> &DependencyGraph {
+        &self.graph
+    }
+
+    /// Sets `cell` of `data` to `value`, or null if `value` is `None`, and
+    /// recalculates the computed cells of its row that depend on it. A
+    /// computed cell is only recalculated if one of the cells its formula
+    /// refers to changed. Nothing is written if the value, or any
+    /// recalculated value, doesn't conform to its column's type.
+    pub fn set_cell(
+        &self,
+        data: &mut Dataset,
+        cell: &CellRef,
+        value: Option<Literal>,
+    ) -> anyhow::Result<Recalculation> {
+        let table = data.get_mut(&cell.table_name).ok_or(anyhow::anyhow!(
+            "Table {} is not defined!",
+            cell.table_name
+        ))?;
+        let schema = table.get_schema();
+        let col_idx =
+            schema
+                .get_column_idx(&cell.column_name)
+                .ok_or(anyhow::anyhow!(
+                    "Table {} has no column {}!",
+                    cell.table_name,
+                    cell.column_name
+                ))?;
+        let column = &schema.get_columns()[col_idx];
+        if column.is_computed() {
+            return Err(anyhow::anyhow!(
+                "Column {} is computed, so can't be set!",
+                cell.get_column()
+            ));
+        }
+        let mut row = table
+            .get_rows()
+            .get(cell.row_idx)
+            .ok_or(anyhow::anyhow!(
+                "Table {} has no row {}!",
+                cell.table_name,
+                cell.row_idx
+            ))?
+            .clone();
+
+        // null cells take the column's default, as when rows are written
+        let value = match value {
+            None if !column.get_type().get_nullable() => {
+                column.get_default_value().cloned()
+            }
+            value => value,
+        };
+        column
+            .get_type()
+            .check_literal(value.as_ref())
+            .map_err(|e| {
+                anyhow::anyhow!("In column {}: {e}", column.get_name())
+            })?;
+
+        let mut recalc = Recalculation::default();
+        if row[col_idx] == value {
+            return Ok(recalc);
+        }
+        row[col_idx] = value;
+        recalc.changed.push(cell.clone());
+
+        let mut changed = HashSet::from([cell.get_column()]);
+        for col in self.graph.get_affected(&[cell.get_column()]) {
+            if !self
+                .graph
+                .get_dependencies(&col)
+                .iter()
+                .any(|dep| changed.contains(dep))
+            {
+                continue;
+            }
+            let idx = schema.get_column_idx(&col.column_name).ok_or(
+                anyhow::anyhow!(
+                    "Table {} has no column {}!",
+                    col.table_name,
+                    col.column_name
+                ),
+            )?;
+            let column = &schema.get_columns()[idx];
+            let formula = column.get_formula().ok_or(anyhow::anyhow!(
+                "Column {col} is no longer computed!"
+            ))?;
+            let val = formula
+                .eval(&|name| {
+                    schema
+                        .get_column_idx(name)
+                        .map(|idx| row[idx].clone())
+                        .ok_or(anyhow::anyhow!("Unknown column {name}!"))
+                })
+                .map_err(|e| {
+                    anyhow::anyhow!("In column {}: {e}", column.get_name())
+                })?
+                .map(|val| convert_result(val, &*column.get_type()));
+            column.get_type().check_literal(val.as_ref()).map_err(|e| {
+                anyhow::anyhow!("In column {}: {e}", column.get_name())
+            })?;
+
+            let recomputed = CellRef::new(
+                col.table_name.clone(),
+                cell.row_idx,
+                col.column_name.clone(),
+            );
+            recalc.recomputed.push(recomputed.clone());
+            if row[idx] != val {
+                row[idx] = val;
+                changed.insert(col);
+                recalc.changed.push(recomputed);
+            }
+        }
+
+        table.set_row(cell.row_idx, row);
+        Ok(recalc)
+    }
+}
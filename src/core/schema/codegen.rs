@@ -2,12 +2,33 @@ use std::fmt::Display;
 
 use crate::{
     core::schema::{
-        ColumnSchema, DBL_TYPE_NAME, DblDataType, INT_TYPE_NAME, IntDataType,
-        STR_TYPE_NAME, SpreadsheetSchema, StrDataType, TableSchema,
+        ColumnSchema, DBL_TYPE_NAME, DECIMAL_TYPE_NAME, DataType, DblDataType,
+        DecimalDataType, IntDataType, LengthUnit, STR_TYPE_NAME, SchemaHeader,
+        SpreadsheetSchema, StrDataType, TableSchema, ViewSchema,
+    },
+    ql::{
+        Stmt,
+        alter::AlterStmt,
+        dml::{DeleteStmt, InsertStmt, UpdateStmt},
+        expr::{Aggregate, BinaryOp, CompareOp, Cond, Expr, Param},
+        import::ImportStmt,
+        lex::{Literal, fmt_name, quote_str},
+        query::{JoinKind, SelectStmt},
     },
-    ql::Stmt,
 };
 
+// Returns the brackets around a number type's bounds: `<` and `>` if both are
+// inclusive, otherwise interval notation.
+fn bound_brackets(min_exclusive: bool, max_exclusive: bool) -> (char, char) {
+    match (min_exclusive, max_exclusive) {
+        (false, false) => ('<', '>'),
+        (min_exclusive, max_exclusive) => (
+            if min_exclusive { '(' } else { '[' },
+            if max_exclusive { ')' } else { ']' },
+        ),
+    }
+}
+
 impl Display for IntDataType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let min = match self.min {
@@ -20,10 +41,12 @@ impl Display for IntDataType {
             None => "",
         };
 
+        let (open, close) =
+            bound_brackets(self.min_exclusive, self.max_exclusive);
         write!(
             f,
-            "{}<{}, {}>{}",
-            INT_TYPE_NAME,
+            "{}{open}{}, {}{close}{}",
+            self.get_super_name(),
             min,
             max,
             if self.nullable { "?" } else { "" }
@@ -43,9 +66,11 @@ impl Display for DblDataType {
             None => "",
         };
 
+        let (open, close) =
+            bound_brackets(self.min_exclusive, self.max_exclusive);
         write!(
             f,
-            "{}<{}, {}>{}",
+            "{}{open}{}, {}{close}{}",
             DBL_TYPE_NAME,
             min,
             max,
@@ -54,18 +79,45 @@ impl Display for DblDataType {
     }
 }
 
-impl Display for StrDataType {
+impl Display for DecimalDataType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let min = match self.min {
+        let precision = match self.precision {
             Some(val) => &val.to_string(),
             None => "",
         };
 
-        let max = match self.max {
+        let scale = match self.scale {
+            Some(val) => &val.to_string(),
+            None => "",
+        };
+
+        write!(
+            f,
+            "{}<{}, {}>{}",
+            DECIMAL_TYPE_NAME,
+            precision,
+            scale,
+            if self.nullable { "?" } else { "" }
+        )
+    }
+}
+
+impl Display for StrDataType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let min = match self.min {
             Some(val) => &val.to_string(),
             None => "",
         };
 
+        // the unit follows the max, and is only shown if it isn't the default
+        let unit = self.get_unit();
+        let max = match (self.max, unit == LengthUnit::default()) {
+            (Some(val), true) => &val.to_string(),
+            (Some(val), false) => &format!("{val} {}", unit.get_name()),
+            (None, true) => "",
+            (None, false) => unit.get_name(),
+        };
+
         write!(
             f,
             "{}<{}, {}>{}",
@@ -77,15 +129,288 @@ impl Display for StrDataType {
     }
 }
 
+impl Display for BinaryOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.get_symbol())
+    }
+}
+
+// Writes an operand of an operator with precedence `min_precedence`,
+// bracketing operations that bind less tightly.
+fn fmt_operand(
+    f: &mut std::fmt::Formatter<'_>,
+    operand: &Expr,
+    min_precedence: u8,
+) -> std::fmt::Result {
+    match operand {
+        Expr::Binary(op, _, _) if op.get_precedence() < min_precedence => {
+            write!(f, "({operand})")
+        }
+        _ => write!(f, "{operand}"),
+    }
+}
+
+impl Display for Expr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            // doubles keep their point, so `2.0` isn't read back as an int
+            Expr::Literal(Literal::Dbl(val)) => write!(f, "{val:?}"),
+            Expr::Literal(lit) => write!(f, "{lit}"),
+            Expr::Column(name) => write!(f, "{name}"),
+            Expr::Neg(operand) => {
+                write!(f, "-")?;
+                fmt_operand(f, operand, u8::MAX)
+            }
+            // `a - (b - c)` isn't `a - b - c`, so a right operand at the same
+            // precedence is bracketed too
+            Expr::Binary(op, left, right) => {
+                fmt_operand(f, left, op.get_precedence())?;
+                write!(f, " {op} ")?;
+                fmt_operand(f, right, op.get_precedence() + 1)
+            }
+            Expr::Aggregate(aggregate) => write!(f, "{aggregate}"),
+            Expr::Param(param) => write!(f, "{param}"),
+        }
+    }
+}
+
+impl Display for Param {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Positional(idx) => write!(f, "${idx}"),
+            Self::Named(name) => write!(f, ":{name}"),
+        }
+    }
+}
+
+impl Display for Aggregate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}(", self.func.get_name())?;
+        if self.distinct {
+            write!(f, "distinct ")?;
+        }
+        match &self.arg {
+            Some(arg) => write!(f, "{arg})"),
+            None => write!(f, "*)"),
+        }
+    }
+}
+
+impl Display for CompareOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.get_symbol())
+    }
+}
+
+// Returns how tightly a condition binds; `and` binds tighter than `or`, and
+// comparisons tighter than both.
+fn cond_precedence(cond: &Cond) -> u8 {
+    match cond {
+        Cond::Or(_, _) => 1,
+        Cond::And(_, _) => 2,
+        _ => 3,
+    }
+}
+
+// Writes an operand of a condition with precedence `min_precedence`,
+// bracketing conditions that bind less tightly.
+fn fmt_cond_operand(
+    f: &mut std::fmt::Formatter<'_>,
+    operand: &Cond,
+    min_precedence: u8,
+) -> std::fmt::Result {
+    if cond_precedence(operand) < min_precedence {
+        write!(f, "({operand})")
+    } else {
+        write!(f, "{operand}")
+    }
+}
+
+impl Display for Cond {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Cond::Compare(op, left, right) => write!(f, "{left} {op} {right}"),
+            Cond::IsNull { expr, negated } => {
+                write!(
+                    f,
+                    "{expr} is {}null",
+                    if *negated { "not " } else { "" }
+                )
+            }
+            Cond::Not(operand) => {
+                write!(f, "not ")?;
+                fmt_cond_operand(f, operand, 3)
+            }
+            Cond::And(left, right) => {
+                fmt_cond_operand(f, left, 2)?;
+                write!(f, " and ")?;
+                fmt_cond_operand(f, right, 2)
+            }
+            Cond::Or(left, right) => {
+                fmt_cond_operand(f, left, 1)?;
+                write!(f, " or ")?;
+                fmt_cond_operand(f, right, 1)
+            }
+        }
+    }
+}
+
+impl Display for SelectStmt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "select ")?;
+        match self.get_items() {
+            Some(items) => {
+                let items: Vec<String> = items
+                    .iter()
+                    .map(|item| match item.get_alias() {
+                        Some(alias) => {
+                            format!(
+                                "{} as {}",
+                                item.get_expr(),
+                                fmt_name(&alias)
+                            )
+                        }
+                        None => item.get_expr().to_string(),
+                    })
+                    .collect();
+                write!(f, "{}", items.join(", "))?;
+            }
+            None => write!(f, "*")?,
+        }
+        write!(f, " from {}", fmt_name(&self.get_table_name()))?;
+        for join in self.get_joins() {
+            let kwd = match join.get_kind() {
+                JoinKind::Inner => "join",
+                JoinKind::Left => "left join",
+            };
+            write!(
+                f,
+                " {kwd} {} on {}",
+                fmt_name(&join.get_table_name()),
+                join.get_on()
+            )?;
+        }
+        if let Some(filter) = self.get_filter() {
+            write!(f, " where {filter}")?;
+        }
+        if !self.get_group_by().is_empty() {
+            let group_by: Vec<String> = self
+                .get_group_by()
+                .iter()
+                .map(|expr| expr.to_string())
+                .collect();
+            write!(f, " group by {}", group_by.join(", "))?;
+        }
+        if let Some(having) = self.get_having() {
+            write!(f, " having {having}")?;
+        }
+        if !self.get_order_by().is_empty() {
+            let order_by: Vec<String> = self
+                .get_order_by()
+                .iter()
+                .map(|order| match order.is_descending() {
+                    true => format!("{} desc", order.get_expr()),
+                    false => order.get_expr().to_string(),
+                })
+                .collect();
+            write!(f, " order by {}", order_by.join(", "))?;
+        }
+        if let Some(limit) = self.get_limit() {
+            write!(f, " limit {limit}")?;
+        }
+        Ok(())
+    }
+}
+
+// Formats the value given to a column by an `insert` or `update` statement.
+fn fmt_value(value: &Option<Expr>) -> String {
+    match value {
+        Some(expr) => expr.to_string(),
+        None => "null".to_string(),
+    }
+}
+
+impl Display for InsertStmt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "insert into {}", fmt_name(&self.get_table_name()))?;
+        if let Some(column_names) = self.get_column_names() {
+            let names: Vec<String> =
+                column_names.iter().map(|name| fmt_name(name)).collect();
+            write!(f, " ({})", names.join(", "))?;
+        }
+        let rows: Vec<String> = self
+            .get_rows()
+            .iter()
+            .map(|values| {
+                let values: Vec<String> =
+                    values.iter().map(fmt_value).collect();
+                format!("({})", values.join(", "))
+            })
+            .collect();
+        write!(f, " values {}", rows.join(", "))
+    }
+}
+
+impl Display for UpdateStmt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let assignments: Vec<String> = self
+            .get_assignments()
+            .iter()
+            .map(|assignment| {
+                format!(
+                    "{} = {}",
+                    fmt_name(&assignment.column_name),
+                    fmt_value(&assignment.value)
+                )
+            })
+            .collect();
+        write!(
+            f,
+            "update {} set {}",
+            fmt_name(&self.get_table_name()),
+            assignments.join(", ")
+        )?;
+        if let Some(filter) = self.get_filter() {
+            write!(f, " where {filter}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for DeleteStmt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "delete from {}", fmt_name(&self.get_table_name()))?;
+        if let Some(filter) = self.get_filter() {
+            write!(f, " where {filter}")?;
+        }
+        Ok(())
+    }
+}
+
 impl Display for ColumnSchema {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(formula) = &self.formula {
+            return write!(
+                f,
+                "{}: {} = {formula}",
+                fmt_name(&self.column_name),
+                self.column_type
+            );
+        }
         match &self.default_value {
             Some(val) => write!(
                 f,
                 "{}: {} = {}",
-                self.column_name, self.column_type, val
+                fmt_name(&self.column_name),
+                self.column_type,
+                val
+            ),
+            None => write!(
+                f,
+                "{}: {}",
+                fmt_name(&self.column_name),
+                self.column_type
             ),
-            None => write!(f, "{}: {}", self.column_name, self.column_type),
         }
     }
 }
@@ -96,26 +421,150 @@ impl Display for TableSchema {
             self.columns.iter().map(|col| col.to_string()).collect();
         let columns_str = column_strs.join(", ");
 
-        write!(f, "{} ({})", self.table_name, columns_str)
+        write!(f, "{} ({})", fmt_name(&self.table_name), columns_str)
+    }
+}
+
+impl Display for ViewSchema {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} as {}", fmt_name(&self.view_name), self.query)
+    }
+}
+
+impl Display for SchemaHeader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "schema {}", quote_str(&self.name))?;
+        if let Some(version) = &self.version {
+            write!(f, " version {}", quote_str(version))?;
+        }
+        if let Some(description) = &self.description {
+            write!(f, " description {}", quote_str(description))?;
+        }
+        Ok(())
     }
 }
 
 impl Display for SpreadsheetSchema {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(header) = &self.header {
+            writeln!(f, "{header};")?;
+        }
         for table in &self.tables {
             writeln!(f, "table {};", table)?;
         }
+        for view in &self.views {
+            writeln!(f, "view {view};")?;
+        }
         Ok(())
     }
 }
 
+impl Display for ImportStmt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "import ")?;
+        if let Some(names) = self.get_names() {
+            let names: Vec<String> =
+                names.iter().map(|name| fmt_name(name)).collect();
+            write!(f, "{{ {} }} from ", names.join(", "))?;
+        }
+        write!(f, "{}", quote_str(&self.get_path()))?;
+        if let Some(alias) = self.get_alias() {
+            write!(f, " as {}", fmt_name(&alias))?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for AlterStmt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::AddColumn { table_name, column } => write!(
+                f,
+                "alter table {} add column {column}",
+                fmt_name(table_name)
+            ),
+            Self::DropColumn {
+                table_name,
+                column_name,
+            } => write!(
+                f,
+                "alter table {} drop column {}",
+                fmt_name(table_name),
+                fmt_name(column_name)
+            ),
+            Self::RenameColumn {
+                table_name,
+                old_name,
+                new_name,
+            } => write!(
+                f,
+                "alter table {} rename column {} to {}",
+                fmt_name(table_name),
+                fmt_name(old_name),
+                fmt_name(new_name)
+            ),
+            Self::AlterColumnType {
+                table_name,
+                column_name,
+                data_type,
+            } => write!(
+                f,
+                "alter table {} alter column {} type {data_type}",
+                fmt_name(table_name),
+                fmt_name(column_name)
+            ),
+            Self::DropTable(table_name) => {
+                write!(f, "drop table {}", fmt_name(table_name))
+            }
+            Self::RenameTable { old_name, new_name } => write!(
+                f,
+                "rename table {} to {}",
+                fmt_name(old_name),
+                fmt_name(new_name)
+            ),
+        }
+    }
+}
+
 impl Display for Stmt {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
+            Self::Schema(header) => write!(f, "{header};"),
+            Self::Import(import) => write!(f, "{import};"),
             Self::TableSchema(schema) => write!(f, "table {schema};",),
+            Self::View(view) => write!(f, "view {view};"),
             Self::TypeDef(type_name, data_type) => {
-                write!(f, "type {type_name} {data_type};")
+                match data_type.get_lineage().first() {
+                    // show the alias a refined type was derived from
+                    Some(base_name) => {
+                        let s = data_type.to_string();
+                        let constraints = s
+                            .strip_prefix(data_type.get_super_name())
+                            .unwrap_or(&s);
+                        // imported types are qualified by their namespace
+                        let base_name: Vec<String> =
+                            base_name.split('.').map(fmt_name).collect();
+                        // refinements without `?` are as nullable as their
+                        // base, so mark the non-nullable ones
+                        let bang =
+                            if data_type.get_nullable() { "" } else { "!" };
+                        write!(
+                            f,
+                            "type {} {}{constraints}{bang};",
+                            fmt_name(type_name),
+                            base_name.join(".")
+                        )
+                    }
+                    None => {
+                        write!(f, "type {} {data_type};", fmt_name(type_name))
+                    }
+                }
             }
+            Self::Alter(alter) => write!(f, "{alter};"),
+            Self::Select(select) => write!(f, "{select};"),
+            Self::Insert(insert) => write!(f, "{insert};"),
+            Self::Update(update) => write!(f, "{update};"),
+            Self::Delete(delete) => write!(f, "{delete};"),
         }
     }
 }
@@ -0,0 +1,188 @@
+use std::{any::Any, num::IntErrorKind};
+
+use rust_decimal::{Decimal, Error as DecimalError};
+
+use crate::{
+    core::schema::{
+        DblDataType, DecimalDataType, IntDataType, StrDataType,
+        validate::{TypeViolation, ViolationKind},
+    },
+    ql::lex::Literal,
+};
+
+/// How forgiving coercion is of the text typed into a cell.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Strictness {
+    /// Text must be exactly a number or string, with no surrounding
+    /// whitespace, thousands separators or leading `+`.
+    Strict,
+    /// Text is trimmed, numbers may contain `,` thousands separators and a
+    /// leading `+`, and integral doubles such as `3.0` are read as integers.
+    #[default]
+    Lenient,
+}
+
+fn wrong_type(text: &str, type_desc: &str) -> TypeViolation {
+    TypeViolation::new(
+        ViolationKind::WrongType,
+        format!("Couldn't read \"{text}\" as {type_desc}"),
+    )
+}
+
+// Removes `,` thousands separators from the integer part of `text`, rejecting
+// separators that aren't between groups of three digits.
+fn strip_thousands_separators(text: &str) -> Option<String> {
+    let (sign, rest) = match text.strip_prefix(['-', '+']) {
+        Some(rest) => (&text[..1], rest),
+        None => ("", text),
+    };
+    let (int_part, frac_part) = match rest.split_once('.') {
+        Some((int_part, frac_part)) => (int_part, Some(frac_part)),
+        None => (rest, None),
+    };
+    if !int_part.contains(',') {
+        return Some(text.to_string());
+    }
+
+    let groups: Vec<&str> = int_part.split(',').collect();
+    let is_grouped = (1..=3).contains(&groups[0].len())
+        && groups[1..].iter().all(|group| group.len() == 3)
+        && groups
+            .iter()
+            .all(|group| group.chars().all(|c| c.is_ascii_digit()));
+    if !is_grouped {
+        return None;
+    }
+
+    let mut s = format!("{sign}{}", groups.concat());
+    if let Some(frac_part) = frac_part {
+        s.push('.');
+        s.push_str(frac_part);
+    }
+    Some(s)
+}
+
+// Returns the text of a number to parse, without thousands separators when
+// lenient. A leading `+` is only accepted when lenient.
+fn number_text(
+    text: &str,
+    strictness: Strictness,
+    type_desc: &str,
+) -> Result<String, TypeViolation> {
+    match strictness {
+        Strictness::Strict if text.starts_with('+') => {
+            Err(wrong_type(text, type_desc))
+        }
+        Strictness::Strict => Ok(text.to_string()),
+        Strictness::Lenient => strip_thousands_separators(text)
+            .ok_or_else(|| wrong_type(text, type_desc)),
+    }
+}
+
+fn too_large_int(text: &str) -> TypeViolation {
+    TypeViolation::new(
+        ViolationKind::TooLarge,
+        format!("{text} is too large for an integer"),
+    )
+}
+
+fn too_small_int(text: &str) -> TypeViolation {
+    TypeViolation::new(
+        ViolationKind::TooSmall,
+        format!("{text} is too small for an integer"),
+    )
+}
+
+fn parse_int(text: &str, strictness: Strictness) -> Result<i64, TypeViolation> {
+    let number = number_text(text, strictness, "an integer")?;
+    match number.parse::<i64>() {
+        Ok(val) => Ok(val),
+        Err(e) => match e.kind() {
+            IntErrorKind::PosOverflow => Err(too_large_int(text)),
+            IntErrorKind::NegOverflow => Err(too_small_int(text)),
+            // integral doubles are accepted when lenient. `i64::MAX as f64`
+            // rounds up to 2^63, which doesn't fit
+            _ if strictness == Strictness::Lenient => {
+                match parse_dbl(text, strictness) {
+                    Ok(val) if val.fract() == 0.0 => {
+                        if val >= i64::MAX as f64 {
+                            Err(too_large_int(text))
+                        } else if val < i64::MIN as f64 {
+                            Err(too_small_int(text))
+                        } else {
+                            Ok(val as i64)
+                        }
+                    }
+                    _ => Err(wrong_type(text, "an integer")),
+                }
+            }
+            _ => Err(wrong_type(text, "an integer")),
+        },
+    }
+}
+
+fn parse_dbl(text: &str, strictness: Strictness) -> Result<f64, TypeViolation> {
+    let number = number_text(text, strictness, "a double")?;
+    match number.parse::<f64>() {
+        // `parse` also accepts `inf` and `NaN`, which can't be stored
+        Ok(val) if val.is_finite() => Ok(val),
+        _ => Err(wrong_type(text, "a double")),
+    }
+}
+
+fn parse_decimal(
+    text: &str,
+    strictness: Strictness,
+) -> Result<Decimal, TypeViolation> {
+    let number = number_text(text, strictness, "a decimal")?;
+    match Decimal::from_str_exact(&number) {
+        Ok(val) => Ok(val),
+        Err(DecimalError::ExceedsMaximumPossibleValue) => {
+            Err(TypeViolation::new(
+                ViolationKind::TooLarge,
+                format!("{text} is too large for a decimal"),
+            ))
+        }
+        Err(DecimalError::LessThanMinimumPossibleValue) => {
+            Err(TypeViolation::new(
+                ViolationKind::TooSmall,
+                format!("{text} is too small for a decimal"),
+            ))
+        }
+        Err(DecimalError::Underflow) => Err(TypeViolation::new(
+            ViolationKind::TooPrecise,
+            format!("{text} has too many digits for a decimal"),
+        )),
+        Err(_) => Err(wrong_type(text, "a decimal")),
+    }
+}
+
+/// Reads `text` as a literal of the kind held by the data type `dtype`, or
+/// `None` if the text is empty.
+pub(crate) fn coerce_text(
+    dtype: &dyn Any,
+    text: &str,
+    strictness: Strictness,
+) -> Result<Option<Literal>, TypeViolation> {
+    let text = match strictness {
+        Strictness::Strict => text,
+        Strictness::Lenient => text.trim(),
+    };
+    if text.is_empty() {
+        return Ok(None);
+    }
+
+    if dtype.is::<IntDataType>() {
+        return Ok(Some(Literal::Int(parse_int(text, strictness)?)));
+    }
+    if dtype.is::<DblDataType>() {
+        return Ok(Some(Literal::Dbl(parse_dbl(text, strictness)?)));
+    }
+    if dtype.is::<DecimalDataType>() {
+        return Ok(Some(Literal::Decimal(parse_decimal(text, strictness)?)));
+    }
+    if dtype.is::<StrDataType>() {
+        return Ok(Some(Literal::Str(text.into())));
+    }
+    Err(wrong_type(text, "a value of this type"))
+}
@@ -0,0 +1,380 @@
+use std::rc::Rc;
+
+use crate::core::schema::{
+    DataType, DblDataType, DecimalDataType, IntDataType, SharedDataType,
+    StrDataType,
+};
+
+type Bounds<T> = (Option<T>, Option<T>);
+
+// Returns `true` if every value within `inner` is also within `outer`.
+fn bounds_contain<T: PartialOrd>(
+    (outer_min, outer_max): Bounds<T>,
+    (inner_min, inner_max): Bounds<T>,
+) -> bool {
+    let min_ok = match (outer_min, inner_min) {
+        (None, _) => true,
+        (Some(outer), Some(inner)) => inner >= outer,
+        (Some(_), None) => false,
+    };
+    let max_ok = match (outer_max, inner_max) {
+        (None, _) => true,
+        (Some(outer), Some(inner)) => inner <= outer,
+        (Some(_), None) => false,
+    };
+    min_ok && max_ok
+}
+
+fn intersect_bounds<T: PartialOrd>(
+    (a_min, a_max): Bounds<T>,
+    (b_min, b_max): Bounds<T>,
+) -> Bounds<T> {
+    let min = match (a_min, b_min) {
+        (Some(a), Some(b)) => Some(if a > b { a } else { b }),
+        (a, b) => a.or(b),
+    };
+    let max = match (a_max, b_max) {
+        (Some(a), Some(b)) => Some(if a < b { a } else { b }),
+        (a, b) => a.or(b),
+    };
+    (min, max)
+}
+
+// A double bound and whether it's exclusive.
+type DblBound = Option<(f64, bool)>;
+
+fn dbl_bounds(dtype: &DblDataType) -> (DblBound, DblBound) {
+    (
+        dtype.min.map(|min| (min, dtype.min_exclusive)),
+        dtype.max.map(|max| (max, dtype.max_exclusive)),
+    )
+}
+
+// Returns `true` if every double within `inner` is also within `outer`.
+fn dbl_bounds_contain(
+    (outer_min, outer_max): (DblBound, DblBound),
+    (inner_min, inner_max): (DblBound, DblBound),
+) -> bool {
+    // an inner bound equal to the outer one is only ok if the outer bound is
+    // inclusive or the inner one is exclusive
+    let min_ok = match (outer_min, inner_min) {
+        (None, _) => true,
+        (Some((outer, outer_ex)), Some((inner, inner_ex))) => {
+            inner > outer || (inner == outer && (inner_ex || !outer_ex))
+        }
+        (Some(_), None) => false,
+    };
+    let max_ok = match (outer_max, inner_max) {
+        (None, _) => true,
+        (Some((outer, outer_ex)), Some((inner, inner_ex))) => {
+            inner < outer || (inner == outer && (inner_ex || !outer_ex))
+        }
+        (Some(_), None) => false,
+    };
+    min_ok && max_ok
+}
+
+fn intersect_dbl_bounds(
+    (a_min, a_max): (DblBound, DblBound),
+    (b_min, b_max): (DblBound, DblBound),
+) -> (DblBound, DblBound) {
+    // of two equal bounds, the exclusive one is narrower
+    let narrower = |a: (f64, bool), b: (f64, bool), a_is_narrower: bool| {
+        if a.0 == b.0 {
+            (a.0, a.1 || b.1)
+        } else if a_is_narrower {
+            a
+        } else {
+            b
+        }
+    };
+    let min = match (a_min, b_min) {
+        (Some(a), Some(b)) => Some(narrower(a, b, a.0 > b.0)),
+        (a, b) => a.or(b),
+    };
+    let max = match (a_max, b_max) {
+        (Some(a), Some(b)) => Some(narrower(a, b, a.0 < b.0)),
+        (a, b) => a.or(b),
+    };
+    (min, max)
+}
+
+// Narrows double bounds to the integers that lie within them. Bounds beyond
+// the range of an `i64` are clamped to it.
+fn dbl_bounds_as_int((min, max): (DblBound, DblBound)) -> Bounds<i64> {
+    (
+        min.map(|(min, exclusive)| match exclusive {
+            true => (min.floor() as i64).saturating_add(1),
+            false => min.ceil() as i64,
+        }),
+        max.map(|(max, exclusive)| match exclusive {
+            true => (max.ceil() as i64).saturating_sub(1),
+            false => max.floor() as i64,
+        }),
+    )
+}
+
+fn int_bounds_as_dbl((min, max): Bounds<i64>) -> (DblBound, DblBound) {
+    (
+        min.map(|min| (min as f64, false)),
+        max.map(|max| (max as f64, false)),
+    )
+}
+
+// Narrows decimal bounds to the integers that lie within them.
+fn decimal_bounds_as_int(dtype: &DecimalDataType) -> Bounds<i64> {
+    let max = dtype
+        .get_max_abs()
+        .and_then(|max| i64::try_from(max.trunc()).ok());
+    (max.map(|max| -max), max)
+}
+
+fn int_range(dtype: &IntDataType) -> Bounds<i64> {
+    let (min, max) = dtype.get_range();
+    (Some(min), Some(max))
+}
+
+// Returns `true` if every value of `from` has no more digits before and after
+// the point than `to` allows.
+fn decimal_digits_contain(
+    from: &DecimalDataType,
+    to: &DecimalDataType,
+) -> bool {
+    let scale_ok = match (from.scale, to.scale) {
+        (_, None) => true,
+        (Some(from), Some(to)) => from <= to,
+        (None, Some(_)) => false,
+    };
+    let digits_ok = match (to.precision, to.scale) {
+        (None, _) => true,
+        // without a scale, only the total number of digits is limited
+        (Some(to), None) => from.precision.is_some_and(|from| from <= to),
+        (Some(_), Some(_)) => {
+            match (from.get_max_int_digits(), to.get_max_int_digits()) {
+                (Some(from), Some(to)) => from <= to,
+                _ => false,
+            }
+        }
+    };
+    scale_ok && digits_ok
+}
+
+/// Returns `true` if every value of `from`, including null, is also a value of
+/// `to`, so data can be moved from a `from` column into a `to` column without
+/// validation failures.
+pub fn is_assignable(from: &dyn DataType, to: &dyn DataType) -> bool {
+    (!from.get_nullable() || to.get_nullable()) && is_value_assignable(from, to)
+}
+
+/// Returns `true` if every non-null value of `from` is also a value of `to`.
+pub fn is_value_assignable(from: &dyn DataType, to: &dyn DataType) -> bool {
+    let (from, to) = (from.as_any(), to.as_any());
+    if let Some(from) = from.downcast_ref::<IntDataType>() {
+        if let Some(to) = to.downcast_ref::<IntDataType>() {
+            return bounds_contain(int_range(to), int_range(from));
+        }
+        if let Some(to) = to.downcast_ref::<DblDataType>() {
+            return dbl_bounds_contain(
+                dbl_bounds(to),
+                int_bounds_as_dbl(from.get_inclusive_bounds()),
+            );
+        }
+        if let Some(to) = to.downcast_ref::<DecimalDataType>() {
+            return bounds_contain(decimal_bounds_as_int(to), int_range(from));
+        }
+    }
+    if let Some(from) = from.downcast_ref::<DecimalDataType>()
+        && let Some(to) = to.downcast_ref::<DecimalDataType>()
+    {
+        return decimal_digits_contain(from, to);
+    }
+    if let Some(from) = from.downcast_ref::<DblDataType>()
+        && let Some(to) = to.downcast_ref::<DblDataType>()
+    {
+        return dbl_bounds_contain(dbl_bounds(to), dbl_bounds(from));
+    }
+    if let Some(from) = from.downcast_ref::<StrDataType>()
+        && let Some(to) = to.downcast_ref::<StrDataType>()
+    {
+        // lengths in different units can only be compared if `to` has no
+        // bounds
+        return (to.min.is_none() && to.max.is_none())
+            || (from.get_unit() == to.get_unit()
+                && bounds_contain((to.min, to.max), (from.min, from.max)));
+    }
+    false
+}
+
+/// Returns `dtype` with null allowed, keeping its bounds, such as for the
+/// columns of a table on the right of a left join.
+pub fn to_nullable(dtype: &SharedDataType) -> SharedDataType {
+    with_nullable(dtype, true)
+}
+
+/// Returns `dtype` with null allowed or not, as `nullable`, keeping its
+/// bounds.
+pub fn with_nullable(dtype: &SharedDataType, nullable: bool) -> SharedDataType {
+    if dtype.get_nullable() == nullable {
+        return dtype.clone();
+    }
+    let any = dtype.as_any();
+    if let Some(int) = any.downcast_ref::<IntDataType>() {
+        Rc::new(IntDataType {
+            nullable,
+            lineage: int.lineage.clone(),
+            ..*int
+        })
+    } else if let Some(dbl) = any.downcast_ref::<DblDataType>() {
+        Rc::new(DblDataType {
+            nullable,
+            lineage: dbl.lineage.clone(),
+            ..*dbl
+        })
+    } else if let Some(decimal) = any.downcast_ref::<DecimalDataType>() {
+        Rc::new(DecimalDataType {
+            nullable,
+            lineage: decimal.lineage.clone(),
+            ..*decimal
+        })
+    } else if let Some(str) = any.downcast_ref::<StrDataType>() {
+        Rc::new(StrDataType {
+            nullable,
+            lineage: str.lineage.clone(),
+            ..*str
+        })
+    } else {
+        dtype.clone()
+    }
+}
+
+/// Returns the narrowest type whose values are values of both `a` and `b`.
+/// An integer and a double or decimal intersect to an integer type.
+pub fn intersect(
+    a: &dyn DataType,
+    b: &dyn DataType,
+) -> anyhow::Result<SharedDataType> {
+    let nullable = a.get_nullable() && b.get_nullable();
+    let empty_type =
+        || anyhow::anyhow!("Empty type: {a} and {b} have no values in common!");
+
+    let (a_any, b_any) = (a.as_any(), b.as_any());
+    let (a_int, b_int) = (
+        a_any.downcast_ref::<IntDataType>(),
+        b_any.downcast_ref::<IntDataType>(),
+    );
+    let other_bounds = |other: &dyn std::any::Any| {
+        if let Some(dbl) = other.downcast_ref::<DblDataType>() {
+            Some(dbl_bounds_as_int(dbl_bounds(dbl)))
+        } else {
+            other
+                .downcast_ref::<DecimalDataType>()
+                .map(decimal_bounds_as_int)
+        }
+    };
+    let int_type = match (a_int, b_int) {
+        // the result has the narrower width of the two
+        (Some(a), Some(b)) => Some((
+            intersect_bounds(
+                a.get_inclusive_bounds(),
+                b.get_inclusive_bounds(),
+            ),
+            if a.width.get_bits() <= b.width.get_bits() {
+                a.width
+            } else {
+                b.width
+            },
+        )),
+        (Some(int), None) => other_bounds(b_any).map(|bounds| {
+            (
+                intersect_bounds(int.get_inclusive_bounds(), bounds),
+                int.width,
+            )
+        }),
+        (None, Some(int)) => other_bounds(a_any).map(|bounds| {
+            (
+                intersect_bounds(int.get_inclusive_bounds(), bounds),
+                int.width,
+            )
+        }),
+        (None, None) => None,
+    };
+    if let Some(((min, max), width)) = int_type {
+        let dtype = IntDataType::new(min, max, nullable).with_width(width);
+        dtype.validate_data_type().map_err(|_| empty_type())?;
+        return Ok(Rc::new(dtype));
+    }
+
+    if let Some(a) = a_any.downcast_ref::<DblDataType>()
+        && let Some(b) = b_any.downcast_ref::<DblDataType>()
+    {
+        let (min, max) = intersect_dbl_bounds(dbl_bounds(a), dbl_bounds(b));
+        let dtype = DblDataType::new(
+            min.map(|(min, _)| min),
+            max.map(|(max, _)| max),
+            nullable,
+        )
+        .with_exclusive_bounds(
+            min.is_some_and(|(_, exclusive)| exclusive),
+            max.is_some_and(|(_, exclusive)| exclusive),
+        );
+        dtype.validate_data_type().map_err(|_| empty_type())?;
+        return Ok(Rc::new(dtype));
+    }
+    if let Some(a) = a_any.downcast_ref::<DecimalDataType>()
+        && let Some(b) = b_any.downcast_ref::<DecimalDataType>()
+    {
+        // precision and scale are upper limits, so the digits allowed on
+        // each side of the point are the fewer of the two types'
+        let (_, scale) = intersect_bounds((None, a.scale), (None, b.scale));
+        let (_, int_digits) = intersect_bounds(
+            (None, a.get_max_int_digits()),
+            (None, b.get_max_int_digits()),
+        );
+        let precision = match (int_digits, scale) {
+            (Some(int_digits), Some(scale)) => Some(int_digits + scale),
+            _ => intersect_bounds((None, a.precision), (None, b.precision)).1,
+        };
+        let dtype = DecimalDataType::new(precision, scale, nullable);
+        dtype.validate_data_type().map_err(|_| empty_type())?;
+        return Ok(Rc::new(dtype));
+    }
+    if let Some(a) = a_any.downcast_ref::<StrDataType>()
+        && let Some(b) = b_any.downcast_ref::<StrDataType>()
+    {
+        let is_bounded =
+            |dtype: &StrDataType| dtype.min.is_some() || dtype.max.is_some();
+        let unit = match (is_bounded(a), is_bounded(b)) {
+            (true, true) if a.get_unit() != b.get_unit() => {
+                return Err(anyhow::anyhow!(
+                    "Can't intersect {a} and {b}, as their lengths are \
+                    measured in different units!"
+                ));
+            }
+            (false, true) => b.get_unit(),
+            _ => a.get_unit(),
+        };
+        let (min, max) = intersect_bounds((a.min, a.max), (b.min, b.max));
+        let dtype = StrDataType::new(min, max, nullable).with_unit(unit);
+        dtype.validate_data_type().map_err(|_| empty_type())?;
+        return Ok(Rc::new(dtype));
+    }
+
+    Err(empty_type())
+}
+
+impl dyn DataType {
+    /// Returns `true` if every value of `self`, including null, is also a
+    /// value of `other`.
+    pub fn is_assignable_to(&self, other: &dyn DataType) -> bool {
+        is_assignable(self, other)
+    }
+
+    /// Returns the narrowest type whose values are values of both `self` and
+    /// `other`, or an error if they have no values in common.
+    pub fn intersect(
+        &self,
+        other: &dyn DataType,
+    ) -> anyhow::Result<SharedDataType> {
+        intersect(self, other)
+    }
+}
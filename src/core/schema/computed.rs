@@ -0,0 +1,251 @@
+use std::{collections::HashMap, hash::Hash, rc::Rc};
+
+use crate::{
+    core::{
+        data::Row,
+        schema::{
+            ColumnSchema, DataType, DblDataType, DecimalDataType, TableSchema,
+            validate::{RowViolation, ViolationKind},
+        },
+    },
+    ql::lex::Literal,
+};
+
+#[derive(Clone, Copy, PartialEq)]
+enum Visit {
+    InProgress,
+    Done,
+}
+
+// Visits `node` and the nodes it depends on, appending each computed node to
+// `order` after its dependencies. `path` holds the nodes being visited, so a
+// cycle can be reported.
+fn visit<T: Clone + Eq + Hash>(
+    node: &T,
+    get_deps: &dyn Fn(&T) -> Option<Vec<T>>,
+    get_name: &dyn Fn(&T) -> String,
+    visits: &mut HashMap<T, Visit>,
+    path: &mut Vec<T>,
+    order: &mut Vec<T>,
+) -> anyhow::Result<()> {
+    match visits.get(node) {
+        Some(Visit::Done) => return Ok(()),
+        Some(Visit::InProgress) => {
+            let start = path.iter().position(|n| n == node).unwrap();
+            let names: Vec<String> =
+                path[start..].iter().chain([node]).map(get_name).collect();
+            return Err(anyhow::anyhow!(
+                "Computed columns refer to each other in a cycle: {}!",
+                names.join(" -> ")
+            ));
+        }
+        None => {}
+    }
+    // nodes that aren't computed don't depend on anything
+    let Some(deps) = get_deps(node) else {
+        visits.insert(node.clone(), Visit::Done);
+        return Ok(());
+    };
+
+    visits.insert(node.clone(), Visit::InProgress);
+    path.push(node.clone());
+    for dep in &deps {
+        visit(dep, get_deps, get_name, visits, path, order)?;
+    }
+    path.pop();
+    visits.insert(node.clone(), Visit::Done);
+    order.push(node.clone());
+    Ok(())
+}
+
+/// Orders the computed columns among `nodes`, those for which `get_deps`
+/// returns the columns their formula refers to, so each comes after the
+/// computed columns it depends on. Returns an error naming the columns
+/// involved, as given by `get_name`, if formulas refer to each other in a
+/// cycle.
+pub(crate) fn order_computed<T: Clone + Eq + Hash>(
+    nodes: impl IntoIterator<Item = T>,
+    get_deps: &dyn Fn(&T) -> Option<Vec<T>>,
+    get_name: &dyn Fn(&T) -> String,
+) -> anyhow::Result<Vec<T>> {
+    let mut visits = HashMap::new();
+    let mut order = vec![];
+    for node in nodes {
+        visit(
+            &node,
+            get_deps,
+            get_name,
+            &mut visits,
+            &mut vec![],
+            &mut order,
+        )?;
+    }
+    Ok(order)
+}
+
+// Converts the result of a formula to the kind of number its column holds, as
+// integer results can be stored in any number column.
+pub(crate) fn convert_result(lit: Literal, dtype: &dyn DataType) -> Literal {
+    let any = dtype.as_any();
+    match lit {
+        Literal::Int(val) if any.is::<DblDataType>() => {
+            Literal::Dbl(val as f64)
+        }
+        Literal::Int(_) if any.is::<DecimalDataType>() => {
+            lit.get_decimal().map_or(lit, Literal::Decimal)
+        }
+        lit => lit,
+    }
+}
+
+/// Changes the references to column `old_name` in the formulas of `columns`
+/// to `new_name`, for when the column is renamed.
+pub fn rename_formula_refs(
+    columns: &mut [ColumnSchema],
+    old_name: &str,
+    new_name: &Rc<str>,
+) {
+    for col in columns {
+        col.formula = col
+            .formula
+            .as_ref()
+            .map(|formula| formula.rename_column(old_name, new_name));
+    }
+}
+
+impl TableSchema {
+    /// Returns the indices of the computed columns, each after the computed
+    /// columns its formula refers to, or an error if formulas refer to each
+    /// other in a cycle.
+    pub fn get_computed_order(&self) -> anyhow::Result<Vec<usize>> {
+        order_computed(
+            0..self.columns.len(),
+            &|idx| {
+                let formula = self.columns[*idx].get_formula()?;
+                Some(
+                    formula
+                        .get_column_refs()
+                        .iter()
+                        .filter_map(|name| self.get_column_idx(name))
+                        .collect(),
+                )
+            },
+            &|idx| self.columns[*idx].get_name().to_string(),
+        )
+    }
+
+    /// Checks the formulas of the table's computed columns: they may only
+    /// refer to columns of the table, can't use aggregates or depend on each
+    /// other in a cycle, and must produce values their column can store.
+    pub fn check_computed_columns(&self) -> anyhow::Result<()> {
+        let computed = self
+            .columns
+            .iter()
+            .filter_map(|col| col.get_formula().map(|formula| (col, formula)));
+
+        for (col, formula) in computed.clone() {
+            if !formula.get_aggregates().is_empty() {
+                return Err(anyhow::anyhow!(
+                    "Computed column {} can't use aggregate functions!",
+                    col.get_name()
+                ));
+            }
+            // lookups across tables aren't supported, so formulas only see
+            // the other cells of their row
+            if let Some(name) = formula
+                .get_column_refs()
+                .into_iter()
+                .find(|name| name.contains('.'))
+            {
+                return Err(anyhow::anyhow!(
+                    "Computed column {} can't refer to {name}, as formulas can \
+                    only use columns of their own table!",
+                    col.get_name()
+                ));
+            }
+            if let Some(name) = formula
+                .get_column_refs()
+                .into_iter()
+                .find(|name| self.get_column_by_name(name).is_none())
+            {
+                return Err(anyhow::anyhow!(
+                    "Computed column {} refers to unknown column {name}!",
+                    col.get_name()
+                ));
+            }
+        }
+
+        self.get_computed_order()?;
+
+        for (col, formula) in computed {
+            let column_type = col.get_type();
+            let formula_type = formula
+                .infer_type(&|name| {
+                    self.get_column_by_name(name).map(|col| col.get_type())
+                })
+                .map_err(|e| {
+                    anyhow::anyhow!(
+                        "In computed column {}: {e}",
+                        col.get_name()
+                    )
+                })?;
+            if !formula_type.is_storable_in(&*column_type) {
+                return Err(anyhow::anyhow!(
+                    "Computed column {} is {formula_type}, which can't be \
+                    stored as {column_type}!",
+                    col.get_name()
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns `row` completed to one cell per column: missing trailing cells
+    /// are null, null cells in columns that aren't nullable take the column's
+    /// default, and computed columns hold the result of their formula,
+    /// whatever the row held for them. Formulas that can't be evaluated,
+    /// such as a division by zero, leave their cell null and are described in
+    /// the returned violations.
+    pub fn complete_row(
+        &self,
+        row: &[Option<Literal>],
+    ) -> (Row, Vec<RowViolation>) {
+        let mut completed: Row = vec![None; self.columns.len()];
+        for (idx, col) in self.columns.iter().enumerate() {
+            if col.is_computed() {
+                continue;
+            }
+            completed[idx] = match row.get(idx).and_then(|cell| cell.as_ref()) {
+                None if !col.get_type().get_nullable() => {
+                    col.get_default_value().cloned()
+                }
+                cell => cell.cloned(),
+            };
+        }
+
+        // tables whose formulas form a cycle fail validation, so their
+        // computed columns are left null
+        let mut violations = vec![];
+        for idx in self.get_computed_order().unwrap_or_default() {
+            let col = &self.columns[idx];
+            let result = col.get_formula().unwrap().eval(&|name| {
+                self.get_column_idx(name)
+                    .map(|idx| completed[idx].clone())
+                    .ok_or(anyhow::anyhow!("Unknown column {name}!"))
+            });
+            match result {
+                Ok(val) => {
+                    completed[idx] =
+                        val.map(|val| convert_result(val, &*col.get_type()))
+                }
+                Err(e) => violations.push(RowViolation::new(
+                    idx,
+                    Some(col.get_name()),
+                    ViolationKind::ComputeError,
+                    e.to_string(),
+                )),
+            }
+        }
+        (completed, violations)
+    }
+}
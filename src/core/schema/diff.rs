@@ -0,0 +1,442 @@
+use std::{fmt::Display, rc::Rc};
+
+use serde_json::{Value, json};
+
+use crate::{
+    core::schema::{
+        ColumnSchema, SharedDataType, SpreadsheetSchema, TableSchema,
+        compat::is_value_assignable,
+    },
+    json::ToJson,
+    ql::{expr::Expr, lex::Literal},
+};
+
+/// How a column's type changed, ignoring nullability.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TypeChangeKind {
+    /// Every value of the old type is a value of the new type.
+    Widening,
+    /// Every value of the new type is a value of the old type.
+    Narrowing,
+    /// Neither type contains the other.
+    Incompatible,
+}
+
+impl TypeChangeKind {
+    fn classify(old: &SharedDataType, new: &SharedDataType) -> Self {
+        if is_value_assignable(&**old, &**new) {
+            Self::Widening
+        } else if is_value_assignable(&**new, &**old) {
+            Self::Narrowing
+        } else {
+            Self::Incompatible
+        }
+    }
+
+    fn get_name(&self) -> &'static str {
+        match self {
+            Self::Widening => "widening",
+            Self::Narrowing => "narrowing",
+            Self::Incompatible => "incompatible",
+        }
+    }
+}
+
+/// A change to a single column of a table present in both schemas.
+#[derive(Clone)]
+pub enum ColumnChange {
+    Added(Rc<str>, SharedDataType),
+    Removed(Rc<str>),
+    TypeChanged {
+        column_name: Rc<str>,
+        old_type: SharedDataType,
+        new_type: SharedDataType,
+        kind: TypeChangeKind,
+    },
+    NullabilityChanged {
+        column_name: Rc<str>,
+        nullable: bool,
+    },
+    DefaultChanged {
+        column_name: Rc<str>,
+        old_default: Option<Literal>,
+        new_default: Option<Literal>,
+    },
+    /// The formula of a computed column changed, or the column became or
+    /// stopped being computed.
+    FormulaChanged {
+        column_name: Rc<str>,
+        old_formula: Option<Expr>,
+        new_formula: Option<Expr>,
+    },
+    /// The columns kept by the table are in a different order. Holds the
+    /// names of the new table's columns, in order.
+    Reordered(Vec<Rc<str>>),
+}
+
+/// A change to a table between two schemas.
+#[derive(Clone)]
+pub enum TableChange {
+    Added(Rc<str>),
+    Removed(Rc<str>),
+    Renamed {
+        old_name: Rc<str>,
+        new_name: Rc<str>,
+    },
+    Altered(Rc<str>, Vec<ColumnChange>),
+}
+
+/// The changes that turn one `SpreadsheetSchema` into another.
+#[derive(Clone, Default)]
+pub struct SchemaDiff {
+    changes: Vec<TableChange>,
+}
+
+impl SchemaDiff {
+    pub fn get_changes(&self) -> &[TableChange] {
+        &self.changes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+// Returns `true` if both types allow the same non-null values and were
+// derived the same way, ignoring nullability.
+fn is_same_value_type(old: &SharedDataType, new: &SharedDataType) -> bool {
+    is_value_assignable(&**old, &**new)
+        && is_value_assignable(&**new, &**old)
+        && old.get_super_name() == new.get_super_name()
+        && old.get_lineage() == new.get_lineage()
+}
+
+fn diff_columns(old: &ColumnSchema, new: &ColumnSchema) -> Vec<ColumnChange> {
+    let column_name = new.get_name();
+    let (old_type, new_type) = (old.get_type(), new.get_type());
+    let mut changes = vec![];
+
+    if !is_same_value_type(&old_type, &new_type) {
+        changes.push(ColumnChange::TypeChanged {
+            column_name: column_name.clone(),
+            kind: TypeChangeKind::classify(&old_type, &new_type),
+            old_type: old_type.clone(),
+            new_type: new_type.clone(),
+        });
+    }
+    if old_type.get_nullable() != new_type.get_nullable() {
+        changes.push(ColumnChange::NullabilityChanged {
+            column_name: column_name.clone(),
+            nullable: new_type.get_nullable(),
+        });
+    }
+    if old.get_default_value() != new.get_default_value() {
+        changes.push(ColumnChange::DefaultChanged {
+            column_name: column_name.clone(),
+            old_default: old.get_default_value().cloned(),
+            new_default: new.get_default_value().cloned(),
+        });
+    }
+    if old.get_formula() != new.get_formula() {
+        changes.push(ColumnChange::FormulaChanged {
+            column_name,
+            old_formula: old.get_formula().cloned(),
+            new_formula: new.get_formula().cloned(),
+        });
+    }
+    changes
+}
+
+// Returns the names of the columns of `table` that `other` also has, in the
+// order of `table`.
+fn shared_column_names(
+    table: &TableSchema,
+    other: &TableSchema,
+) -> Vec<Rc<str>> {
+    table
+        .get_columns()
+        .iter()
+        .map(|col| col.get_name())
+        .filter(|name| other.get_column_by_name(name).is_some())
+        .collect()
+}
+
+fn diff_tables(old: &TableSchema, new: &TableSchema) -> Vec<ColumnChange> {
+    let mut changes = vec![];
+    if shared_column_names(old, new) != shared_column_names(new, old) {
+        changes.push(ColumnChange::Reordered(
+            new.get_columns().iter().map(|col| col.get_name()).collect(),
+        ));
+    }
+    for old_col in old.get_columns() {
+        match new.get_column_by_name(&old_col.get_name()) {
+            Some(new_col) => changes.extend(diff_columns(old_col, new_col)),
+            None => changes.push(ColumnChange::Removed(old_col.get_name())),
+        }
+    }
+    for new_col in new.get_columns() {
+        if old.get_column_by_name(&new_col.get_name()).is_none() {
+            changes.push(ColumnChange::Added(
+                new_col.get_name(),
+                new_col.get_type(),
+            ));
+        }
+    }
+    changes
+}
+
+/// Compares two schemas. A removed table and an added table with identical
+/// columns are reported as a rename, followed by the reordering of the
+/// renamed table's columns if their order differs.
+pub fn diff_schemas(
+    old: &SpreadsheetSchema,
+    new: &SpreadsheetSchema,
+) -> SchemaDiff {
+    let mut changes = vec![];
+    let mut added: Vec<&Rc<TableSchema>> = new
+        .get_tables()
+        .iter()
+        .filter(|table| old.get_table_by_name(&table.get_name()).is_none())
+        .collect();
+
+    for old_table in old.get_tables() {
+        let name = old_table.get_name();
+        if let Some(new_table) = new.get_table_by_name(&name) {
+            let column_changes = diff_tables(old_table, new_table);
+            if !column_changes.is_empty() {
+                changes.push(TableChange::Altered(name, column_changes));
+            }
+            continue;
+        }
+
+        let rename_idx = added.iter().position(|table| {
+            diff_tables(old_table, table)
+                .iter()
+                .all(|change| matches!(change, ColumnChange::Reordered(_)))
+        });
+        match rename_idx {
+            Some(idx) => {
+                let new_table = added.remove(idx);
+                let new_name = new_table.get_name();
+                changes.push(TableChange::Renamed {
+                    old_name: name,
+                    new_name: new_name.clone(),
+                });
+                let column_changes = diff_tables(old_table, new_table);
+                if !column_changes.is_empty() {
+                    changes
+                        .push(TableChange::Altered(new_name, column_changes));
+                }
+            }
+            None => changes.push(TableChange::Removed(name)),
+        }
+    }
+
+    for table in added {
+        changes.push(TableChange::Added(table.get_name()));
+    }
+
+    SchemaDiff { changes }
+}
+
+fn fmt_default(lit: &Option<Literal>) -> String {
+    match lit {
+        Some(lit) => lit.to_string(),
+        None => "none".to_string(),
+    }
+}
+
+fn fmt_formula(formula: &Option<Expr>) -> String {
+    match formula {
+        Some(formula) => formula.to_string(),
+        None => "none".to_string(),
+    }
+}
+
+impl Display for ColumnChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Added(name, dtype) => write!(f, "+ column {name}: {dtype}"),
+            Self::Removed(name) => write!(f, "- column {name}"),
+            Self::TypeChanged {
+                column_name,
+                old_type,
+                new_type,
+                kind,
+            } => write!(
+                f,
+                "~ column {column_name}: {old_type} -> {new_type} ({})",
+                kind.get_name()
+            ),
+            Self::NullabilityChanged {
+                column_name,
+                nullable,
+            } => write!(
+                f,
+                "~ column {column_name}: {}",
+                if *nullable {
+                    "now nullable"
+                } else {
+                    "no longer nullable"
+                }
+            ),
+            Self::DefaultChanged {
+                column_name,
+                old_default,
+                new_default,
+            } => write!(
+                f,
+                "~ column {column_name}: default {} -> {}",
+                fmt_default(old_default),
+                fmt_default(new_default)
+            ),
+            Self::FormulaChanged {
+                column_name,
+                old_formula,
+                new_formula,
+            } => write!(
+                f,
+                "~ column {column_name}: formula {} -> {}",
+                fmt_formula(old_formula),
+                fmt_formula(new_formula)
+            ),
+            Self::Reordered(column_names) => {
+                write!(f, "~ columns reordered: {}", column_names.join(", "))
+            }
+        }
+    }
+}
+
+impl Display for TableChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Added(name) => write!(f, "+ table {name}"),
+            Self::Removed(name) => write!(f, "- table {name}"),
+            Self::Renamed { old_name, new_name } => {
+                write!(f, "~ table {old_name} renamed to {new_name}")
+            }
+            Self::Altered(name, changes) => {
+                write!(f, "~ table {name}")?;
+                for change in changes {
+                    write!(f, "\n    {change}")?;
+                }
+                std::fmt::Result::Ok(())
+            }
+        }
+    }
+}
+
+impl Display for SchemaDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.changes.is_empty() {
+            return write!(f, "No changes");
+        }
+        let lines: Vec<String> = self
+            .changes
+            .iter()
+            .map(|change| change.to_string())
+            .collect();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+impl ToJson for ColumnChange {
+    fn to_json(&self) -> Value {
+        match self {
+            Self::Added(name, dtype) => json!({
+                "change": "add_column",
+                "column_name": &**name,
+                "column_type": dtype.to_json()
+            }),
+            Self::Removed(name) => json!({
+                "change": "remove_column",
+                "column_name": &**name
+            }),
+            Self::TypeChanged {
+                column_name,
+                old_type,
+                new_type,
+                kind,
+            } => json!({
+                "change": "change_type",
+                "column_name": &**column_name,
+                "old_type": old_type.to_json(),
+                "new_type": new_type.to_json(),
+                "kind": kind.get_name()
+            }),
+            Self::NullabilityChanged {
+                column_name,
+                nullable,
+            } => json!({
+                "change": "change_nullability",
+                "column_name": &**column_name,
+                "nullable": nullable
+            }),
+            Self::DefaultChanged {
+                column_name,
+                old_default,
+                new_default,
+            } => json!({
+                "change": "change_default",
+                "column_name": &**column_name,
+                "old_default": old_default.as_ref().map(|lit| lit.to_json()),
+                "new_default": new_default.as_ref().map(|lit| lit.to_json())
+            }),
+            Self::FormulaChanged {
+                column_name,
+                old_formula,
+                new_formula,
+            } => json!({
+                "change": "change_formula",
+                "column_name": &**column_name,
+                "old_formula": old_formula.as_ref().map(|expr| expr.to_string()),
+                "new_formula": new_formula.as_ref().map(|expr| expr.to_string())
+            }),
+            Self::Reordered(column_names) => json!({
+                "change": "reorder_columns",
+                "column_names": column_names
+                    .iter()
+                    .map(|name| &**name)
+                    .collect::<Vec<&str>>()
+            }),
+        }
+    }
+}
+
+impl ToJson for TableChange {
+    fn to_json(&self) -> Value {
+        match self {
+            Self::Added(name) => {
+                json!({"change": "add_table", "table_name": &**name})
+            }
+            Self::Removed(name) => {
+                json!({"change": "remove_table", "table_name": &**name})
+            }
+            Self::Renamed { old_name, new_name } => json!({
+                "change": "rename_table",
+                "old_name": &**old_name,
+                "new_name": &**new_name
+            }),
+            Self::Altered(name, changes) => json!({
+                "change": "alter_table",
+                "table_name": &**name,
+                "columns": changes
+                    .iter()
+                    .map(|change| change.to_json())
+                    .collect::<Vec<Value>>()
+            }),
+        }
+    }
+}
+
+impl ToJson for SchemaDiff {
+    fn to_json(&self) -> Value {
+        json!({
+            "changes": self
+                .changes
+                .iter()
+                .map(|change| change.to_json())
+                .collect::<Vec<Value>>()
+        })
+    }
+}
@@ -0,0 +1,427 @@
+use crate::{
+    core::schema::{
+        ColumnSchema, DataType, DblDataType, DecimalDataType, IntDataType,
+        IntWidth, LengthUnit, SpreadsheetSchema, StrDataType, TableSchema,
+    },
+    json::ToJson,
+    ql::{
+        expr::{BinaryOp, Expr, ValueKind},
+        lex::Literal,
+    },
+};
+use serde::Serialize;
+use serde_json::{Map, Value, json};
+use std::fmt::Display;
+
+/// Target formats supported by the schema exporters.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExportFormat {
+    Sql,
+    JsonSchema,
+    Ts,
+}
+
+impl TryFrom<&str> for ExportFormat {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "sql" => Ok(Self::Sql),
+            "jsonschema" => Ok(Self::JsonSchema),
+            "ts" => Ok(Self::Ts),
+            _ => Err(anyhow::anyhow!(
+                "Unknown export format {value} (expected sql, jsonschema or ts)"
+            )),
+        }
+    }
+}
+
+/// Exports `schema` in the given format.
+pub fn export_schema(
+    schema: &SpreadsheetSchema,
+    format: ExportFormat,
+) -> String {
+    match format {
+        ExportFormat::Sql => to_sql(schema),
+        ExportFormat::JsonSchema => {
+            serde_json::to_string_pretty(&to_json_schema(schema)).unwrap()
+        }
+        ExportFormat::Ts => to_ts(schema),
+    }
+}
+
+fn quote_sql_ident(ident: &str) -> String {
+    format!("\"{}\"", ident.replace('"', "\"\""))
+}
+
+fn sql_literal(lit: &Literal) -> String {
+    match lit {
+        Literal::Str(val) => format!("'{}'", val.replace('\'', "''")),
+        _ => lit.to_string(),
+    }
+}
+
+// Writes `expr` as an SQL expression over the columns of `table`. SQL joins
+// strings with `||` and divides integers without a remainder, so those
+// operations are translated.
+fn sql_expr(expr: &Expr, table: &TableSchema) -> String {
+    let kind = |expr: &Expr| {
+        expr.infer_type(&|name| {
+            table.get_column_by_name(name).map(|col| col.get_type())
+        })
+        .ok()
+        .map(|expr_type| expr_type.kind)
+    };
+    let operand = |expr: &Expr| match expr {
+        Expr::Binary(..) => format!("({})", sql_expr(expr, table)),
+        _ => sql_expr(expr, table),
+    };
+    match expr {
+        Expr::Literal(lit) => sql_literal(lit),
+        Expr::Column(name) => quote_sql_ident(name),
+        Expr::Neg(inner) => format!("-{}", operand(inner)),
+        Expr::Binary(op, left, right) => {
+            let (left_kind, right_kind) = (kind(left), kind(right));
+            let symbol = match op {
+                BinaryOp::Add if left_kind == Some(ValueKind::Str) => "||",
+                _ => op.get_symbol(),
+            };
+            let left = match op {
+                BinaryOp::Div
+                    if left_kind == Some(ValueKind::Int)
+                        && right_kind == Some(ValueKind::Int) =>
+                {
+                    format!("CAST({} AS DOUBLE PRECISION)", operand(left))
+                }
+                _ => operand(left),
+            };
+            format!("{left} {symbol} {}", operand(right))
+        }
+        Expr::Aggregate(aggregate) => {
+            let arg = match &aggregate.arg {
+                Some(arg) => sql_expr(arg, table),
+                None => "*".to_string(),
+            };
+            let distinct = if aggregate.distinct { "DISTINCT " } else { "" };
+            format!(
+                "{}({distinct}{arg})",
+                aggregate.func.get_name().to_uppercase()
+            )
+        }
+        Expr::Param(param) => param.to_string(),
+    }
+}
+
+fn sql_bound_checks<T: Display>(
+    name: &str,
+    (min, min_exclusive): (Option<T>, bool),
+    (max, max_exclusive): (Option<T>, bool),
+) -> Vec<String> {
+    let mut checks = vec![];
+    if let Some(min) = min {
+        let op = if min_exclusive { ">" } else { ">=" };
+        checks.push(format!("{name} {op} {min}"));
+    }
+    if let Some(max) = max {
+        let op = if max_exclusive { "<" } else { "<=" };
+        checks.push(format!("{name} {op} {max}"));
+    }
+    checks
+}
+
+fn sql_column(column: &ColumnSchema, table: &TableSchema) -> String {
+    let name = quote_sql_ident(&column.get_name());
+    let column_type = column.get_type();
+    let any = column_type.as_any();
+    let mut checks = vec![];
+
+    let sql_type = if let Some(dtype) = any.downcast_ref::<IntDataType>() {
+        checks.extend(sql_bound_checks(
+            &name,
+            (dtype.get_min(), dtype.is_min_exclusive()),
+            (dtype.get_max(), dtype.is_max_exclusive()),
+        ));
+        match dtype.get_width() {
+            IntWidth::Bits32 => "INTEGER".to_string(),
+            IntWidth::Bits64 => "BIGINT".to_string(),
+        }
+    } else if let Some(dtype) = any.downcast_ref::<DecimalDataType>() {
+        match (dtype.get_precision(), dtype.get_scale()) {
+            (Some(precision), Some(scale)) => {
+                format!("DECIMAL({precision}, {scale})")
+            }
+            (Some(precision), None) => format!("DECIMAL({precision})"),
+            (None, _) => "DECIMAL".to_string(),
+        }
+    } else if let Some(dtype) = any.downcast_ref::<DblDataType>() {
+        checks.extend(sql_bound_checks(
+            &name,
+            (dtype.get_min(), dtype.is_min_exclusive()),
+            (dtype.get_max(), dtype.is_max_exclusive()),
+        ));
+        "DOUBLE PRECISION".to_string()
+    } else if let Some(dtype) = any.downcast_ref::<StrDataType>() {
+        // SQL has no grapheme length function, so those bounds are dropped
+        let length_fn = match dtype.get_unit() {
+            LengthUnit::Bytes => Some("OCTET_LENGTH"),
+            LengthUnit::Chars => Some("LENGTH"),
+            LengthUnit::Graphemes => None,
+        };
+        if let Some(length_fn) = length_fn {
+            if let Some(min) = dtype.get_min() {
+                checks.push(format!("{length_fn}({name}) >= {min}"));
+            }
+            if let Some(max) = dtype.get_max() {
+                checks.push(format!("{length_fn}({name}) <= {max}"));
+            }
+        }
+        "TEXT".to_string()
+    } else {
+        "TEXT".to_string()
+    };
+
+    let mut sql = format!("{name} {sql_type}");
+    if !column_type.get_nullable() {
+        sql.push_str(" NOT NULL");
+    }
+    if let Some(default_value) = column.get_default_value() {
+        sql.push_str(&format!(" DEFAULT {}", sql_literal(default_value)));
+    }
+    if let Some(formula) = column.get_formula() {
+        sql.push_str(&format!(
+            " GENERATED ALWAYS AS ({}) STORED",
+            sql_expr(formula, table)
+        ));
+    }
+    if !checks.is_empty() {
+        sql.push_str(&format!(" CHECK ({})", checks.join(" AND ")));
+    }
+    sql
+}
+
+fn sql_table(table: &TableSchema) -> String {
+    let columns: Vec<String> = table
+        .get_columns()
+        .iter()
+        .map(|column| format!("    {}", sql_column(column, table)))
+        .collect();
+
+    format!(
+        "CREATE TABLE {} (\n{}\n);",
+        quote_sql_ident(&table.get_name()),
+        columns.join(",\n")
+    )
+}
+
+/// Exports `schema` as a series of SQL `CREATE TABLE` statements.
+pub fn to_sql(schema: &SpreadsheetSchema) -> String {
+    let tables: Vec<String> = schema
+        .get_tables()
+        .iter()
+        .map(|table| sql_table(table))
+        .collect();
+    tables.join("\n\n")
+}
+
+fn insert_json_schema_bounds<T: Serialize>(
+    out: &mut Map<String, Value>,
+    (min, min_exclusive): (Option<T>, bool),
+    (max, max_exclusive): (Option<T>, bool),
+) {
+    if let Some(min) = min {
+        let key = if min_exclusive {
+            "exclusiveMinimum"
+        } else {
+            "minimum"
+        };
+        out.insert(key.into(), json!(min));
+    }
+    if let Some(max) = max {
+        let key = if max_exclusive {
+            "exclusiveMaximum"
+        } else {
+            "maximum"
+        };
+        out.insert(key.into(), json!(max));
+    }
+}
+
+fn json_schema_type(dtype: &dyn DataType) -> Value {
+    let any = dtype.as_any();
+    let mut out = Map::new();
+
+    let type_name = if let Some(dtype) = any.downcast_ref::<IntDataType>() {
+        insert_json_schema_bounds(
+            &mut out,
+            (dtype.get_min(), dtype.is_min_exclusive()),
+            (dtype.get_max(), dtype.is_max_exclusive()),
+        );
+        "integer"
+    } else if let Some(dtype) = any.downcast_ref::<DecimalDataType>() {
+        // decimals are written to JSON as strings to keep their precision
+        let frac_digits = match dtype.get_scale() {
+            Some(0) => String::new(),
+            Some(scale) => format!("(\\.[0-9]{{1,{scale}}})?"),
+            None => "(\\.[0-9]+)?".to_string(),
+        };
+        let int_digits = match dtype.get_max_int_digits() {
+            Some(digits) => format!("{{1,{}}}", digits.max(1)),
+            None => "+".to_string(),
+        };
+        out.insert(
+            "pattern".into(),
+            json!(format!("^-?[0-9]{int_digits}{frac_digits}$")),
+        );
+        "string"
+    } else if let Some(dtype) = any.downcast_ref::<DblDataType>() {
+        insert_json_schema_bounds(
+            &mut out,
+            (dtype.get_min(), dtype.is_min_exclusive()),
+            (dtype.get_max(), dtype.is_max_exclusive()),
+        );
+        "number"
+    } else if let Some(dtype) = any.downcast_ref::<StrDataType>() {
+        // JSON Schema lengths are measured in chars
+        if dtype.get_unit() == LengthUnit::Chars {
+            if let Some(min) = dtype.get_min() {
+                out.insert("minLength".into(), json!(min));
+            }
+            if let Some(max) = dtype.get_max() {
+                out.insert("maxLength".into(), json!(max));
+            }
+        }
+        "string"
+    } else {
+        "string"
+    };
+
+    if dtype.get_nullable() {
+        out.insert("type".into(), json!([type_name, "null"]));
+    } else {
+        out.insert("type".into(), json!(type_name));
+    }
+    Value::Object(out)
+}
+
+fn json_schema_table(table: &TableSchema) -> Value {
+    let mut properties = Map::new();
+    let mut required = vec![];
+
+    for column in table.get_columns() {
+        let mut property = json_schema_type(column.get_type().as_ref());
+        if let Some(default_value) = column.get_default_value()
+            && let Value::Object(map) = &mut property
+        {
+            map.insert("default".into(), default_value.to_json());
+        }
+        if column.is_computed()
+            && let Value::Object(map) = &mut property
+        {
+            map.insert("readOnly".into(), json!(true));
+        }
+        if !column.get_type().get_nullable() {
+            required.push(Value::String(column.get_name().to_string()));
+        }
+        properties.insert(column.get_name().to_string(), property);
+    }
+
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+        "additionalProperties": false
+    })
+}
+
+/// Exports `schema` as a JSON Schema document with one definition per table.
+pub fn to_json_schema(schema: &SpreadsheetSchema) -> Value {
+    let mut defs = Map::new();
+    for table in schema.get_tables() {
+        defs.insert(table.get_name().to_string(), json_schema_table(table));
+    }
+
+    let mut out = json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": schema.get_name().as_deref(),
+        "$defs": defs
+    });
+    if let Some(description) = schema
+        .get_header()
+        .and_then(|header| header.get_description())
+    {
+        out["description"] = Value::String(description.to_string());
+    }
+    out
+}
+
+fn is_ts_ident(s: &str) -> bool {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' || c == '$' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+        }
+        _ => false,
+    }
+}
+
+fn ts_type_name(s: &str) -> String {
+    let name: String = s
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{name}")
+    } else {
+        name
+    }
+}
+
+fn ts_table(table: &TableSchema) -> String {
+    let mut out =
+        format!("export interface {} {{\n", ts_type_name(&table.get_name()));
+
+    for column in table.get_columns() {
+        let column_type = column.get_type();
+        let any = column_type.as_any();
+        let ts_type = match any.downcast_ref::<IntDataType>() {
+            // 64-bit values beyond the range of a double are strings in JSON
+            Some(dtype) if dtype.get_width() == IntWidth::Bits64 => {
+                "number | string"
+            }
+            Some(_) => "number",
+            None if any.is::<DblDataType>() => "number",
+            None => "string",
+        };
+
+        let name = column.get_name();
+        let name = if is_ts_ident(&name) {
+            name.to_string()
+        } else {
+            format!("{:?}", &*name)
+        };
+        let name = if column.is_computed() {
+            format!("readonly {name}")
+        } else {
+            name
+        };
+
+        if column_type.get_nullable() {
+            out.push_str(&format!("    {name}: {ts_type} | null;\n"));
+        } else {
+            out.push_str(&format!("    {name}: {ts_type};\n"));
+        }
+    }
+
+    out.push('}');
+    out
+}
+
+/// Exports `schema` as a series of TypeScript interfaces, one per table.
+pub fn to_ts(schema: &SpreadsheetSchema) -> String {
+    let tables: Vec<String> = schema
+        .get_tables()
+        .iter()
+        .map(|table| ts_table(table))
+        .collect();
+    tables.join("\n\n")
+}
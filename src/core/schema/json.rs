@@ -1,45 +1,81 @@
 use crate::{
     core::schema::{
-        ColumnSchema, DBL_TYPE_NAME, DblDataType, INT_TYPE_NAME, IntDataType,
-        STR_TYPE_NAME, SpreadsheetSchema, StrDataType, TableSchema,
+        ColumnSchema, DBL_TYPE_NAME, DECIMAL_TYPE_NAME, DataType, DblDataType,
+        DecimalDataType, IntDataType, STR_TYPE_NAME, SchemaHeader,
+        SpreadsheetSchema, StrDataType, TableSchema, ViewSchema,
     },
     json::ToJson,
     ql::lex::Literal,
 };
 use serde_json::{Number, Value, json};
+use std::rc::Rc;
 
 impl ToJson for Literal {
     fn to_json(&self) -> Value {
         match self {
-            Self::Int(val) => {
-                Value::Number(Number::from_i128(*val as i128).unwrap())
-            }
+            Self::Int(val) => int_to_json(*val),
             Self::Dbl(val) => Value::Number(Number::from_f64(*val).unwrap()),
+            // decimals are written as strings so readers don't round them
+            Self::Decimal(val) => Value::String(val.to_string()),
             Self::Str(val) => Value::String(val.to_string()),
         }
     }
 }
 
+// The largest integer a JSON reader using doubles can hold exactly.
+const MAX_SAFE_INTEGER: i64 = (1 << 53) - 1;
+
+// Writes integers outside the range doubles hold exactly as strings.
+fn int_to_json(val: i64) -> Value {
+    if val.unsigned_abs() <= MAX_SAFE_INTEGER as u64 {
+        Value::Number(val.into())
+    } else {
+        Value::String(val.to_string())
+    }
+}
+
+fn lineage_to_json(lineage: &[Rc<str>]) -> Value {
+    Value::Array(
+        lineage
+            .iter()
+            .map(|name| Value::String(name.to_string()))
+            .collect(),
+    )
+}
+
 impl ToJson for IntDataType {
     fn to_json(&self) -> Value {
-        json!({"super": INT_TYPE_NAME, "nullable": self.nullable, "min": self.min, "max": self.max})
+        json!({"super": self.get_super_name(), "nullable": self.nullable, "min": self.min.map(int_to_json), "max": self.max.map(int_to_json), "min_exclusive": self.min_exclusive, "max_exclusive": self.max_exclusive, "lineage": lineage_to_json(&self.lineage)})
     }
 }
 
 impl ToJson for DblDataType {
     fn to_json(&self) -> Value {
-        json!({"super": DBL_TYPE_NAME, "nullable": self.nullable, "min": self.min, "max": self.max})
+        json!({"super": DBL_TYPE_NAME, "nullable": self.nullable, "min": self.min, "max": self.max, "min_exclusive": self.min_exclusive, "max_exclusive": self.max_exclusive, "lineage": lineage_to_json(&self.lineage)})
+    }
+}
+
+impl ToJson for DecimalDataType {
+    fn to_json(&self) -> Value {
+        json!({"super": DECIMAL_TYPE_NAME, "nullable": self.nullable, "precision": self.precision, "scale": self.scale, "lineage": lineage_to_json(&self.lineage)})
     }
 }
 
 impl ToJson for StrDataType {
     fn to_json(&self) -> Value {
-        json!({"super": STR_TYPE_NAME, "nullable": self.nullable, "min": self.min, "max": self.max})
+        json!({"super": STR_TYPE_NAME, "nullable": self.nullable, "min": self.min, "max": self.max, "length_unit": self.get_unit().get_name(), "lineage": lineage_to_json(&self.lineage)})
     }
 }
 
 impl ToJson for ColumnSchema {
     fn to_json(&self) -> Value {
+        if let Some(formula) = &self.formula {
+            return json!({
+                "column_name": *self.column_name,
+                "column_type": self.column_type.to_json(),
+                "formula": formula.to_string()
+            });
+        }
         match &self.default_value {
             Some(val) => {
                 json!({
@@ -66,11 +102,36 @@ impl ToJson for TableSchema {
     }
 }
 
+impl ToJson for ViewSchema {
+    fn to_json(&self) -> Value {
+        json!({
+            "view_name": *self.view_name,
+            "query": self.query.to_string(),
+            "columns": Value::Array(
+                self.table.columns.iter().map(|col| col.to_json()).collect(),
+            )
+        })
+    }
+}
+
+impl ToJson for SchemaHeader {
+    fn to_json(&self) -> Value {
+        json!({
+            "ss_name": *self.name,
+            "version": self.version.as_deref(),
+            "description": self.description.as_deref()
+        })
+    }
+}
+
 impl ToJson for SpreadsheetSchema {
     fn to_json(&self) -> Value {
         json!({
-            "ss_name": *self.ss_name,
-            "tables": Value::Array(self.tables.iter().map(|table| table.to_json()).collect())
+            "ss_name": self.get_name().as_deref(),
+            "version": self.header.as_ref().and_then(|header| header.version.as_deref()),
+            "description": self.header.as_ref().and_then(|header| header.description.as_deref()),
+            "tables": Value::Array(self.tables.iter().map(|table| table.to_json()).collect()),
+            "views": Value::Array(self.views.iter().map(|view| view.to_json()).collect())
         })
     }
 }
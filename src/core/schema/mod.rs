@@ -1,74 +1,253 @@
 pub mod codegen;
+pub mod coerce;
+pub mod compat;
+pub mod computed;
+pub mod diff;
+pub mod export;
 pub mod json;
+pub mod refine;
+pub mod validate;
 
-use anyhow::Ok;
-
-use crate::{json::ToJson, ql::lex::Literal};
+use crate::{
+    core::schema::{
+        coerce::{Strictness, coerce_text},
+        validate::{TypeViolation, ViolationKind},
+    },
+    json::ToJson,
+    ql::{SymbolTable, expr::Expr, lex::Literal, query::SelectStmt},
+};
+use rust_decimal::Decimal;
 use std::{
+    any::Any,
     fmt::{Debug, Display},
     rc::Rc,
 };
+use unicode_segmentation::UnicodeSegmentation;
 
 pub const INT_TYPE_NAME: &str = "int";
+pub const INT64_TYPE_NAME: &str = "int64";
 pub const DBL_TYPE_NAME: &str = "dbl";
+pub const DECIMAL_TYPE_NAME: &str = "decimal";
 pub const STR_TYPE_NAME: &str = "str";
 
+/// The largest precision a decimal type can have.
+pub const MAX_DECIMAL_PRECISION: u32 = 28;
+
 /// Represents a data type in the application.
 pub trait DataType: ToJson + Display {
+    /// Returns `self` as `&dyn Any` so callers can downcast to the concrete
+    /// data type.
+    fn as_any(&self) -> &dyn Any;
+
+    /// Returns the name of the built-in type this type is derived from.
+    fn get_super_name(&self) -> &'static str;
+
+    /// Returns the names of the type aliases this type was refined from,
+    /// nearest first.
+    fn get_lineage(&self) -> &[Rc<str>];
+
     fn get_nullable(&self) -> bool;
 
     fn validate_literal(&self, lit: Option<&Literal>) -> anyhow::Result<()> {
+        Ok(self.check_literal(lit)?)
+    }
+
+    /// Checks `lit`, or null if `lit` is `None`, against the type, describing
+    /// why it doesn't conform.
+    fn check_literal(
+        &self,
+        lit: Option<&Literal>,
+    ) -> Result<(), TypeViolation> {
         match lit {
             Some(lit) => self.validator(lit),
-            None => {
-                if self.get_nullable() {
-                    return Ok(());
-                } else {
-                    return Err(anyhow::anyhow!("Required value was null!"));
-                }
-            }
+            None if self.get_nullable() => Ok(()),
+            None => Err(TypeViolation::new(
+                ViolationKind::NullNotAllowed,
+                "Required value was null!".to_string(),
+            )),
         }
     }
 
+    /// Converts text typed into a cell into a value of this type, checking it
+    /// conforms. Empty text is null, except in non-nullable string types where
+    /// it's the empty string.
+    fn coerce(
+        &self,
+        text: &str,
+        strictness: Strictness,
+    ) -> Result<Option<Literal>, TypeViolation> {
+        let lit = match coerce_text(self.as_any(), text, strictness)? {
+            None if !self.get_nullable()
+                && self.as_any().is::<StrDataType>() =>
+            {
+                Some(Literal::Str("".into()))
+            }
+            lit => lit,
+        };
+        self.check_literal(lit.as_ref())?;
+        Ok(lit)
+    }
+
     fn validate_data_type(&self) -> anyhow::Result<()>;
 
-    fn validator(&self, lit: &Literal) -> anyhow::Result<()>;
+    fn validator(&self, lit: &Literal) -> Result<(), TypeViolation>;
 }
 
 /// Type alias over `Rc<dyn DataType>` for convenience.
 pub type SharedDataType = Rc<dyn DataType>;
 
+/// The number of bits an integer type's values are stored in.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum IntWidth {
+    #[default]
+    Bits32,
+    Bits64,
+}
+
+impl IntWidth {
+    pub fn get_bits(&self) -> u32 {
+        match self {
+            Self::Bits32 => 32,
+            Self::Bits64 => 64,
+        }
+    }
+
+    /// Returns the smallest and largest values of this width.
+    pub fn get_range(&self) -> (i64, i64) {
+        match self {
+            Self::Bits32 => (i32::MIN.into(), i32::MAX.into()),
+            Self::Bits64 => (i64::MIN, i64::MAX),
+        }
+    }
+}
+
+// Checks the min is below the max, or no greater than it if both bounds are
+// inclusive.
+fn validate_bounds<T: PartialOrd + Display>(
+    (min, min_exclusive): (Option<T>, bool),
+    (max, max_exclusive): (Option<T>, bool),
+) -> anyhow::Result<()> {
+    if let Some(min) = min
+        && let Some(max) = max
+    {
+        if min > max {
+            return Err(anyhow::anyhow!(
+                "Can't have min ({min}) > max ({max})"
+            ));
+        }
+        if min == max && (min_exclusive || max_exclusive) {
+            return Err(anyhow::anyhow!(
+                "Can't have min ({min}) = max ({max}) with an exclusive bound"
+            ));
+        }
+    }
+    Ok(())
+}
+
 /// Represents an integer data type in the application.
 #[derive(Debug)]
 pub struct IntDataType {
-    min: Option<i32>,
-    max: Option<i32>,
+    min: Option<i64>,
+    max: Option<i64>,
+    min_exclusive: bool,
+    max_exclusive: bool,
     nullable: bool,
+    width: IntWidth,
+    lineage: Vec<Rc<str>>,
 }
 
 impl IntDataType {
-    pub fn new(min: Option<i32>, max: Option<i32>, nullable: bool) -> Self {
-        Self { min, max, nullable }
+    pub fn new(min: Option<i64>, max: Option<i64>, nullable: bool) -> Self {
+        Self {
+            min,
+            max,
+            min_exclusive: false,
+            max_exclusive: false,
+            nullable,
+            width: IntWidth::default(),
+            lineage: vec![],
+        }
     }
 
-    fn validate_i32(&self, val: i32) -> anyhow::Result<()> {
-        if let Some(min) = self.min
-            && val < min
-        {
-            return Err(anyhow::anyhow!(
-                "Minimum value {} (entered {})",
-                min,
-                val
+    /// Sets whether the min and max are excluded from the type's values.
+    pub fn with_exclusive_bounds(
+        mut self,
+        min_exclusive: bool,
+        max_exclusive: bool,
+    ) -> Self {
+        self.min_exclusive = min_exclusive;
+        self.max_exclusive = max_exclusive;
+        self
+    }
+
+    /// Sets the number of bits the type's values are stored in.
+    pub fn with_width(mut self, width: IntWidth) -> Self {
+        self.width = width;
+        self
+    }
+
+    pub fn get_min(&self) -> Option<i64> {
+        self.min
+    }
+
+    pub fn get_max(&self) -> Option<i64> {
+        self.max
+    }
+
+    pub fn is_min_exclusive(&self) -> bool {
+        self.min_exclusive
+    }
+
+    pub fn is_max_exclusive(&self) -> bool {
+        self.max_exclusive
+    }
+
+    pub fn get_width(&self) -> IntWidth {
+        self.width
+    }
+
+    /// Returns the bounds of the type with exclusive bounds moved inwards to
+    /// the nearest included value.
+    pub fn get_inclusive_bounds(&self) -> (Option<i64>, Option<i64>) {
+        (
+            self.min
+                .map(|min| min.saturating_add(self.min_exclusive as i64)),
+            self.max
+                .map(|max| max.saturating_sub(self.max_exclusive as i64)),
+        )
+    }
+
+    /// Returns the smallest and largest values of the type, taking missing
+    /// bounds from its width.
+    pub fn get_range(&self) -> (i64, i64) {
+        let (width_min, width_max) = self.width.get_range();
+        let (min, max) = self.get_inclusive_bounds();
+        (min.unwrap_or(width_min), max.unwrap_or(width_max))
+    }
+
+    fn validate_i64(&self, val: i64) -> Result<(), TypeViolation> {
+        let (min, max) = self.get_range();
+        if val < min {
+            return Err(TypeViolation::new(
+                ViolationKind::TooSmall,
+                match self.min {
+                    Some(min) if self.min_exclusive => {
+                        format!("Value must be above {min} (entered {val})")
+                    }
+                    _ => format!("Minimum value {} (entered {})", min, val),
+                },
             ));
         }
 
-        if let Some(max) = self.max
-            && val > max
-        {
-            return Err(anyhow::anyhow!(
-                "Maximum value {} (entered {})",
-                max,
-                val
+        if val > max {
+            return Err(TypeViolation::new(
+                ViolationKind::TooLarge,
+                match self.max {
+                    Some(max) if self.max_exclusive => {
+                        format!("Value must be below {max} (entered {val})")
+                    }
+                    _ => format!("Maximum value {} (entered {})", max, val),
+                },
             ));
         }
 
@@ -77,27 +256,54 @@ impl IntDataType {
 }
 
 impl DataType for IntDataType {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn get_super_name(&self) -> &'static str {
+        match self.width {
+            IntWidth::Bits32 => INT_TYPE_NAME,
+            IntWidth::Bits64 => INT64_TYPE_NAME,
+        }
+    }
+
+    fn get_lineage(&self) -> &[Rc<str>] {
+        &self.lineage
+    }
+
     fn get_nullable(&self) -> bool {
         self.nullable
     }
 
-    fn validator(&self, lit: &Literal) -> anyhow::Result<()> {
+    fn validator(&self, lit: &Literal) -> Result<(), TypeViolation> {
         match lit {
-            Literal::Int(val) => self.validate_i32(*val),
-            _ => Err(anyhow::anyhow!(
+            Literal::Int(val) => self.validate_i64(*val),
+            _ => Err(TypeViolation::new(
+                ViolationKind::WrongType,
                 "Couldn't validate non-integer literal against integer type."
+                    .to_string(),
             )),
         }
     }
 
     fn validate_data_type(&self) -> anyhow::Result<()> {
-        if let Some(min) = self.min
-            && let Some(max) = self.max
-            && min > max
-        {
-            return Err(anyhow::anyhow!(
-                "Can't have min ({min}) > max ({max})"
-            ));
+        let (width_min, width_max) = self.width.get_range();
+        for bound in [self.min, self.max].into_iter().flatten() {
+            if bound < width_min || bound > width_max {
+                return Err(anyhow::anyhow!(
+                    "Bound {bound} doesn't fit in a {}-bit integer",
+                    self.width.get_bits()
+                ));
+            }
+        }
+        validate_bounds(
+            (self.min, self.min_exclusive),
+            (self.max, self.max_exclusive),
+        )?;
+        // exclusive int bounds can leave no values, as in `int(0, 1)`
+        let (min, max) = self.get_range();
+        if min > max {
+            return Err(anyhow::anyhow!("Type {self} has no values"));
         }
         Ok(())
     }
@@ -109,33 +315,87 @@ impl DataType for IntDataType {
 pub struct DblDataType {
     min: Option<f64>,
     max: Option<f64>,
+    min_exclusive: bool,
+    max_exclusive: bool,
     nullable: bool,
+    lineage: Vec<Rc<str>>,
 }
 
 impl DblDataType {
     pub fn new(min: Option<f64>, max: Option<f64>, nullable: bool) -> Self {
-        Self { min, max, nullable }
+        Self {
+            min,
+            max,
+            min_exclusive: false,
+            max_exclusive: false,
+            nullable,
+            lineage: vec![],
+        }
     }
 
-    fn validate_f64(&self, val: f64) -> anyhow::Result<()> {
-        if let Some(min) = self.min
-            && val < min
-        {
-            return Err(anyhow::anyhow!(
-                "Minimum value {} (entered {})",
-                min,
-                val
+    /// Sets whether the min and max are excluded from the type's values.
+    pub fn with_exclusive_bounds(
+        mut self,
+        min_exclusive: bool,
+        max_exclusive: bool,
+    ) -> Self {
+        self.min_exclusive = min_exclusive;
+        self.max_exclusive = max_exclusive;
+        self
+    }
+
+    pub fn get_min(&self) -> Option<f64> {
+        self.min
+    }
+
+    pub fn get_max(&self) -> Option<f64> {
+        self.max
+    }
+
+    pub fn is_min_exclusive(&self) -> bool {
+        self.min_exclusive
+    }
+
+    pub fn is_max_exclusive(&self) -> bool {
+        self.max_exclusive
+    }
+
+    fn validate_f64(&self, val: f64) -> Result<(), TypeViolation> {
+        if !val.is_finite() {
+            return Err(TypeViolation::new(
+                ViolationKind::WrongType,
+                format!("Value must be a finite number (entered {val})"),
             ));
         }
 
-        if let Some(max) = self.max
-            && val > max
-        {
-            return Err(anyhow::anyhow!(
-                "Maximum value {} (entered {})",
-                max,
-                val
-            ));
+        if let Some(min) = self.min {
+            if self.min_exclusive && val <= min {
+                return Err(TypeViolation::new(
+                    ViolationKind::TooSmall,
+                    format!("Value must be above {min} (entered {val})"),
+                ));
+            }
+            if val < min {
+                return Err(TypeViolation::new(
+                    ViolationKind::TooSmall,
+                    format!("Minimum value {} (entered {})", min, val),
+                ));
+            }
+        }
+
+        if let Some(max) = self.max {
+            if self.max_exclusive && val >= max {
+                return Err(TypeViolation::new(
+                    ViolationKind::TooLarge,
+                    format!("Value must be below {max} (entered {val})"),
+                ));
+            }
+            if val > max {
+                return Err(TypeViolation::new(
+                    ViolationKind::TooLarge,
+                    format!("Maximum value {} (entered {})", max, val),
+                ));
+            }
         }
 
         Ok(())
@@ -143,70 +403,345 @@ impl DblDataType {
 }
 
 impl DataType for DblDataType {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn get_super_name(&self) -> &'static str {
+        DBL_TYPE_NAME
+    }
+
+    fn get_lineage(&self) -> &[Rc<str>] {
+        &self.lineage
+    }
+
     fn get_nullable(&self) -> bool {
         self.nullable
     }
 
-    fn validator(&self, lit: &Literal) -> anyhow::Result<()> {
+    fn validator(&self, lit: &Literal) -> Result<(), TypeViolation> {
         match lit {
             Literal::Dbl(val) => self.validate_f64(*val),
-            _ => Err(anyhow::anyhow!(
+            _ => Err(TypeViolation::new(
+                ViolationKind::WrongType,
                 "Couldn't validate non-double literal against double type."
+                    .to_string(),
             )),
         }
     }
 
     fn validate_data_type(&self) -> anyhow::Result<()> {
-        if let Some(min) = self.min
-            && let Some(max) = self.max
-            && min > max
+        // NaN and infinite bounds can only come from outside the DDL
+        for bound in [self.min, self.max].into_iter().flatten() {
+            if !bound.is_finite() {
+                return Err(anyhow::anyhow!(
+                    "Bound {bound} must be a finite number"
+                ));
+            }
+        }
+        validate_bounds(
+            (self.min, self.min_exclusive),
+            (self.max, self.max_exclusive),
+        )?;
+        Ok(())
+    }
+}
+
+/// Represents an exact decimal data type in the application, with at most
+/// `precision` digits, `scale` of them after the decimal point.
+#[derive(Debug)]
+pub struct DecimalDataType {
+    precision: Option<u32>,
+    scale: Option<u32>,
+    nullable: bool,
+    lineage: Vec<Rc<str>>,
+}
+
+impl DecimalDataType {
+    pub fn new(
+        precision: Option<u32>,
+        scale: Option<u32>,
+        nullable: bool,
+    ) -> Self {
+        Self {
+            precision,
+            scale,
+            nullable,
+            lineage: vec![],
+        }
+    }
+
+    pub fn get_precision(&self) -> Option<u32> {
+        self.precision
+    }
+
+    pub fn get_scale(&self) -> Option<u32> {
+        self.scale
+    }
+
+    /// Returns the most digits a value may have before the decimal point, or
+    /// `None` if there's no limit besides that of a decimal.
+    pub fn get_max_int_digits(&self) -> Option<u32> {
+        self.precision
+            .map(|precision| precision - self.scale.unwrap_or(0).min(precision))
+    }
+
+    /// Returns the largest magnitude of a value of the type, if the type
+    /// limits the digits before the decimal point.
+    pub fn get_max_abs(&self) -> Option<Decimal> {
+        let int_digits = self.get_max_int_digits()?;
+        let scale = self.scale.unwrap_or(0);
+        Some(Decimal::from_i128_with_scale(
+            10i128.pow(int_digits + scale) - 1,
+            scale,
+        ))
+    }
+
+    fn validate_decimal(&self, val: Decimal) -> Result<(), TypeViolation> {
+        let val = val.normalize();
+        if let Some(scale) = self.scale
+            && val.scale() > scale
+        {
+            return Err(TypeViolation::new(
+                ViolationKind::TooPrecise,
+                format!(
+                    "Maximum {scale} digits after the decimal point (entered \
+                    {val})"
+                ),
+            ));
+        }
+
+        // without a scale, digits after the point count towards the precision
+        let max_int_digits = match (self.precision, self.scale) {
+            (Some(precision), None) => {
+                Some(precision.saturating_sub(val.scale()))
+            }
+            _ => self.get_max_int_digits(),
+        };
+        let int_digits = match val.trunc().abs() {
+            int if int.is_zero() => 0,
+            int => int.to_string().len() as u32,
+        };
+        if let Some(max_int_digits) = max_int_digits
+            && int_digits > max_int_digits
+        {
+            return Err(TypeViolation::new(
+                if val.is_sign_negative() {
+                    ViolationKind::TooSmall
+                } else {
+                    ViolationKind::TooLarge
+                },
+                format!(
+                    "Maximum {max_int_digits} digits before the decimal point \
+                    (entered {val})"
+                ),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl DataType for DecimalDataType {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn get_super_name(&self) -> &'static str {
+        DECIMAL_TYPE_NAME
+    }
+
+    fn get_lineage(&self) -> &[Rc<str>] {
+        &self.lineage
+    }
+
+    fn get_nullable(&self) -> bool {
+        self.nullable
+    }
+
+    fn validator(&self, lit: &Literal) -> Result<(), TypeViolation> {
+        match lit {
+            Literal::Decimal(val) => self.validate_decimal(*val),
+            _ => Err(TypeViolation::new(
+                ViolationKind::WrongType,
+                "Couldn't validate non-decimal literal against decimal type."
+                    .to_string(),
+            )),
+        }
+    }
+
+    fn validate_data_type(&self) -> anyhow::Result<()> {
+        if let Some(precision) = self.precision
+            && !(1..=MAX_DECIMAL_PRECISION).contains(&precision)
         {
             return Err(anyhow::anyhow!(
-                "Can't have min ({min}) > max ({max})"
+                "Precision ({precision}) must be between 1 and \
+                {MAX_DECIMAL_PRECISION}"
+            ));
+        }
+        if let Some(scale) = self.scale
+            && scale > self.precision.unwrap_or(MAX_DECIMAL_PRECISION)
+        {
+            return Err(anyhow::anyhow!(
+                "Can't have scale ({scale}) > precision ({})",
+                self.precision.unwrap_or(MAX_DECIMAL_PRECISION)
             ));
         }
         Ok(())
     }
 }
 
+/// The unit a string type's length bounds are measured in.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum LengthUnit {
+    /// UTF-8 bytes.
+    Bytes,
+    /// Unicode scalar values.
+    #[default]
+    Chars,
+    /// Extended grapheme clusters, the characters a user perceives.
+    Graphemes,
+}
+
+impl LengthUnit {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Self::Bytes => "bytes",
+            Self::Chars => "chars",
+            Self::Graphemes => "graphemes",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "bytes" => Some(Self::Bytes),
+            "chars" => Some(Self::Chars),
+            "graphemes" => Some(Self::Graphemes),
+            _ => None,
+        }
+    }
+
+    /// Returns the length of `s` in this unit.
+    pub fn measure(&self, s: &str) -> usize {
+        match self {
+            Self::Bytes => s.len(),
+            Self::Chars => s.chars().count(),
+            Self::Graphemes => s.graphemes(true).count(),
+        }
+    }
+
+    /// Returns the longest prefix of `s` no longer than `len` in this unit.
+    pub fn truncate<'a>(&self, s: &'a str, len: usize) -> &'a str {
+        let end = match self {
+            Self::Bytes => (0..=len.min(s.len()))
+                .rev()
+                .find(|idx| s.is_char_boundary(*idx))
+                .unwrap_or(0),
+            Self::Chars => {
+                s.char_indices().nth(len).map_or(s.len(), |(idx, _)| idx)
+            }
+            Self::Graphemes => s
+                .grapheme_indices(true)
+                .nth(len)
+                .map_or(s.len(), |(idx, _)| idx),
+        };
+        &s[..end]
+    }
+}
+
 /// Represents a string data type in the application.
 #[derive(Debug)]
 pub struct StrDataType {
     min: Option<usize>,
     max: Option<usize>,
     nullable: bool,
+    // `None` if the type doesn't name a unit, so refinements can inherit one
+    unit: Option<LengthUnit>,
+    lineage: Vec<Rc<str>>,
 }
 
 impl StrDataType {
     pub fn new(min: Option<usize>, max: Option<usize>, nullable: bool) -> Self {
-        Self { min, max, nullable }
+        Self {
+            min,
+            max,
+            nullable,
+            unit: None,
+            lineage: vec![],
+        }
     }
 
-    fn validate_str(&self, s: &str) -> anyhow::Result<()> {
+    /// Sets the unit the length bounds are measured in.
+    pub fn with_unit(mut self, unit: LengthUnit) -> Self {
+        self.unit = Some(unit);
+        self
+    }
+
+    pub fn get_min(&self) -> Option<usize> {
+        self.min
+    }
+
+    pub fn get_max(&self) -> Option<usize> {
+        self.max
+    }
+
+    pub fn get_unit(&self) -> LengthUnit {
+        self.unit.unwrap_or_default()
+    }
+
+    fn validate_str(&self, s: &str) -> Result<(), TypeViolation> {
+        let unit = self.get_unit();
+        let len = unit.measure(s);
         if let Some(min) = &self.min
-            && s.len() < *min
+            && len < *min
         {
-            return Err(anyhow::anyhow!("Minimum length {min}"));
+            return Err(TypeViolation::new(
+                ViolationKind::TooShort,
+                format!(
+                    "Minimum length {min} {} (entered {len})",
+                    unit.get_name()
+                ),
+            ));
         }
         if let Some(max) = &self.max
-            && s.len() < *max
+            && len > *max
         {
-            return Err(anyhow::anyhow!("Minimum length {max}"));
+            return Err(TypeViolation::new(
+                ViolationKind::TooLong,
+                format!(
+                    "Maximum length {max} {} (entered {len})",
+                    unit.get_name()
+                ),
+            ));
         }
         Ok(())
     }
 }
 
 impl DataType for StrDataType {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn get_super_name(&self) -> &'static str {
+        STR_TYPE_NAME
+    }
+
+    fn get_lineage(&self) -> &[Rc<str>] {
+        &self.lineage
+    }
+
     fn get_nullable(&self) -> bool {
         self.nullable
     }
 
-    fn validator(&self, lit: &Literal) -> anyhow::Result<()> {
+    fn validator(&self, lit: &Literal) -> Result<(), TypeViolation> {
         match lit {
             Literal::Str(val) => self.validate_str(&val),
-            _ => Err(anyhow::anyhow!(
+            _ => Err(TypeViolation::new(
+                ViolationKind::WrongType,
                 "Couldn't validate non-string literal against string type."
+                    .to_string(),
             )),
         }
     }
@@ -224,11 +759,14 @@ impl DataType for StrDataType {
     }
 }
 
-/// Represents a column schema in the application.
+/// Represents a column schema in the application. A column with a formula is
+/// computed from the other columns of its row.
+#[derive(Clone)]
 pub struct ColumnSchema {
     column_name: Rc<str>,
     column_type: Rc<dyn DataType>,
     default_value: Option<Literal>,
+    formula: Option<Expr>,
 }
 
 impl ColumnSchema {
@@ -241,9 +779,17 @@ impl ColumnSchema {
             column_name,
             column_type,
             default_value,
+            formula: None,
         }
     }
 
+    /// Sets the formula the column's values are computed with, or makes it
+    /// an ordinary column if `formula` is `None`.
+    pub fn with_formula(mut self, formula: Option<Expr>) -> Self {
+        self.formula = formula;
+        self
+    }
+
     pub fn get_name(&self) -> Rc<str> {
         self.column_name.clone()
     }
@@ -252,8 +798,29 @@ impl ColumnSchema {
         self.column_type.clone()
     }
 
+    pub fn get_default_value(&self) -> Option<&Literal> {
+        self.default_value.as_ref()
+    }
+
+    pub fn get_formula(&self) -> Option<&Expr> {
+        self.formula.as_ref()
+    }
+
+    pub fn is_computed(&self) -> bool {
+        self.formula.is_some()
+    }
+
     pub fn validate_column_schema(&self) -> anyhow::Result<()> {
-        self.get_type().validate_data_type()
+        self.get_type().validate_data_type()?;
+        if let Some(default) = self.get_default_value() {
+            self.get_type().check_literal(Some(default)).map_err(|e| {
+                anyhow::anyhow!(
+                    "Default value {default} of column {} is invalid: {e}!",
+                    self.get_name()
+                )
+            })?;
+        }
+        Ok(())
     }
 }
 
@@ -283,29 +850,151 @@ impl TableSchema {
         self.columns.get(idx)
     }
 
+    pub fn get_columns(&self) -> &[ColumnSchema] {
+        &self.columns
+    }
+
+    pub fn get_column_by_name(&self, name: &str) -> Option<&ColumnSchema> {
+        self.columns.iter().find(|col| &*col.column_name == name)
+    }
+
+    pub fn get_column_idx(&self, name: &str) -> Option<usize> {
+        self.columns
+            .iter()
+            .position(|col| &*col.column_name == name)
+    }
+
     pub fn validate_table_schema(&self) -> anyhow::Result<()> {
         for col in &self.columns {
             col.validate_column_schema()?;
         }
-        Ok(())
+        self.check_computed_columns()
     }
 }
 
 pub type SharedTableSchema = Rc<TableSchema>;
 
+/// A named query that can be selected from like a table. Its rows are the
+/// query's results, so the table schema it's given is read-only.
+pub struct ViewSchema {
+    view_name: Rc<str>,
+    query: SelectStmt,
+    table: SharedTableSchema,
+}
+
+impl ViewSchema {
+    /// Creates the view, checking `query` against `symtable` to derive the
+    /// names and types of its columns.
+    pub fn new(
+        view_name: Rc<str>,
+        query: SelectStmt,
+        symtable: &SymbolTable,
+    ) -> anyhow::Result<Self> {
+        let mut columns: Vec<ColumnSchema> = vec![];
+        for col in query.check(symtable)? {
+            if columns
+                .iter()
+                .any(|other| other.column_name == col.get_name())
+            {
+                return Err(anyhow::anyhow!(
+                    "View {view_name} has more than one column {}!",
+                    col.get_name()
+                ));
+            }
+            columns.push(ColumnSchema::new(
+                col.get_name(),
+                col.get_type(),
+                None,
+            ));
+        }
+        Ok(Self {
+            table: Rc::new(TableSchema::new(view_name.clone(), columns)),
+            view_name,
+            query,
+        })
+    }
+
+    pub fn get_name(&self) -> Rc<str> {
+        self.view_name.clone()
+    }
+
+    pub fn get_query(&self) -> &SelectStmt {
+        &self.query
+    }
+
+    /// Returns the columns of the view's rows as a table schema.
+    pub fn get_table_schema(&self) -> SharedTableSchema {
+        self.table.clone()
+    }
+}
+
+pub type SharedViewSchema = Rc<ViewSchema>;
+
+/// The `schema` header statement that names a spreadsheet.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SchemaHeader {
+    name: Rc<str>,
+    version: Option<Rc<str>>,
+    description: Option<Rc<str>>,
+}
+
+impl SchemaHeader {
+    pub fn new(
+        name: Rc<str>,
+        version: Option<Rc<str>>,
+        description: Option<Rc<str>>,
+    ) -> Self {
+        Self {
+            name,
+            version,
+            description,
+        }
+    }
+
+    pub fn get_name(&self) -> Rc<str> {
+        self.name.clone()
+    }
+
+    pub fn get_version(&self) -> Option<Rc<str>> {
+        self.version.clone()
+    }
+
+    pub fn get_description(&self) -> Option<Rc<str>> {
+        self.description.clone()
+    }
+}
+
 /// Represents a database schema in the application.
 pub struct SpreadsheetSchema {
-    ss_name: Rc<str>,
+    header: Option<SchemaHeader>,
     tables: Vec<Rc<TableSchema>>,
+    views: Vec<SharedViewSchema>,
 }
 
 impl SpreadsheetSchema {
-    pub fn new(ss_name: Rc<str>, tables: Vec<Rc<TableSchema>>) -> Self {
-        Self { ss_name, tables }
+    pub fn new(
+        header: Option<SchemaHeader>,
+        tables: Vec<Rc<TableSchema>>,
+    ) -> Self {
+        Self {
+            header,
+            tables,
+            views: vec![],
+        }
     }
 
-    pub fn get_name(&self) -> Rc<str> {
-        self.ss_name.clone()
+    pub fn with_views(mut self, views: Vec<SharedViewSchema>) -> Self {
+        self.views = views;
+        self
+    }
+
+    /// Returns the name given in the `schema` header, if there is one.
+    pub fn get_name(&self) -> Option<Rc<str>> {
+        self.header.as_ref().map(|header| header.get_name())
+    }
+
+    pub fn get_header(&self) -> Option<&SchemaHeader> {
+        self.header.as_ref()
     }
 
     pub fn get_num_tables(&self) -> usize {
@@ -316,6 +1005,22 @@ impl SpreadsheetSchema {
         self.tables.get(idx)
     }
 
+    pub fn get_tables(&self) -> &[Rc<TableSchema>] {
+        &self.tables
+    }
+
+    pub fn get_table_by_name(&self, name: &str) -> Option<&Rc<TableSchema>> {
+        self.tables.iter().find(|table| &*table.table_name == name)
+    }
+
+    pub fn get_views(&self) -> &[SharedViewSchema] {
+        &self.views
+    }
+
+    pub fn get_view_by_name(&self, name: &str) -> Option<&SharedViewSchema> {
+        self.views.iter().find(|view| &*view.view_name == name)
+    }
+
     pub fn validate_spreadsheet_schema(&self) -> anyhow::Result<()> {
         for table in &self.tables {
             table.validate_table_schema()?;
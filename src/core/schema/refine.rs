@@ -0,0 +1,229 @@
+use std::{fmt::Display, rc::Rc};
+
+use crate::core::schema::{
+    DataType, DblDataType, DecimalDataType, IntDataType, StrDataType,
+    compat::is_value_assignable,
+};
+
+// Merges the bounds of a refinement with those of its base type. Bounds left
+// empty in the refinement are inherited from the base; the others must lie
+// inside the base bounds, so every value of the refined type is a value of the
+// base type.
+fn refine_bounds<T: PartialOrd + Copy + Display>(
+    (base_min, base_max): (Option<T>, Option<T>),
+    (min, max): (Option<T>, Option<T>),
+) -> anyhow::Result<(Option<T>, Option<T>)> {
+    let min = match (base_min, min) {
+        (Some(base_min), Some(min)) if min < base_min => {
+            return Err(anyhow::anyhow!(
+                "Refined min ({min}) is below the base type's min ({base_min})"
+            ));
+        }
+        (base_min, None) => base_min,
+        (_, min) => min,
+    };
+
+    let max = match (base_max, max) {
+        (Some(base_max), Some(max)) if max > base_max => {
+            return Err(anyhow::anyhow!(
+                "Refined max ({max}) is above the base type's max ({base_max})"
+            ));
+        }
+        (base_max, None) => base_max,
+        (_, max) => max,
+    };
+
+    Ok((min, max))
+}
+
+// Returns whether a refined bound is exclusive, given the base's bound. A
+// refinement can't include a value its base type excludes.
+fn refine_exclusive<T: PartialEq + Display>(
+    (base, base_exclusive): (Option<T>, bool),
+    (bound, exclusive): (Option<T>, bool),
+) -> anyhow::Result<bool> {
+    match (base, bound) {
+        (_, None) => Ok(base_exclusive),
+        (Some(base), Some(bound))
+            if bound == base && base_exclusive && !exclusive =>
+        {
+            Err(anyhow::anyhow!(
+                "Refined bound {bound} is included, but the base type \
+                excludes it"
+            ))
+        }
+        _ => Ok(exclusive),
+    }
+}
+
+// Like the max of `refine_bounds`, for limits such as a decimal's precision.
+fn refine_limit(
+    what: &str,
+    base: Option<u32>,
+    limit: Option<u32>,
+) -> anyhow::Result<Option<u32>> {
+    match (base, limit) {
+        (Some(base), Some(limit)) if limit > base => Err(anyhow::anyhow!(
+            "Refined {what} ({limit}) is above the base type's {what} ({base})"
+        )),
+        (base, None) => Ok(base),
+        (_, limit) => Ok(limit),
+    }
+}
+
+// Returns whether a refined type is nullable: as `?` or `!` say, or else as
+// its base is.
+fn refine_nullable(base: bool, nullable: Option<bool>) -> anyhow::Result<bool> {
+    match nullable {
+        Some(true) if !base => Err(anyhow::anyhow!(
+            "Can't refine a non-nullable type into a nullable one"
+        )),
+        Some(nullable) => Ok(nullable),
+        None => Ok(base),
+    }
+}
+
+fn refine_lineage(base_name: Rc<str>, base: &dyn DataType) -> Vec<Rc<str>> {
+    std::iter::once(base_name)
+        .chain(base.get_lineage().iter().cloned())
+        .collect()
+}
+
+impl IntDataType {
+    /// Derives a narrower type from `self`, the type named `base_name`, using
+    /// the bounds of `refinement`. It's nullable as `nullable` says, or as
+    /// `self` is if that's `None`.
+    pub fn refine(
+        &self,
+        base_name: Rc<str>,
+        refinement: &IntDataType,
+        nullable: Option<bool>,
+    ) -> anyhow::Result<Self> {
+        // ints are compared by the values they include, so `int(0, >` and
+        // `int<1, >` have the same min
+        refine_bounds(
+            self.get_inclusive_bounds(),
+            refinement.get_inclusive_bounds(),
+        )?;
+        let (min, min_exclusive) = match refinement.min {
+            Some(min) => (Some(min), refinement.min_exclusive),
+            None => (self.min, self.min_exclusive),
+        };
+        let (max, max_exclusive) = match refinement.max {
+            Some(max) => (Some(max), refinement.max_exclusive),
+            None => (self.max, self.max_exclusive),
+        };
+        let refined = Self {
+            min,
+            max,
+            min_exclusive,
+            max_exclusive,
+            nullable: refine_nullable(self.nullable, nullable)?,
+            width: self.width,
+            lineage: refine_lineage(base_name, self),
+        };
+        refined.validate_data_type()?;
+        Ok(refined)
+    }
+}
+
+impl DblDataType {
+    /// Derives a narrower type from `self`, the type named `base_name`, using
+    /// the bounds of `refinement`. It's nullable as `nullable` says, or as
+    /// `self` is if that's `None`.
+    pub fn refine(
+        &self,
+        base_name: Rc<str>,
+        refinement: &DblDataType,
+        nullable: Option<bool>,
+    ) -> anyhow::Result<Self> {
+        let (min, max) = refine_bounds(
+            (self.min, self.max),
+            (refinement.min, refinement.max),
+        )?;
+        let refined = Self {
+            min,
+            max,
+            min_exclusive: refine_exclusive(
+                (self.min, self.min_exclusive),
+                (refinement.min, refinement.min_exclusive),
+            )?,
+            max_exclusive: refine_exclusive(
+                (self.max, self.max_exclusive),
+                (refinement.max, refinement.max_exclusive),
+            )?,
+            nullable: refine_nullable(self.nullable, nullable)?,
+            lineage: refine_lineage(base_name, self),
+        };
+        refined.validate_data_type()?;
+        Ok(refined)
+    }
+}
+
+impl DecimalDataType {
+    /// Derives a narrower type from `self`, the type named `base_name`, using
+    /// the precision and scale of `refinement`. It's nullable as `nullable`
+    /// says, or as `self` is if that's `None`.
+    pub fn refine(
+        &self,
+        base_name: Rc<str>,
+        refinement: &DecimalDataType,
+        nullable: Option<bool>,
+    ) -> anyhow::Result<Self> {
+        let precision =
+            refine_limit("precision", self.precision, refinement.precision)?;
+        let scale = refine_limit("scale", self.scale, refinement.scale)?;
+        let refined = Self {
+            precision,
+            scale,
+            nullable: refine_nullable(self.nullable, nullable)?,
+            lineage: refine_lineage(base_name.clone(), self),
+        };
+        refined.validate_data_type()?;
+        // a smaller scale leaves more digits before the point
+        if !is_value_assignable(&refined, self) {
+            return Err(anyhow::anyhow!(
+                "Refinement {refined} allows values outside of {base_name}"
+            ));
+        }
+        Ok(refined)
+    }
+}
+
+impl StrDataType {
+    /// Derives a narrower type from `self`, the type named `base_name`, using
+    /// the length bounds of `refinement`. It's nullable as `nullable` says, or
+    /// as `self` is if that's `None`.
+    pub fn refine(
+        &self,
+        base_name: Rc<str>,
+        refinement: &StrDataType,
+        nullable: Option<bool>,
+    ) -> anyhow::Result<Self> {
+        let (min, max) = refine_bounds(
+            (self.min, self.max),
+            (refinement.min, refinement.max),
+        )?;
+        // bounds measured in different units can't be compared
+        let unit = match refinement.unit {
+            Some(unit) if unit != self.get_unit() => {
+                return Err(anyhow::anyhow!(
+                    "Can't measure a refinement of {base_name} in {}, as it's \
+                    measured in {}",
+                    unit.get_name(),
+                    self.get_unit().get_name()
+                ));
+            }
+            _ => self.unit,
+        };
+        let refined = Self {
+            min,
+            max,
+            nullable: refine_nullable(self.nullable, nullable)?,
+            unit,
+            lineage: refine_lineage(base_name, self),
+        };
+        refined.validate_data_type()?;
+        Ok(refined)
+    }
+}
@@ -0,0 +1,201 @@
+use std::{fmt::Display, rc::Rc};
+
+use serde_json::{Value, json};
+
+use crate::{core::schema::TableSchema, json::ToJson, ql::lex::Literal};
+
+/// The ways a value can fail to conform to a data type.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ViolationKind {
+    /// A number below the type's minimum.
+    TooSmall,
+    /// A number above the type's maximum.
+    TooLarge,
+    /// A decimal with more digits after the point than the type's scale.
+    TooPrecise,
+    /// A string shorter than the type's minimum length.
+    TooShort,
+    /// A string longer than the type's maximum length.
+    TooLong,
+    /// A value of a different type, such as a string in an integer column.
+    WrongType,
+    /// A null value in a column that isn't nullable and has no default.
+    NullNotAllowed,
+    /// No value, in a row too short to reach a column that isn't nullable
+    /// and has no default.
+    MissingValue,
+    /// A value beyond the last column of the table.
+    ExtraValue,
+    /// A computed column whose formula couldn't be evaluated, such as a
+    /// division by zero.
+    ComputeError,
+}
+
+impl ViolationKind {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Self::TooSmall => "TooSmall",
+            Self::TooLarge => "TooLarge",
+            Self::TooPrecise => "TooPrecise",
+            Self::TooShort => "TooShort",
+            Self::TooLong => "TooLong",
+            Self::WrongType => "WrongType",
+            Self::NullNotAllowed => "NullNotAllowed",
+            Self::MissingValue => "MissingValue",
+            Self::ExtraValue => "ExtraValue",
+            Self::ComputeError => "ComputeError",
+        }
+    }
+}
+
+/// The reason a value failed validation against a data type.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TypeViolation {
+    kind: ViolationKind,
+    message: String,
+}
+
+impl TypeViolation {
+    pub fn new(kind: ViolationKind, message: String) -> Self {
+        Self { kind, message }
+    }
+
+    pub fn get_kind(&self) -> ViolationKind {
+        self.kind
+    }
+
+    pub fn get_message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl Display for TypeViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for TypeViolation {}
+
+/// A cell of a row that failed validation against its table's schema.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RowViolation {
+    column_idx: usize,
+    column_name: Option<Rc<str>>,
+    kind: ViolationKind,
+    message: String,
+}
+
+impl RowViolation {
+    pub fn new(
+        column_idx: usize,
+        column_name: Option<Rc<str>>,
+        kind: ViolationKind,
+        message: String,
+    ) -> Self {
+        Self {
+            column_idx,
+            column_name,
+            kind,
+            message,
+        }
+    }
+
+    pub fn get_column_idx(&self) -> usize {
+        self.column_idx
+    }
+
+    /// Returns the name of the cell's column, or `None` for extra values.
+    pub fn get_column_name(&self) -> Option<Rc<str>> {
+        self.column_name.clone()
+    }
+
+    pub fn get_kind(&self) -> ViolationKind {
+        self.kind
+    }
+
+    pub fn get_message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl Display for RowViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.column_name {
+            Some(name) => write!(f, "In column {name}: {}", self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl ToJson for RowViolation {
+    fn to_json(&self) -> Value {
+        json!({
+            "column_idx": self.column_idx,
+            "column_name": self.column_name.as_deref(),
+            "kind": self.kind.get_name(),
+            "message": self.message
+        })
+    }
+}
+
+impl TableSchema {
+    /// Checks every cell of `row` against its column, returning all the
+    /// violations found. The row is first completed as by
+    /// [`TableSchema::complete_row`], so a null cell in a column that isn't
+    /// nullable is filled by the column's default, if it has one, and
+    /// computed columns are checked with the result of their formula. A row
+    /// may leave out trailing cells whose columns are nullable, have a
+    /// default or are computed; leaving out any other cell is a
+    /// `MissingValue` violation.
+    pub fn validate_row(&self, row: &[Option<Literal>]) -> Vec<RowViolation> {
+        let (completed, mut violations) = self.complete_row(row);
+
+        for (idx, col) in self.get_columns().iter().enumerate() {
+            if violations
+                .iter()
+                .any(|violation| violation.column_idx == idx)
+            {
+                continue;
+            }
+            if let Err(violation) =
+                col.get_type().check_literal(completed[idx].as_ref())
+            {
+                // a cell past the end of a short row is missing, not null
+                let (kind, message) = match violation.kind {
+                    ViolationKind::NullNotAllowed
+                        if idx >= row.len() && !col.is_computed() =>
+                    {
+                        (
+                            ViolationKind::MissingValue,
+                            "Required value is missing!".to_string(),
+                        )
+                    }
+                    kind => (kind, violation.message),
+                };
+                violations.push(RowViolation {
+                    column_idx: idx,
+                    column_name: Some(col.get_name()),
+                    kind,
+                    message,
+                });
+            }
+        }
+        violations.sort_by_key(|violation| violation.column_idx);
+
+        for idx in self.get_num_columns()..row.len() {
+            violations.push(RowViolation {
+                column_idx: idx,
+                column_name: None,
+                kind: ViolationKind::ExtraValue,
+                message: format!(
+                    "Table {} only has {} columns",
+                    self.get_name(),
+                    self.get_num_columns()
+                ),
+            });
+        }
+
+        violations
+    }
+}
@@ -2,12 +2,18 @@ use rlrl::parse::TokenQueue;
 
 use crate::{
     core::schema::SpreadsheetSchema,
-    ql::{lex::setup_lexer, parse::parse_spreadsheet_schema},
+    ql::{
+        import::{FsLoader, parse_schema_with_loader},
+        lex::setup_lexer,
+        parse::parse_spreadsheet_schema,
+    },
 };
 
 pub mod core;
 pub mod json;
+pub mod lsp;
 pub mod ql;
+pub mod repl;
 
 #[cfg(test)]
 mod tests;
@@ -20,3 +26,11 @@ pub fn parse_valid_schema_from_str(
     schema.validate_spreadsheet_schema()?;
     Ok(schema)
 }
+
+/// Parses and validates the schema file at `path`, resolving its imports
+/// relative to it.
+pub fn parse_valid_schema_from_file(
+    path: &str,
+) -> anyhow::Result<SpreadsheetSchema> {
+    parse_schema_with_loader(path, &FsLoader)
+}
@@ -0,0 +1,717 @@
+use std::{collections::HashMap, rc::Rc};
+
+use rlrl::parse::TokenQueue;
+use serde_json::{Value, json};
+
+use crate::{
+    core::schema::{
+        DBL_TYPE_NAME, DECIMAL_TYPE_NAME, INT_TYPE_NAME, INT64_TYPE_NAME,
+        STR_TYPE_NAME,
+    },
+    ql::{
+        Stmt, Symbol, SymbolTable,
+        import::{FsLoader, Importer, SymbolOrigins},
+        lex::{fmt_name, setup_lexer, unescape_str},
+        parse::Parse,
+    },
+};
+
+pub const BUILTIN_TYPE_NAMES: [&str; 5] = [
+    INT_TYPE_NAME,
+    INT64_TYPE_NAME,
+    DBL_TYPE_NAME,
+    DECIMAL_TYPE_NAME,
+    STR_TYPE_NAME,
+];
+
+/// A position in a document, with the character offset counted in UTF-16 code
+/// units as LSP requires.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Position {
+    pub line: u32,
+    pub character: u32,
+}
+
+impl Position {
+    pub fn from_json(value: &Value) -> Option<Self> {
+        Some(Self {
+            line: value.get("line")?.as_u64()? as u32,
+            character: value.get("character")?.as_u64()? as u32,
+        })
+    }
+
+    pub fn to_json(self) -> Value {
+        json!({"line": self.line, "character": self.character})
+    }
+}
+
+/// A range in a document.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Range {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Range {
+    pub fn contains(&self, pos: Position) -> bool {
+        self.start <= pos && pos <= self.end
+    }
+
+    pub fn to_json(self) -> Value {
+        json!({"start": self.start.to_json(), "end": self.end.to_json()})
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum SpanKind {
+    Word,
+    Str,
+    Number,
+    Punct(char),
+}
+
+/// A token with its location in the source. Only used to locate symbols; the
+/// statements themselves are parsed with the real lexer and parser.
+#[derive(Clone, Debug)]
+struct Span {
+    kind: SpanKind,
+    // the identifier or unquoted string value
+    text: Rc<str>,
+    range: Range,
+    start_byte: usize,
+    end_byte: usize,
+}
+
+struct Scanner<'a> {
+    src: &'a str,
+    byte: usize,
+    pos: Position,
+}
+
+impl<'a> Scanner<'a> {
+    fn peek(&self) -> Option<char> {
+        self.src[self.byte..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.byte += c.len_utf8();
+        if c == '\n' {
+            self.pos.line += 1;
+            self.pos.character = 0;
+        } else {
+            self.pos.character += c.len_utf16() as u32;
+        }
+        Some(c)
+    }
+
+    fn bump_while(&mut self, f: impl Fn(char) -> bool) {
+        while let Some(c) = self.peek()
+            && f(c)
+        {
+            self.bump();
+        }
+    }
+
+    fn scan(src: &'a str) -> Vec<Span> {
+        let mut scanner = Scanner {
+            src,
+            byte: 0,
+            pos: Position {
+                line: 0,
+                character: 0,
+            },
+        };
+        let mut spans = vec![];
+
+        while let Some(c) = scanner.peek() {
+            let start = scanner.pos;
+            let start_byte = scanner.byte;
+
+            let kind = if c.is_whitespace() {
+                scanner.bump();
+                continue;
+            } else if src[start_byte..].starts_with("//") {
+                scanner.bump_while(|c| c != '\n' && c != '\r');
+                continue;
+            } else if c.is_ascii_alphabetic() {
+                scanner.bump_while(|c| c.is_ascii_alphanumeric() || c == '_');
+                SpanKind::Word
+            } else if c.is_ascii_digit()
+                || ((c == '-' || c == '.')
+                    && src[start_byte + 1..]
+                        .trim_start_matches('.')
+                        .starts_with(|c: char| c.is_ascii_digit()))
+            {
+                scanner.bump();
+                // exponents may be signed, as in `1e-6`
+                let mut prev = c;
+                while let Some(c) = scanner.peek()
+                    && (c.is_ascii_alphanumeric()
+                        || c == '.'
+                        || c == '_'
+                        || (matches!(c, '+' | '-')
+                            && matches!(prev, 'e' | 'E')))
+                {
+                    scanner.bump();
+                    prev = c;
+                }
+                SpanKind::Number
+            } else if c == '"' {
+                scanner.bump();
+                while let Some(c) = scanner.bump() {
+                    match c {
+                        '\\' => {
+                            scanner.bump();
+                        }
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+                SpanKind::Str
+            } else {
+                scanner.bump();
+                SpanKind::Punct(c)
+            };
+
+            let raw = &src[start_byte..scanner.byte];
+            let text = match kind {
+                SpanKind::Str if raw.len() >= 2 && raw.ends_with('"') => {
+                    &raw[1..raw.len() - 1]
+                }
+                SpanKind::Str => &raw[1..],
+                _ => raw,
+            };
+            // names given as strings may contain escapes; malformed ones are
+            // reported by the real lexer
+            let text = match kind {
+                SpanKind::Str => {
+                    unescape_str(text).unwrap_or_else(|_| text.to_string())
+                }
+                _ => text.to_string(),
+            };
+
+            spans.push(Span {
+                kind,
+                text: text.into(),
+                range: Range {
+                    start,
+                    end: scanner.pos,
+                },
+                start_byte,
+                end_byte: scanner.byte,
+            });
+        }
+
+        spans
+    }
+}
+
+/// The role a name plays where it appears in the document.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Role {
+    TypeDef,
+    TableDef,
+    ViewDef,
+    // the name of the table the column belongs to
+    ColumnDef(Rc<str>),
+    // the name of the type definition or `table.column` the reference is in
+    TypeRef(Rc<str>),
+    // a table or view named by an alter statement or query
+    TableRef,
+}
+
+/// An occurrence of a name in the document.
+#[derive(Clone, Debug)]
+pub struct NameRef {
+    pub name: Rc<str>,
+    pub role: Role,
+    pub range: Range,
+}
+
+impl NameRef {
+    fn is_type(&self) -> bool {
+        matches!(self.role, Role::TypeDef | Role::TypeRef(_))
+    }
+
+    fn is_table(&self) -> bool {
+        matches!(self.role, Role::TableDef | Role::ViewDef | Role::TableRef)
+    }
+}
+
+/// A problem found in the document.
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    pub range: Range,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "range": self.range.to_json(),
+            "severity": 1,
+            "source": "ftables",
+            "message": self.message
+        })
+    }
+}
+
+// A top-level statement and the names it defines, for document symbols.
+struct StmtOutline {
+    def: NameRef,
+    range: Range,
+    columns: Vec<NameRef>,
+}
+
+/// The result of analysing a document: its diagnostics, the names it defines
+/// and references, and the symbol table built by the parser.
+pub struct Analysis {
+    pub diagnostics: Vec<Diagnostic>,
+    pub names: Vec<NameRef>,
+    outline: Vec<StmtOutline>,
+    symtable: SymbolTable,
+}
+
+fn name_ref(span: &Span, role: Role) -> Option<NameRef> {
+    match span.kind {
+        SpanKind::Word | SpanKind::Str => Some(NameRef {
+            name: span.text.clone(),
+            role,
+            range: span.range,
+        }),
+        _ => None,
+    }
+}
+
+// Collects the tables and views a query names: after `from`, `join` or
+// `into`, and before the `.` of a qualified column such as `Movies.movieId`.
+fn query_table_refs(spans: &[Span]) -> Vec<NameRef> {
+    let mut refs = vec![];
+    for (i, span) in spans.iter().enumerate() {
+        let follows_kwd = i > 0
+            && spans[i - 1].kind == SpanKind::Word
+            && matches!(&*spans[i - 1].text, "from" | "join" | "into");
+        let is_qualifier = span.kind == SpanKind::Word
+            && spans
+                .get(i + 1)
+                .is_some_and(|next| next.kind == SpanKind::Punct('.'));
+        if (follows_kwd || is_qualifier)
+            && let Some(table_ref) = name_ref(span, Role::TableRef)
+        {
+            refs.push(table_ref);
+        }
+    }
+    refs
+}
+
+// Collects the names defined and referenced in a single statement, and the
+// outline entry of the type, table or view it defines.
+fn outline_stmt(
+    spans: &[Span],
+    range: Range,
+) -> Option<(Option<StmtOutline>, Vec<NameRef>)> {
+    let first = spans.first()?;
+    let mut refs = vec![];
+
+    match (first.kind, &*first.text) {
+        (SpanKind::Word, "type") => {
+            let def = name_ref(spans.get(1)?, Role::TypeDef)?;
+            if let Some(type_ref) = spans.get(2).and_then(|span| {
+                name_ref(span, Role::TypeRef(def.name.clone()))
+            }) {
+                refs.push(type_ref);
+            }
+            refs.push(def.clone());
+            Some((
+                Some(StmtOutline {
+                    def,
+                    range,
+                    columns: vec![],
+                }),
+                refs,
+            ))
+        }
+        (SpanKind::Word, "table") => {
+            let def = name_ref(spans.get(1)?, Role::TableDef)?;
+            let mut columns = vec![];
+            let mut depth = 0;
+            for (i, span) in spans.iter().enumerate() {
+                match span.kind {
+                    SpanKind::Punct('(') => depth += 1,
+                    SpanKind::Punct(')') => depth -= 1,
+                    SpanKind::Punct(':') if depth == 1 && i > 0 => {
+                        let Some(column) = name_ref(
+                            &spans[i - 1],
+                            Role::ColumnDef(def.name.clone()),
+                        ) else {
+                            continue;
+                        };
+                        let context: Rc<str> =
+                            format!("{}.{}", def.name, column.name).into();
+                        if let Some(type_ref) =
+                            spans.get(i + 1).and_then(|span| {
+                                name_ref(span, Role::TypeRef(context))
+                            })
+                        {
+                            refs.push(type_ref);
+                        }
+                        columns.push(column);
+                    }
+                    _ => {}
+                }
+            }
+            refs.push(def.clone());
+            refs.extend(columns.iter().cloned());
+            Some((
+                Some(StmtOutline {
+                    def,
+                    range,
+                    columns,
+                }),
+                refs,
+            ))
+        }
+        (SpanKind::Word, "view") => {
+            let def = name_ref(spans.get(1)?, Role::ViewDef)?;
+            refs.push(def.clone());
+            refs.extend(query_table_refs(spans.get(2..)?));
+            Some((
+                Some(StmtOutline {
+                    def,
+                    range,
+                    columns: vec![],
+                }),
+                refs,
+            ))
+        }
+        // `alter table Users ...`, `drop table Users` and `rename table Users
+        // to People`
+        (SpanKind::Word, "alter" | "drop" | "rename") => {
+            let table = spans.get(2)?;
+            refs.extend(name_ref(table, Role::TableRef));
+            if &*first.text == "rename" {
+                refs.extend(
+                    spans
+                        .get(4)
+                        .and_then(|span| name_ref(span, Role::TableRef)),
+                );
+            }
+            // the types of added and altered columns, as in `add column
+            // age: int` or `alter column age type ageType`
+            for (i, span) in spans.iter().enumerate().skip(4) {
+                let is_type_kwd = match span.kind {
+                    SpanKind::Punct(':') => true,
+                    SpanKind::Word => &*span.text == "type",
+                    _ => false,
+                };
+                if is_type_kwd
+                    && let Some(type_ref) = spans.get(i + 1).and_then(|next| {
+                        let context: Rc<str> =
+                            format!("{}.{}", table.text, spans[i - 1].text)
+                                .into();
+                        name_ref(next, Role::TypeRef(context))
+                    })
+                {
+                    refs.push(type_ref);
+                }
+            }
+            Some((None, refs))
+        }
+        (SpanKind::Word, "update") => {
+            refs.extend(
+                spans.get(1).and_then(|span| name_ref(span, Role::TableRef)),
+            );
+            refs.extend(query_table_refs(spans));
+            Some((None, refs))
+        }
+        (SpanKind::Word, "select" | "insert" | "delete") => {
+            Some((None, query_table_refs(spans)))
+        }
+        _ => None,
+    }
+}
+
+// Parses and validates the `idx`th statement of the file at `path` against
+// the shared symbol table.
+fn check_stmt(
+    src: &str,
+    idx: usize,
+    path: Option<&str>,
+    symtable: &mut SymbolTable,
+    origins: &mut SymbolOrigins,
+) -> anyhow::Result<()> {
+    let mut tq = TokenQueue::from(setup_lexer().lex(src)?);
+    let stmt = tq.parse_with_mut(Stmt::parse, symtable)?;
+    if !tq.is_consumed() {
+        return Err(anyhow::anyhow!("Unexpected input after statement"));
+    }
+    match stmt {
+        Stmt::Schema(_) if idx > 0 => Err(anyhow::anyhow!(
+            "Schema header must be the first statement!"
+        )),
+        Stmt::Schema(_) => Ok(()),
+        Stmt::Import(import) => match path {
+            Some(path) => Importer::new(&FsLoader)
+                .import(path, &import, symtable, origins),
+            None => Err(anyhow::anyhow!(
+                "Couldn't resolve import of {} outside a file!",
+                import.get_path()
+            )),
+        },
+        Stmt::TypeDef(_, data_type) => data_type.validate_data_type(),
+        Stmt::TableSchema(table) => table.validate_table_schema(),
+        Stmt::Alter(alter) => match alter
+            .get_new_table_name()
+            .and_then(|name| symtable.get(&name))
+        {
+            Some(Symbol::TableSchema(table)) => table.validate_table_schema(),
+            _ => Ok(()),
+        },
+        // queries are checked against the symbol table as they're parsed
+        Stmt::View(_)
+        | Stmt::Select(_)
+        | Stmt::Insert(_)
+        | Stmt::Update(_)
+        | Stmt::Delete(_) => Ok(()),
+    }
+}
+
+impl Analysis {
+    pub fn new(src: &str) -> Self {
+        Self::new_in_file(src, None)
+    }
+
+    /// Analyses `src` as the contents of the file at `path`, so its imports
+    /// can be resolved relative to it.
+    pub fn new_in_file(src: &str, path: Option<&str>) -> Self {
+        let spans = Scanner::scan(src);
+        let mut symtable = HashMap::new();
+        let mut origins = HashMap::new();
+        let mut diagnostics = vec![];
+        let mut names = vec![];
+        let mut outline = vec![];
+
+        let mut start = 0;
+        let mut idx = 0;
+        while start < spans.len() {
+            let end = spans[start..]
+                .iter()
+                .position(|span| span.kind == SpanKind::Punct(';'))
+                .map(|i| start + i);
+            let stmt_spans = &spans[start..end.unwrap_or(spans.len())];
+
+            if let (Some(first), Some(last)) =
+                (stmt_spans.first(), stmt_spans.last())
+            {
+                let range = Range {
+                    start: first.range.start,
+                    end: match end {
+                        Some(end) => spans[end].range.end,
+                        None => last.range.end,
+                    },
+                };
+                let stmt_src = &src[first.start_byte..last.end_byte];
+
+                if let Err(e) =
+                    check_stmt(stmt_src, idx, path, &mut symtable, &mut origins)
+                {
+                    diagnostics.push(Diagnostic {
+                        range,
+                        message: format!("{e:#}"),
+                    });
+                } else if end.is_none() {
+                    diagnostics.push(Diagnostic {
+                        range: last.range,
+                        message: "Expected ';'".into(),
+                    });
+                }
+
+                if let Some((stmt_outline, refs)) =
+                    outline_stmt(stmt_spans, range)
+                {
+                    outline.extend(stmt_outline);
+                    names.extend(refs);
+                }
+            }
+
+            start = end.map_or(spans.len(), |end| end + 1);
+            idx += 1;
+        }
+
+        Self {
+            diagnostics,
+            names,
+            outline,
+            symtable,
+        }
+    }
+
+    /// Returns the name at `pos`, if any.
+    pub fn name_at(&self, pos: Position) -> Option<&NameRef> {
+        self.names.iter().find(|name| name.range.contains(pos))
+    }
+
+    /// Returns the definition of the name at `pos`.
+    pub fn definition(&self, pos: Position) -> Option<&NameRef> {
+        let name = self.name_at(pos)?;
+        let is_type = name.is_type();
+        self.names.iter().find(|def| {
+            def.name == name.name
+                && match def.role {
+                    Role::TypeDef => is_type,
+                    Role::TableDef | Role::ViewDef => name.is_table(),
+                    _ => false,
+                }
+        })
+    }
+
+    /// Returns the hover text for the name at `pos`: the `Display` of the
+    /// resolved table, column or data type.
+    pub fn hover(&self, pos: Position) -> Option<String> {
+        let name = self.name_at(pos)?;
+        match &name.role {
+            Role::TableDef | Role::ViewDef | Role::TableRef => {
+                match self.symtable.get(&name.name)? {
+                    Symbol::TableSchema(table) => {
+                        Some(format!("table {table}"))
+                    }
+                    Symbol::View(view) => Some(format!("view {view}")),
+                    _ => None,
+                }
+            }
+            Role::ColumnDef(table_name) => {
+                let column = self.get_column(table_name, &name.name)?;
+                Some(column.to_string())
+            }
+            Role::TypeDef => match self.symtable.get(&name.name)? {
+                Symbol::DataType(dtype) => {
+                    Some(format!("type {} {dtype}", name.name))
+                }
+                _ => None,
+            },
+            Role::TypeRef(context) => {
+                if let Some(Symbol::DataType(dtype)) =
+                    self.symtable.get(&name.name)
+                {
+                    return Some(format!("type {} {dtype}", name.name));
+                }
+                // built-in type: show the resolved type of the enclosing
+                // definition
+                match context.split_once('.') {
+                    Some((table_name, column_name)) => Some(
+                        self.get_column(table_name, column_name)?
+                            .get_type()
+                            .to_string(),
+                    ),
+                    None => match self.symtable.get(context)? {
+                        Symbol::DataType(dtype) => Some(dtype.to_string()),
+                        _ => None,
+                    },
+                }
+            }
+        }
+    }
+
+    fn get_column(
+        &self,
+        table_name: &str,
+        column_name: &str,
+    ) -> Option<&crate::core::schema::ColumnSchema> {
+        match self.symtable.get(table_name)? {
+            Symbol::TableSchema(table) => table
+                .get_columns()
+                .iter()
+                .find(|column| &*column.get_name() == column_name),
+            _ => None,
+        }
+    }
+
+    /// Returns the completion items for type names: the built-in types
+    /// followed by the user-defined aliases.
+    pub fn completions(&self) -> Vec<Value> {
+        let mut items: Vec<Value> = BUILTIN_TYPE_NAMES
+            .iter()
+            .map(|name| json!({"label": name, "kind": 14}))
+            .collect();
+        for name in &self.names {
+            if name.role == Role::TypeDef
+                && let Some(Symbol::DataType(dtype)) =
+                    self.symtable.get(&name.name)
+            {
+                items.push(json!({
+                    "label": *name.name,
+                    "kind": 25,
+                    "detail": dtype.to_string()
+                }));
+            }
+        }
+        items
+    }
+
+    /// Returns the document symbols: tables with their columns as children,
+    /// views and type definitions.
+    pub fn document_symbols(&self) -> Vec<Value> {
+        self.outline
+            .iter()
+            .map(|stmt| {
+                let children: Vec<Value> = stmt
+                    .columns
+                    .iter()
+                    .map(|column| {
+                        json!({
+                            "name": *column.name,
+                            "kind": 8,
+                            "range": column.range.to_json(),
+                            "selectionRange": column.range.to_json()
+                        })
+                    })
+                    .collect();
+                json!({
+                    "name": *stmt.def.name,
+                    "kind": match stmt.def.role {
+                        Role::TableDef => 5,
+                        Role::ViewDef => 11,
+                        _ => 26,
+                    },
+                    "range": stmt.range.to_json(),
+                    "selectionRange": stmt.def.range.to_json(),
+                    "children": children
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the edits that rename the type, table or view at `pos` to
+    /// `new_name`, including where statements and queries refer to it.
+    pub fn rename(
+        &self,
+        pos: Position,
+        new_name: &str,
+    ) -> anyhow::Result<Vec<(Range, String)>> {
+        let def = self.definition(pos).ok_or(anyhow::anyhow!(
+            "Only types, tables and views can be renamed"
+        ))?;
+        if BUILTIN_TYPE_NAMES.contains(&new_name) {
+            return Err(anyhow::anyhow!("Can't rename to built-in type name"));
+        }
+        if self.symtable.contains_key(new_name) {
+            return Err(anyhow::anyhow!(
+                "Symbol {new_name} is already assigned!"
+            ));
+        }
+
+        Ok(self
+            .names
+            .iter()
+            .filter(|name| {
+                name.name == def.name
+                    && match def.role {
+                        Role::TypeDef => name.is_type(),
+                        _ => name.is_table(),
+                    }
+            })
+            .map(|name| (name.range, fmt_name(new_name)))
+            .collect())
+    }
+}
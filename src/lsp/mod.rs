@@ -0,0 +1,274 @@
+pub mod analysis;
+
+use std::{
+    collections::HashMap,
+    io::{BufRead, Write},
+};
+
+use serde_json::{Value, json};
+
+use crate::lsp::analysis::{Analysis, Position};
+
+/// Reads a single `Content-Length` framed JSON-RPC message. Returns `None` at
+/// end of input.
+pub fn read_message(
+    reader: &mut impl BufRead,
+) -> anyhow::Result<Option<Value>> {
+    let mut content_length = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':')
+            && name.eq_ignore_ascii_case("Content-Length")
+        {
+            content_length = Some(value.trim().parse::<usize>()?);
+        }
+    }
+
+    let content_length = content_length
+        .ok_or(anyhow::anyhow!("Missing Content-Length header"))?;
+    let mut buf = vec![0; content_length];
+    reader.read_exact(&mut buf)?;
+    Ok(Some(serde_json::from_slice(&buf)?))
+}
+
+/// Writes a single `Content-Length` framed JSON-RPC message.
+pub fn write_message(
+    writer: &mut impl Write,
+    msg: &Value,
+) -> anyhow::Result<()> {
+    let body = msg.to_string();
+    write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// A language server for the DDL, holding the text of every open document.
+#[derive(Default)]
+pub struct Server {
+    documents: HashMap<String, String>,
+    shutdown: bool,
+}
+
+fn text_document_uri(params: &Value) -> anyhow::Result<String> {
+    params["textDocument"]["uri"]
+        .as_str()
+        .map(|uri| uri.to_string())
+        .ok_or(anyhow::anyhow!("Missing textDocument.uri"))
+}
+
+// Returns the filesystem path of a `file://` URI.
+fn file_path(uri: &str) -> Option<&str> {
+    uri.strip_prefix("file://")
+}
+
+fn position(params: &Value) -> anyhow::Result<Position> {
+    Position::from_json(&params["position"])
+        .ok_or(anyhow::anyhow!("Missing position"))
+}
+
+impl Server {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn analyse(&self, params: &Value) -> anyhow::Result<Analysis> {
+        let uri = text_document_uri(params)?;
+        let text = self
+            .documents
+            .get(&uri)
+            .ok_or(anyhow::anyhow!("Unknown document {uri}"))?;
+        Ok(Analysis::new_in_file(text, file_path(&uri)))
+    }
+
+    fn publish_diagnostics(&self, uri: &str) -> anyhow::Result<Value> {
+        let diagnostics: Vec<Value> = match self.documents.get(uri) {
+            Some(text) => Analysis::new_in_file(text, file_path(uri))
+                .diagnostics
+                .iter()
+                .map(|diagnostic| diagnostic.to_json())
+                .collect(),
+            None => vec![],
+        };
+        Ok(json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/publishDiagnostics",
+            "params": {"uri": uri, "diagnostics": diagnostics}
+        }))
+    }
+
+    /// Handles a notification, returning any notifications to send back.
+    fn handle_notification(
+        &mut self,
+        method: &str,
+        params: &Value,
+    ) -> anyhow::Result<Vec<Value>> {
+        match method {
+            "textDocument/didOpen" => {
+                let uri = text_document_uri(params)?;
+                let text = params["textDocument"]["text"]
+                    .as_str()
+                    .ok_or(anyhow::anyhow!("Missing textDocument.text"))?;
+                self.documents.insert(uri.clone(), text.to_string());
+                Ok(vec![self.publish_diagnostics(&uri)?])
+            }
+            "textDocument/didChange" => {
+                // the server only advertises full document sync, so the last
+                // change holds the whole text
+                let uri = text_document_uri(params)?;
+                let text = params["contentChanges"]
+                    .as_array()
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change["text"].as_str())
+                    .ok_or(anyhow::anyhow!("Missing contentChanges"))?;
+                self.documents.insert(uri.clone(), text.to_string());
+                Ok(vec![self.publish_diagnostics(&uri)?])
+            }
+            "textDocument/didClose" => {
+                let uri = text_document_uri(params)?;
+                self.documents.remove(&uri);
+                Ok(vec![self.publish_diagnostics(&uri)?])
+            }
+            _ => Ok(vec![]),
+        }
+    }
+
+    /// Handles a request, returning its result, or `None` if the method isn't
+    /// supported.
+    fn handle_request(
+        &mut self,
+        method: &str,
+        params: &Value,
+    ) -> Option<anyhow::Result<Value>> {
+        let result = match method {
+            "initialize" => Ok(json!({
+                "capabilities": {
+                    "textDocumentSync": 1,
+                    "hoverProvider": true,
+                    "definitionProvider": true,
+                    "completionProvider": {},
+                    "documentSymbolProvider": true,
+                    "renameProvider": true
+                },
+                "serverInfo": {"name": "ftables-lsp"}
+            })),
+            "shutdown" => {
+                self.shutdown = true;
+                Ok(Value::Null)
+            }
+            "textDocument/hover" => self.hover(params),
+            "textDocument/definition" => self.definition(params),
+            "textDocument/completion" => self
+                .analyse(params)
+                .map(|analysis| Value::Array(analysis.completions())),
+            "textDocument/documentSymbol" => self
+                .analyse(params)
+                .map(|analysis| Value::Array(analysis.document_symbols())),
+            "textDocument/rename" => self.rename(params),
+            _ => return None,
+        };
+        Some(result)
+    }
+
+    fn hover(&self, params: &Value) -> anyhow::Result<Value> {
+        Ok(match self.analyse(params)?.hover(position(params)?) {
+            Some(text) => json!({
+                "contents": {"kind": "plaintext", "value": text}
+            }),
+            None => Value::Null,
+        })
+    }
+
+    fn definition(&self, params: &Value) -> anyhow::Result<Value> {
+        let uri = text_document_uri(params)?;
+        Ok(match self.analyse(params)?.definition(position(params)?) {
+            Some(def) => json!({"uri": uri, "range": def.range.to_json()}),
+            None => Value::Null,
+        })
+    }
+
+    fn rename(&self, params: &Value) -> anyhow::Result<Value> {
+        let uri = text_document_uri(params)?;
+        let new_name = params["newName"]
+            .as_str()
+            .ok_or(anyhow::anyhow!("Missing newName"))?;
+        let edits: Vec<Value> = self
+            .analyse(params)?
+            .rename(position(params)?, new_name)?
+            .into_iter()
+            .map(|(range, text)| {
+                json!({"range": range.to_json(), "newText": text})
+            })
+            .collect();
+        Ok(json!({"changes": {uri: edits}}))
+    }
+
+    /// Handles a single message, returning the messages to send back and
+    /// whether the server should exit.
+    pub fn handle_message(&mut self, msg: &Value) -> (Vec<Value>, bool) {
+        let method = msg["method"].as_str().unwrap_or("");
+        let params = &msg["params"];
+
+        if method == "exit" {
+            return (vec![], true);
+        }
+
+        match msg.get("id") {
+            Some(id) => {
+                let response = match self.handle_request(method, params) {
+                    Some(Ok(result)) => {
+                        json!({"jsonrpc": "2.0", "id": id, "result": result})
+                    }
+                    Some(Err(e)) => json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": {"code": -32603, "message": format!("{e:#}")}
+                    }),
+                    None => json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": {
+                            "code": -32601,
+                            "message": format!("Unhandled method {method}")
+                        }
+                    }),
+                };
+                (vec![response], false)
+            }
+            None => match self.handle_notification(method, params) {
+                Ok(msgs) => (msgs, false),
+                Err(_) => (vec![], false),
+            },
+        }
+    }
+
+    /// Serves requests from `reader` until the client sends `exit` or closes
+    /// the stream.
+    pub fn run(
+        &mut self,
+        reader: &mut impl BufRead,
+        writer: &mut impl Write,
+    ) -> anyhow::Result<()> {
+        while let Some(msg) = read_message(reader)? {
+            let (out, exit) = self.handle_message(&msg);
+            for msg in out {
+                write_message(writer, &msg)?;
+            }
+            if exit {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns `true` once the client has requested a shutdown.
+    pub fn is_shutdown(&self) -> bool {
+        self.shutdown
+    }
+}
@@ -0,0 +1,247 @@
+use std::rc::Rc;
+
+use crate::{
+    core::{
+        migrate::MigrationOp,
+        schema::{
+            ColumnSchema, SharedDataType, TableSchema,
+            computed::rename_formula_refs,
+        },
+    },
+    ql::{Symbol, SymbolTable},
+};
+
+/// A statement that changes an existing table, so a schema's history can be
+/// written as an append-only script.
+///
+/// `alter table Users add column email: str?;`, `alter table Users drop column
+/// email;`, `alter table Users rename column name to fullName;` and `alter
+/// table Users alter column age type int<0, 150>;` change a table's columns,
+/// while `drop table Users;` and `rename table Users to People;` change the
+/// table itself.
+#[derive(Clone)]
+pub enum AlterStmt {
+    AddColumn {
+        table_name: Rc<str>,
+        column: ColumnSchema,
+    },
+    DropColumn {
+        table_name: Rc<str>,
+        column_name: Rc<str>,
+    },
+    RenameColumn {
+        table_name: Rc<str>,
+        old_name: Rc<str>,
+        new_name: Rc<str>,
+    },
+    AlterColumnType {
+        table_name: Rc<str>,
+        column_name: Rc<str>,
+        data_type: SharedDataType,
+    },
+    DropTable(Rc<str>),
+    RenameTable {
+        old_name: Rc<str>,
+        new_name: Rc<str>,
+    },
+}
+
+fn column_idx(table: &TableSchema, column_name: &str) -> anyhow::Result<usize> {
+    table
+        .get_columns()
+        .iter()
+        .position(|col| &*col.get_name() == column_name)
+        .ok_or(anyhow::anyhow!(
+            "Table {} has no column {column_name}!",
+            table.get_name()
+        ))
+}
+
+fn check_new_column(
+    table: &TableSchema,
+    column_name: &str,
+) -> anyhow::Result<()> {
+    if table.get_column_by_name(column_name).is_some() {
+        return Err(anyhow::anyhow!(
+            "Column {column_name} is already defined in table {}!",
+            table.get_name()
+        ));
+    }
+    Ok(())
+}
+
+impl AlterStmt {
+    /// Returns the name of the table the statement changes.
+    pub fn get_table_name(&self) -> Rc<str> {
+        match self {
+            Self::AddColumn { table_name, .. }
+            | Self::DropColumn { table_name, .. }
+            | Self::RenameColumn { table_name, .. }
+            | Self::AlterColumnType { table_name, .. }
+            | Self::DropTable(table_name)
+            | Self::RenameTable {
+                old_name: table_name,
+                ..
+            } => table_name.clone(),
+        }
+    }
+
+    /// Returns the name of the table after the statement, or `None` if the
+    /// statement drops it.
+    pub fn get_new_table_name(&self) -> Option<Rc<str>> {
+        match self {
+            Self::DropTable(_) => None,
+            Self::RenameTable { new_name, .. } => Some(new_name.clone()),
+            _ => Some(self.get_table_name()),
+        }
+    }
+
+    /// Applies the statement to `table`, returning the changed table or
+    /// `None` if the statement drops it. The changed table is validated, so
+    /// defaults must fit a column's new type and formulas can't refer to
+    /// dropped columns.
+    pub fn apply(
+        &self,
+        table: &TableSchema,
+    ) -> anyhow::Result<Option<TableSchema>> {
+        let mut columns = table.get_columns().to_vec();
+        let table_name = match self {
+            Self::AddColumn { column, .. } => {
+                check_new_column(table, &column.get_name())?;
+                columns.push(column.clone());
+                table.get_name()
+            }
+            Self::DropColumn { column_name, .. } => {
+                columns.remove(column_idx(table, column_name)?);
+                table.get_name()
+            }
+            Self::RenameColumn {
+                old_name, new_name, ..
+            } => {
+                let idx = column_idx(table, old_name)?;
+                check_new_column(table, new_name)?;
+                columns[idx] = ColumnSchema::new(
+                    new_name.clone(),
+                    columns[idx].get_type(),
+                    columns[idx].get_default_value().cloned(),
+                )
+                .with_formula(columns[idx].get_formula().cloned());
+                rename_formula_refs(&mut columns, old_name, new_name);
+                table.get_name()
+            }
+            Self::AlterColumnType {
+                column_name,
+                data_type,
+                ..
+            } => {
+                let idx = column_idx(table, column_name)?;
+                columns[idx] = ColumnSchema::new(
+                    column_name.clone(),
+                    data_type.clone(),
+                    columns[idx].get_default_value().cloned(),
+                )
+                .with_formula(columns[idx].get_formula().cloned());
+                table.get_name()
+            }
+            Self::DropTable(_) => return Ok(None),
+            Self::RenameTable { new_name, .. } => new_name.clone(),
+        };
+        let table = TableSchema::new(table_name, columns);
+        table.validate_table_schema()?;
+        Ok(Some(table))
+    }
+
+    /// Applies the statement to the table it names in `symtable`. Tables that
+    /// views select from can't be altered, as the views' queries and columns
+    /// were checked against them as they were.
+    pub fn apply_to_symtable(
+        &self,
+        symtable: &mut SymbolTable,
+    ) -> anyhow::Result<()> {
+        let table_name = self.get_table_name();
+        let table = match symtable.get(&table_name) {
+            Some(Symbol::TableSchema(table)) => table.clone(),
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "Table {table_name} is not defined!"
+                ));
+            }
+        };
+
+        let dependent_view = symtable
+            .values()
+            .filter_map(|symbol| match symbol {
+                Symbol::View(view)
+                    if view
+                        .get_query()
+                        .get_table_names()
+                        .contains(&table_name) =>
+                {
+                    Some(view.get_name())
+                }
+                _ => None,
+            })
+            .min();
+        if let Some(view_name) = dependent_view {
+            return Err(anyhow::anyhow!(
+                "Can't alter table {table_name}, as view {view_name} selects from it!"
+            ));
+        }
+
+        match self.apply(&table)? {
+            Some(table) => {
+                let new_name = table.get_name();
+                if new_name != table_name && symtable.contains_key(&new_name) {
+                    return Err(anyhow::anyhow!(
+                        "Symbol {new_name} is already assigned!"
+                    ));
+                }
+                symtable.remove(&table_name);
+                symtable.insert(new_name, Symbol::TableSchema(Rc::new(table)));
+            }
+            None => {
+                symtable.remove(&table_name);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the migration operation that makes stored rows follow the
+    /// statement.
+    pub fn to_migration_op(&self) -> MigrationOp {
+        match self.clone() {
+            Self::AddColumn { table_name, column } => {
+                MigrationOp::AddColumn { table_name, column }
+            }
+            Self::DropColumn {
+                table_name,
+                column_name,
+            } => MigrationOp::DropColumn {
+                table_name,
+                column_name,
+            },
+            Self::RenameColumn {
+                table_name,
+                old_name,
+                new_name,
+            } => MigrationOp::RenameColumn {
+                table_name,
+                old_name,
+                new_name,
+            },
+            Self::AlterColumnType {
+                table_name,
+                column_name,
+                data_type,
+            } => MigrationOp::ChangeType {
+                table_name,
+                column_name,
+                new_type: data_type,
+            },
+            Self::DropTable(table_name) => MigrationOp::DropTable(table_name),
+            Self::RenameTable { old_name, new_name } => {
+                MigrationOp::RenameTable { old_name, new_name }
+            }
+        }
+    }
+}
@@ -0,0 +1,452 @@
+use std::rc::Rc;
+
+use crate::{
+    core::{
+        data::{Dataset, Row, TableData},
+        schema::{
+            ColumnSchema, DataType, DblDataType, DecimalDataType,
+            SharedDataType, SharedTableSchema, TableSchema,
+            computed::convert_result,
+        },
+    },
+    ql::{
+        Symbol, SymbolTable,
+        expr::{Aggregate, Cond, Expr, ExprType, ValueKind},
+        lex::Literal,
+        query::get_table,
+    },
+};
+
+/// An `insert` statement, such as `insert into Users (userId, userName)
+/// values (1, "ann"), (2, "bob");`. Values are constant expressions, or
+/// `None` for `null`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InsertStmt {
+    table_name: Rc<str>,
+    // `None` if every column that isn't computed is given a value, in order
+    column_names: Option<Vec<Rc<str>>>,
+    rows: Vec<Vec<Option<Expr>>>,
+}
+
+/// The assignment of a value, or `null` if `value` is `None`, to a column in
+/// an `update` statement.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Assignment {
+    pub column_name: Rc<str>,
+    pub value: Option<Expr>,
+}
+
+impl Assignment {
+    pub fn new(column_name: Rc<str>, value: Option<Expr>) -> Self {
+        Self { column_name, value }
+    }
+}
+
+/// An `update` statement, such as `update Users set bio = null where userId
+/// = 1;`. Without a `where` condition every row is updated.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UpdateStmt {
+    table_name: Rc<str>,
+    assignments: Vec<Assignment>,
+    filter: Option<Cond>,
+}
+
+/// A `delete` statement, such as `delete from Users where userId = 1;`.
+/// Without a `where` condition every row is deleted.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeleteStmt {
+    table_name: Rc<str>,
+    filter: Option<Cond>,
+}
+
+// Returns the table named `name`, unless it's a view, whose rows come from
+// its query so can't be changed.
+fn get_writable_table(
+    symtable: &SymbolTable,
+    name: &str,
+) -> anyhow::Result<SharedTableSchema> {
+    if let Some(Symbol::View(_)) = symtable.get(name) {
+        return Err(anyhow::anyhow!("View {name} is read-only!"));
+    }
+    get_table(symtable, name)
+}
+
+// Returns the index of the column `name` of `table`, if values can be written
+// to it.
+fn get_writable_idx(table: &TableSchema, name: &str) -> anyhow::Result<usize> {
+    let idx = table.get_column_idx(name).ok_or(anyhow::anyhow!(
+        "Table {} has no column {name}!",
+        table.get_name()
+    ))?;
+    if table.get_columns()[idx].is_computed() {
+        return Err(anyhow::anyhow!(
+            "Column {name} is computed, so can't be set!"
+        ));
+    }
+    Ok(idx)
+}
+
+// Aggregates summarise groups of rows, so can't be used by statements that
+// change rows one at a time.
+fn check_no_aggregates(aggregates: Vec<&Aggregate>) -> anyhow::Result<()> {
+    match aggregates.first() {
+        Some(aggregate) => Err(anyhow::anyhow!(
+            "Aggregate {aggregate} can't be used when changing rows!"
+        )),
+        None => Ok(()),
+    }
+}
+
+// Checks the values of `value` can be stored in `column`. Whether a null can
+// be is only known once the value is written.
+fn check_value(
+    column: &ColumnSchema,
+    value: &Option<Expr>,
+    column_type: &impl Fn(&str) -> Option<SharedDataType>,
+) -> anyhow::Result<()> {
+    let Some(expr) = value else {
+        return Ok(());
+    };
+    check_no_aggregates(expr.get_aggregates())?;
+    let dtype = column.get_type();
+    let expr_type = ExprType {
+        nullable: false,
+        ..expr.infer_type(column_type)?
+    };
+    // doubles and decimals are converted to the kind of number the column
+    // holds
+    let storable = expr_type.is_storable_in(&*dtype)
+        || matches!(
+            (expr_type.kind, ValueKind::of_type(&*dtype)),
+            (
+                ValueKind::Dbl | ValueKind::Decimal,
+                Some(ValueKind::Dbl | ValueKind::Decimal)
+            )
+        );
+    if !storable {
+        return Err(anyhow::anyhow!(
+            "Can't store {} in column {} of type {dtype}!",
+            expr_type.kind.get_name(),
+            column.get_name()
+        ));
+    }
+    Ok(())
+}
+
+// Converts a number to the kind of number its column holds.
+fn convert_value(lit: Literal, dtype: &dyn DataType) -> Literal {
+    let any = dtype.as_any();
+    match &lit {
+        Literal::Dbl(_) if any.is::<DecimalDataType>() => {
+            lit.get_decimal().map_or(lit, Literal::Decimal)
+        }
+        Literal::Decimal(_) if any.is::<DblDataType>() => {
+            lit.get_f64().map_or(lit, Literal::Dbl)
+        }
+        _ => convert_result(lit, dtype),
+    }
+}
+
+// Evaluates `value` for `column` and validates the result against the
+// column's type.
+fn eval_value(
+    column: &ColumnSchema,
+    value: &Option<Expr>,
+    column_value: &impl Fn(&str) -> anyhow::Result<Option<Literal>>,
+) -> anyhow::Result<Option<Literal>> {
+    let dtype = column.get_type();
+    let val = match value {
+        Some(expr) => expr
+            .eval(column_value)?
+            .map(|lit| convert_value(lit, &*dtype)),
+        None => None,
+    };
+    dtype
+        .validate_literal(val.as_ref())
+        .map_err(|e| anyhow::anyhow!("In column {}: {e}", column.get_name()))?;
+    Ok(val)
+}
+
+// Validates `row` against `table`, returning it with its computed columns
+// filled.
+fn complete_valid_row(table: &TableSchema, row: &Row) -> anyhow::Result<Row> {
+    if let Some(violation) = table.validate_row(row).first() {
+        return Err(anyhow::anyhow!("{violation}"));
+    }
+    Ok(table.complete_row(row).0)
+}
+
+fn get_table_data<'a>(
+    data: &'a mut Dataset,
+    table_name: &str,
+) -> anyhow::Result<&'a mut TableData> {
+    data.get_mut(table_name)
+        .ok_or(anyhow::anyhow!("Table {table_name} has no data!"))
+}
+
+// Returns the indexes of the rows of `data` that satisfy `filter`, or of every
+// row if there is no filter.
+fn get_matching_rows(
+    data: &TableData,
+    filter: Option<&Cond>,
+) -> anyhow::Result<Vec<usize>> {
+    let table = data.get_schema();
+    let mut row_idxs = vec![];
+    for (row_idx, row) in data.get_rows().iter().enumerate() {
+        if let Some(filter) = filter
+            && filter.eval(&row_value(&table, row))? != Some(true)
+        {
+            continue;
+        }
+        row_idxs.push(row_idx);
+    }
+    Ok(row_idxs)
+}
+
+// Returns a function looking up the value of a column in `row`.
+fn row_value<'a>(
+    table: &'a TableSchema,
+    row: &'a Row,
+) -> impl Fn(&str) -> anyhow::Result<Option<Literal>> + 'a {
+    move |name: &str| {
+        table
+            .get_column_idx(name)
+            .map(|idx| row[idx].clone())
+            .ok_or(anyhow::anyhow!("Unknown column {name}!"))
+    }
+}
+
+impl InsertStmt {
+    pub fn new(
+        table_name: Rc<str>,
+        column_names: Option<Vec<Rc<str>>>,
+        rows: Vec<Vec<Option<Expr>>>,
+    ) -> Self {
+        Self {
+            table_name,
+            column_names,
+            rows,
+        }
+    }
+
+    pub fn get_table_name(&self) -> Rc<str> {
+        self.table_name.clone()
+    }
+
+    /// Returns the columns given values, or `None` if every column that isn't
+    /// computed is, in order.
+    pub fn get_column_names(&self) -> Option<&[Rc<str>]> {
+        self.column_names.as_deref()
+    }
+
+    pub fn get_rows(&self) -> &[Vec<Option<Expr>>] {
+        &self.rows
+    }
+
+    // Returns the indexes of the columns given values, in order.
+    fn get_target_idxs(
+        &self,
+        table: &TableSchema,
+    ) -> anyhow::Result<Vec<usize>> {
+        let Some(names) = &self.column_names else {
+            return Ok((0..table.get_columns().len())
+                .filter(|idx| !table.get_columns()[*idx].is_computed())
+                .collect());
+        };
+        let mut idxs = vec![];
+        for name in names {
+            let idx = get_writable_idx(table, name)?;
+            if idxs.contains(&idx) {
+                return Err(anyhow::anyhow!(
+                    "Column {name} is given more than once!"
+                ));
+            }
+            idxs.push(idx);
+        }
+        Ok(idxs)
+    }
+
+    /// Checks the statement against the tables in `symtable`.
+    pub fn check(&self, symtable: &SymbolTable) -> anyhow::Result<()> {
+        let table = get_writable_table(symtable, &self.table_name)?;
+        let idxs = self.get_target_idxs(&table)?;
+        for values in &self.rows {
+            if values.len() != idxs.len() {
+                return Err(anyhow::anyhow!(
+                    "Expected {} values, got {}!",
+                    idxs.len(),
+                    values.len()
+                ));
+            }
+            // values can't refer to columns
+            for (idx, value) in idxs.iter().zip(values) {
+                check_value(&table.get_columns()[*idx], value, &|_| None)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Appends the rows to their table in `data`, returning how many were
+    /// inserted. Columns without a value take their default, or are null.
+    /// Nothing is inserted if any row doesn't conform to the table's schema.
+    pub fn execute(
+        &self,
+        symtable: &SymbolTable,
+        data: &mut Dataset,
+    ) -> anyhow::Result<usize> {
+        self.check(symtable)?;
+        let table_data = get_table_data(data, &self.table_name)?;
+        let table = table_data.get_schema();
+        let idxs = self.get_target_idxs(&table)?;
+
+        let mut new_rows = vec![];
+        for values in &self.rows {
+            let mut row: Row = table
+                .get_columns()
+                .iter()
+                .map(|col| col.get_default_value().cloned())
+                .collect();
+            for (idx, value) in idxs.iter().zip(values) {
+                row[*idx] =
+                    eval_value(&table.get_columns()[*idx], value, &|name| {
+                        Err(anyhow::anyhow!("Unknown column {name}!"))
+                    })?;
+            }
+            new_rows.push(complete_valid_row(&table, &row)?);
+        }
+
+        let count = new_rows.len();
+        table_data.append_rows(new_rows);
+        Ok(count)
+    }
+}
+
+impl UpdateStmt {
+    pub fn new(
+        table_name: Rc<str>,
+        assignments: Vec<Assignment>,
+        filter: Option<Cond>,
+    ) -> Self {
+        Self {
+            table_name,
+            assignments,
+            filter,
+        }
+    }
+
+    pub fn get_table_name(&self) -> Rc<str> {
+        self.table_name.clone()
+    }
+
+    pub fn get_assignments(&self) -> &[Assignment] {
+        &self.assignments
+    }
+
+    pub fn get_filter(&self) -> Option<&Cond> {
+        self.filter.as_ref()
+    }
+
+    /// Checks the statement against the tables in `symtable`.
+    pub fn check(&self, symtable: &SymbolTable) -> anyhow::Result<()> {
+        let table = get_writable_table(symtable, &self.table_name)?;
+        let column_type = |name: &str| {
+            table.get_column_by_name(name).map(|col| col.get_type())
+        };
+        let mut idxs = vec![];
+        for assignment in &self.assignments {
+            let idx = get_writable_idx(&table, &assignment.column_name)?;
+            if idxs.contains(&idx) {
+                return Err(anyhow::anyhow!(
+                    "Column {} is set more than once!",
+                    assignment.column_name
+                ));
+            }
+            idxs.push(idx);
+            check_value(
+                &table.get_columns()[idx],
+                &assignment.value,
+                &column_type,
+            )?;
+        }
+        if let Some(filter) = &self.filter {
+            check_no_aggregates(filter.get_aggregates())?;
+            filter.check(&column_type)?;
+        }
+        Ok(())
+    }
+
+    /// Updates the rows of the table in `data` that satisfy the `where`
+    /// condition, returning how many were updated. Every value is evaluated
+    /// against the row as it was before the update. Nothing is updated if
+    /// any updated row doesn't conform to the table's schema.
+    pub fn execute(
+        &self,
+        symtable: &SymbolTable,
+        data: &mut Dataset,
+    ) -> anyhow::Result<usize> {
+        self.check(symtable)?;
+        let table_data = get_table_data(data, &self.table_name)?;
+        let table = table_data.get_schema();
+
+        let mut updated = vec![];
+        for row_idx in get_matching_rows(table_data, self.filter.as_ref())? {
+            let row = &table_data.get_rows()[row_idx];
+            let mut new_row = row.clone();
+            for assignment in &self.assignments {
+                let idx = get_writable_idx(&table, &assignment.column_name)?;
+                new_row[idx] = eval_value(
+                    &table.get_columns()[idx],
+                    &assignment.value,
+                    &row_value(&table, row),
+                )?;
+            }
+            updated.push((row_idx, complete_valid_row(&table, &new_row)?));
+        }
+
+        let count = updated.len();
+        for (row_idx, row) in updated {
+            table_data.set_row(row_idx, row);
+        }
+        Ok(count)
+    }
+}
+
+impl DeleteStmt {
+    pub fn new(table_name: Rc<str>, filter: Option<Cond>) -> Self {
+        Self { table_name, filter }
+    }
+
+    pub fn get_table_name(&self) -> Rc<str> {
+        self.table_name.clone()
+    }
+
+    pub fn get_filter(&self) -> Option<&Cond> {
+        self.filter.as_ref()
+    }
+
+    /// Checks the statement against the tables in `symtable`.
+    pub fn check(&self, symtable: &SymbolTable) -> anyhow::Result<()> {
+        let table = get_writable_table(symtable, &self.table_name)?;
+        if let Some(filter) = &self.filter {
+            check_no_aggregates(filter.get_aggregates())?;
+            filter.check(&|name: &str| {
+                table.get_column_by_name(name).map(|col| col.get_type())
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Deletes the rows of the table in `data` that satisfy the `where`
+    /// condition, returning how many were deleted.
+    pub fn execute(
+        &self,
+        symtable: &SymbolTable,
+        data: &mut Dataset,
+    ) -> anyhow::Result<usize> {
+        self.check(symtable)?;
+        let table_data = get_table_data(data, &self.table_name)?;
+        let row_idxs = get_matching_rows(table_data, self.filter.as_ref())?;
+        table_data.remove_rows(&row_idxs);
+        Ok(row_idxs.len())
+    }
+}
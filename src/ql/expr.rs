@@ -0,0 +1,962 @@
+use std::{cmp::Ordering, collections::HashMap, fmt::Display, rc::Rc};
+
+use crate::{
+    core::schema::{
+        DBL_TYPE_NAME, DECIMAL_TYPE_NAME, DataType, DblDataType,
+        DecimalDataType, INT_TYPE_NAME, IntDataType, IntWidth, STR_TYPE_NAME,
+        SharedDataType, StrDataType, compat::with_nullable,
+    },
+    ql::lex::Literal,
+};
+
+/// An arithmetic operator joining two expressions.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl BinaryOp {
+    pub fn get_symbol(&self) -> &'static str {
+        match self {
+            Self::Add => "+",
+            Self::Sub => "-",
+            Self::Mul => "*",
+            Self::Div => "/",
+        }
+    }
+
+    /// Returns how tightly the operator binds; `*` and `/` bind tighter than
+    /// `+` and `-`.
+    pub fn get_precedence(&self) -> u8 {
+        match self {
+            Self::Add | Self::Sub => 1,
+            Self::Mul | Self::Div => 2,
+        }
+    }
+}
+
+/// A function summarising the values of an expression over a group of rows.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AggregateFunc {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+}
+
+impl AggregateFunc {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Self::Count => "count",
+            Self::Sum => "sum",
+            Self::Avg => "avg",
+            Self::Min => "min",
+            Self::Max => "max",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "count" => Some(Self::Count),
+            "sum" => Some(Self::Sum),
+            "avg" => Some(Self::Avg),
+            "min" => Some(Self::Min),
+            "max" => Some(Self::Max),
+            _ => None,
+        }
+    }
+}
+
+/// A call to an aggregate function, such as `avg(rating)` or
+/// `count(distinct userId)`. `count(*)` has no argument and counts rows.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Aggregate {
+    pub func: AggregateFunc,
+    pub arg: Option<Expr>,
+    pub distinct: bool,
+}
+
+/// A placeholder for a value given when a prepared query is run, either by
+/// position, as `$1`, or by name, as `:name`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Param {
+    Positional(usize),
+    Named(Rc<str>),
+}
+
+/// An expression in the query language, such as the formula of a computed
+/// column. Columns are referred to by name, so `price * quantity` and
+/// `first + " " + last` are expressions over a table's columns.
+///
+/// Aggregates are evaluated over a group of rows before the rest of the
+/// expression, so `eval` looks up their values by their text, as if they were
+/// columns.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    Literal(Literal),
+    Column(Rc<str>),
+    Neg(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Aggregate(Box<Aggregate>),
+    Param(Param),
+}
+
+/// The kinds of value an expression can produce.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ValueKind {
+    Int,
+    Dbl,
+    Decimal,
+    Str,
+}
+
+impl ValueKind {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Self::Int => INT_TYPE_NAME,
+            Self::Dbl => DBL_TYPE_NAME,
+            Self::Decimal => DECIMAL_TYPE_NAME,
+            Self::Str => STR_TYPE_NAME,
+        }
+    }
+
+    /// Returns the kind of value held by columns of `dtype`.
+    pub fn of_type(dtype: &dyn DataType) -> Option<Self> {
+        let any = dtype.as_any();
+        if any.is::<IntDataType>() {
+            Some(Self::Int)
+        } else if any.is::<DblDataType>() {
+            Some(Self::Dbl)
+        } else if any.is::<DecimalDataType>() {
+            Some(Self::Decimal)
+        } else if any.is::<StrDataType>() {
+            Some(Self::Str)
+        } else {
+            None
+        }
+    }
+
+    pub fn of_literal(lit: &Literal) -> Self {
+        match lit {
+            Literal::Int(_) => Self::Int,
+            Literal::Dbl(_) => Self::Dbl,
+            Literal::Decimal(_) => Self::Decimal,
+            Literal::Str(_) => Self::Str,
+        }
+    }
+
+    fn is_number(&self) -> bool {
+        *self != Self::Str
+    }
+}
+
+/// The type inferred for an expression: the kind of value it produces and
+/// whether it may be null.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ExprType {
+    pub kind: ValueKind,
+    pub nullable: bool,
+}
+
+impl ExprType {
+    /// Returns `true` if every value of the expression can be stored in a
+    /// column of `dtype`, ignoring its bounds. Integers can be stored in any
+    /// number column.
+    pub fn is_storable_in(&self, dtype: &dyn DataType) -> bool {
+        let kind_ok = match (self.kind, ValueKind::of_type(dtype)) {
+            (ValueKind::Int, Some(kind)) => kind.is_number(),
+            (kind, Some(to)) => kind == to,
+            (_, None) => false,
+        };
+        kind_ok && (!self.nullable || dtype.get_nullable())
+    }
+
+    /// Returns an unbounded data type holding the expression's values.
+    /// Integers are 64-bit, as arithmetic on them can leave 32 bits.
+    pub fn to_data_type(&self) -> SharedDataType {
+        match self.kind {
+            ValueKind::Int => Rc::new(
+                IntDataType::new(None, None, self.nullable)
+                    .with_width(IntWidth::Bits64),
+            ),
+            ValueKind::Dbl => {
+                Rc::new(DblDataType::new(None, None, self.nullable))
+            }
+            ValueKind::Decimal => {
+                Rc::new(DecimalDataType::new(None, None, self.nullable))
+            }
+            ValueKind::Str => {
+                Rc::new(StrDataType::new(None, None, self.nullable))
+            }
+        }
+    }
+}
+
+impl Display for ExprType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.kind.get_name())?;
+        if self.nullable {
+            write!(f, "?")?;
+        }
+        Ok(())
+    }
+}
+
+// Returns the kind of value `op` produces from operands of kinds `left` and
+// `right`, or `None` if it can't be applied to them. Dividing integers gives
+// a double, and doubles only mix with decimals when they're constants, such
+// as the `1.1` of `price * 1.1`, which are read as decimals.
+fn binary_kind(
+    op: BinaryOp,
+    (left, left_is_const): (ValueKind, bool),
+    (right, right_is_const): (ValueKind, bool),
+) -> Option<ValueKind> {
+    use ValueKind::*;
+    match (left, right) {
+        (Str, Str) if op == BinaryOp::Add => Some(Str),
+        (Str, _) | (_, Str) => None,
+        (Int, Int) if op == BinaryOp::Div => Some(Dbl),
+        (Int, Int) => Some(Int),
+        (Int | Dbl, Int | Dbl) => Some(Dbl),
+        (Int | Decimal, Int | Decimal) => Some(Decimal),
+        (Decimal, Dbl) if right_is_const => Some(Decimal),
+        (Dbl, Decimal) if left_is_const => Some(Decimal),
+        _ => None,
+    }
+}
+
+fn eval_binary(
+    op: BinaryOp,
+    left: &Literal,
+    right: &Literal,
+) -> anyhow::Result<Literal> {
+    let symbol = op.get_symbol();
+    let out_of_range = || {
+        anyhow::anyhow!("Result of {left} {symbol} {right} is out of range!")
+    };
+    let is_zero = right.get_f64() == Some(0.0);
+
+    match (left, right) {
+        (Literal::Str(a), Literal::Str(b)) if op == BinaryOp::Add => {
+            Ok(Literal::Str(format!("{a}{b}").into()))
+        }
+        (Literal::Str(_), _) | (_, Literal::Str(_)) => Err(anyhow::anyhow!(
+            "Can't apply {symbol} to {left} and {right}!"
+        )),
+        _ if op == BinaryOp::Div && is_zero => {
+            Err(anyhow::anyhow!("Division by zero in {left} / {right}!"))
+        }
+        (Literal::Decimal(_), _) | (_, Literal::Decimal(_)) => {
+            let (a, b) = left
+                .get_decimal()
+                .zip(right.get_decimal())
+                .ok_or_else(out_of_range)?;
+            match op {
+                BinaryOp::Add => a.checked_add(b),
+                BinaryOp::Sub => a.checked_sub(b),
+                BinaryOp::Mul => a.checked_mul(b),
+                BinaryOp::Div => a.checked_div(b),
+            }
+            .map(Literal::Decimal)
+            .ok_or_else(out_of_range)
+        }
+        (Literal::Int(a), Literal::Int(b)) if op != BinaryOp::Div => match op {
+            BinaryOp::Add => a.checked_add(*b),
+            BinaryOp::Sub => a.checked_sub(*b),
+            _ => a.checked_mul(*b),
+        }
+        .map(Literal::Int)
+        .ok_or_else(out_of_range),
+        _ => {
+            let (a, b) = (left.get_f64().unwrap(), right.get_f64().unwrap());
+            let val = match op {
+                BinaryOp::Add => a + b,
+                BinaryOp::Sub => a - b,
+                BinaryOp::Mul => a * b,
+                BinaryOp::Div => a / b,
+            };
+            if val.is_finite() {
+                Ok(Literal::Dbl(val))
+            } else {
+                Err(out_of_range())
+            }
+        }
+    }
+}
+
+impl Aggregate {
+    pub fn new(func: AggregateFunc, arg: Option<Expr>, distinct: bool) -> Self {
+        Self {
+            func,
+            arg,
+            distinct,
+        }
+    }
+
+    /// Infers the type of the aggregate's result, given the type of each
+    /// column its argument can refer to. Counts are never null, but other
+    /// aggregates are null over a group with no values, as in SQL. Averages
+    /// of integers are doubles.
+    pub fn infer_type(
+        &self,
+        column_type: &impl Fn(&str) -> Option<SharedDataType>,
+    ) -> anyhow::Result<ExprType> {
+        let Some(arg) = &self.arg else {
+            return Ok(ExprType {
+                kind: ValueKind::Int,
+                nullable: false,
+            });
+        };
+        if !arg.get_aggregates().is_empty() {
+            return Err(anyhow::anyhow!(
+                "Aggregate functions can't be nested!"
+            ));
+        }
+        let arg_kind = arg.infer_type(column_type)?.kind;
+        let kind = match (self.func, arg_kind) {
+            (AggregateFunc::Count, _) => ValueKind::Int,
+            (AggregateFunc::Sum | AggregateFunc::Avg, ValueKind::Str) => {
+                return Err(anyhow::anyhow!(
+                    "Can't apply {} to a {}!",
+                    self.func.get_name(),
+                    arg_kind.get_name()
+                ));
+            }
+            (AggregateFunc::Avg, ValueKind::Int) => ValueKind::Dbl,
+            (_, kind) => kind,
+        };
+        Ok(ExprType {
+            kind,
+            nullable: self.func != AggregateFunc::Count,
+        })
+    }
+
+    /// Evaluates the aggregate over the values of its argument in a group of
+    /// rows, one per row. Nulls are ignored, except by `count(*)`, which
+    /// counts every row.
+    pub fn eval(
+        &self,
+        values: Vec<Option<Literal>>,
+    ) -> anyhow::Result<Option<Literal>> {
+        if self.arg.is_none() {
+            return Ok(Some(Literal::Int(values.len() as i64)));
+        }
+        let mut vals: Vec<Literal> = values.into_iter().flatten().collect();
+        if self.distinct {
+            let mut distinct: Vec<Literal> = vec![];
+            for val in vals {
+                if !distinct.iter().any(|seen| {
+                    compare_literals(seen, &val) == Some(Ordering::Equal)
+                }) {
+                    distinct.push(val);
+                }
+            }
+            vals = distinct;
+        }
+
+        let sum = |vals: &[Literal]| match vals.split_first() {
+            Some((first, rest)) => rest
+                .iter()
+                .try_fold(first.clone(), |total, val| {
+                    eval_binary(BinaryOp::Add, &total, val)
+                })
+                .map(Some),
+            None => Ok(None),
+        };
+        // keeps the least value if `ordering` is `Less`, else the greatest
+        let extreme = |ordering: Ordering| {
+            vals.iter().cloned().reduce(|a, b| {
+                match compare_literals(&b, &a) == Some(ordering) {
+                    true => b,
+                    false => a,
+                }
+            })
+        };
+        match self.func {
+            AggregateFunc::Count => Ok(Some(Literal::Int(vals.len() as i64))),
+            AggregateFunc::Sum => sum(&vals),
+            AggregateFunc::Avg => match sum(&vals)? {
+                Some(total) => Ok(Some(eval_binary(
+                    BinaryOp::Div,
+                    &total,
+                    &Literal::Int(vals.len() as i64),
+                )?)),
+                None => Ok(None),
+            },
+            AggregateFunc::Min => Ok(extreme(Ordering::Less)),
+            AggregateFunc::Max => Ok(extreme(Ordering::Greater)),
+        }
+    }
+}
+
+impl Expr {
+    /// Returns the names of the columns the expression refers to, in the
+    /// order they first appear.
+    pub fn get_column_refs(&self) -> Vec<Rc<str>> {
+        let mut refs = vec![];
+        self.collect_column_refs(true, &mut refs);
+        refs
+    }
+
+    /// Returns the names of the columns the expression refers to outside of
+    /// aggregates, which must be grouped by in a query that aggregates.
+    pub fn get_unaggregated_refs(&self) -> Vec<Rc<str>> {
+        let mut refs = vec![];
+        self.collect_column_refs(false, &mut refs);
+        refs
+    }
+
+    fn collect_column_refs(
+        &self,
+        in_aggregates: bool,
+        refs: &mut Vec<Rc<str>>,
+    ) {
+        match self {
+            Self::Literal(_) | Self::Param(_) => {}
+            Self::Column(name) => {
+                if !refs.contains(name) {
+                    refs.push(name.clone());
+                }
+            }
+            Self::Neg(operand) => {
+                operand.collect_column_refs(in_aggregates, refs)
+            }
+            Self::Binary(_, left, right) => {
+                left.collect_column_refs(in_aggregates, refs);
+                right.collect_column_refs(in_aggregates, refs);
+            }
+            Self::Aggregate(aggregate) => {
+                if let Some(arg) = &aggregate.arg
+                    && in_aggregates
+                {
+                    arg.collect_column_refs(in_aggregates, refs);
+                }
+            }
+        }
+    }
+
+    /// Returns the aggregates in the expression, outermost first.
+    pub fn get_aggregates(&self) -> Vec<&Aggregate> {
+        match self {
+            Self::Literal(_) | Self::Column(_) | Self::Param(_) => vec![],
+            Self::Neg(operand) => operand.get_aggregates(),
+            Self::Binary(_, left, right) => {
+                let mut aggregates = left.get_aggregates();
+                aggregates.extend(right.get_aggregates());
+                aggregates
+            }
+            Self::Aggregate(aggregate) => vec![&**aggregate],
+        }
+    }
+
+    /// Returns a copy of the expression with references to column
+    /// `old_name` changed to `new_name`.
+    pub fn rename_column(&self, old_name: &str, new_name: &Rc<str>) -> Self {
+        match self {
+            Self::Column(name) if &**name == old_name => {
+                Self::Column(new_name.clone())
+            }
+            Self::Literal(_) | Self::Column(_) | Self::Param(_) => self.clone(),
+            Self::Neg(operand) => {
+                Self::Neg(Box::new(operand.rename_column(old_name, new_name)))
+            }
+            Self::Binary(op, left, right) => Self::Binary(
+                *op,
+                Box::new(left.rename_column(old_name, new_name)),
+                Box::new(right.rename_column(old_name, new_name)),
+            ),
+            Self::Aggregate(aggregate) => {
+                Self::Aggregate(Box::new(Aggregate {
+                    arg: aggregate
+                        .arg
+                        .as_ref()
+                        .map(|arg| arg.rename_column(old_name, new_name)),
+                    ..**aggregate
+                }))
+            }
+        }
+    }
+
+    /// Infers the type of the expression, given the type of each column it
+    /// can refer to. The result may be null if any column it refers to is
+    /// nullable.
+    pub fn infer_type(
+        &self,
+        column_type: &impl Fn(&str) -> Option<SharedDataType>,
+    ) -> anyhow::Result<ExprType> {
+        match self {
+            Self::Literal(lit) => Ok(ExprType {
+                kind: ValueKind::of_literal(lit),
+                nullable: false,
+            }),
+            Self::Column(name) => {
+                let dtype = column_type(name)
+                    .ok_or(anyhow::anyhow!("Unknown column {name}!"))?;
+                Ok(ExprType {
+                    kind: ValueKind::of_type(&*dtype).ok_or(
+                        anyhow::anyhow!(
+                            "Column {name} has a type expressions can't use!"
+                        ),
+                    )?,
+                    nullable: dtype.get_nullable(),
+                })
+            }
+            Self::Neg(operand) => {
+                let operand_type = operand.infer_type(column_type)?;
+                if !operand_type.kind.is_number() {
+                    return Err(anyhow::anyhow!(
+                        "Can't negate a {}!",
+                        operand_type.kind.get_name()
+                    ));
+                }
+                Ok(operand_type)
+            }
+            Self::Binary(op, left, right) => {
+                let left_type = left.infer_type(column_type)?;
+                let right_type = right.infer_type(column_type)?;
+                let kind = binary_kind(
+                    *op,
+                    (left_type.kind, left.get_column_refs().is_empty()),
+                    (right_type.kind, right.get_column_refs().is_empty()),
+                )
+                .ok_or(anyhow::anyhow!(
+                    "Can't apply {} to {} and {}!",
+                    op.get_symbol(),
+                    left_type.kind.get_name(),
+                    right_type.kind.get_name()
+                ))?;
+                Ok(ExprType {
+                    kind,
+                    nullable: left_type.nullable || right_type.nullable,
+                })
+            }
+            Self::Aggregate(aggregate) => aggregate.infer_type(column_type),
+            // parameters are given types from their context when a query is
+            // prepared, and looked up by their text
+            Self::Param(param) => {
+                let dtype =
+                    column_type(&param.to_string()).ok_or(anyhow::anyhow!(
+                        "Parameter {param} can only be used in a prepared \
+                        query!"
+                    ))?;
+                Ok(ExprType {
+                    kind: ValueKind::of_type(&*dtype).ok_or(anyhow::anyhow!(
+                        "Parameter {param} has a type expressions can't use!"
+                    ))?,
+                    nullable: false,
+                })
+            }
+        }
+    }
+
+    /// Evaluates the expression, given the value of each column it can refer
+    /// to. The result is null if any value it uses is null.
+    pub fn eval(
+        &self,
+        column_value: &impl Fn(&str) -> anyhow::Result<Option<Literal>>,
+    ) -> anyhow::Result<Option<Literal>> {
+        match self {
+            Self::Literal(lit) => Ok(Some(lit.clone())),
+            Self::Column(name) => column_value(name),
+            Self::Neg(operand) => match operand.eval(column_value)? {
+                Some(val) => match val.checked_neg() {
+                    Some(neg) => Ok(Some(neg)),
+                    None => Err(anyhow::anyhow!("Can't negate {val}!")),
+                },
+                None => Ok(None),
+            },
+            Self::Binary(op, left, right) => {
+                match (left.eval(column_value)?, right.eval(column_value)?) {
+                    (Some(left), Some(right)) => {
+                        Ok(Some(eval_binary(*op, &left, &right)?))
+                    }
+                    _ => Ok(None),
+                }
+            }
+            Self::Aggregate(_) => column_value(&self.to_string()),
+            Self::Param(param) => {
+                Err(anyhow::anyhow!("Parameter {param} has no value!"))
+            }
+        }
+    }
+
+    /// Returns the parameters in the expression, in the order they appear.
+    pub fn get_params(&self) -> Vec<&Param> {
+        match self {
+            Self::Literal(_) | Self::Column(_) => vec![],
+            Self::Neg(operand) => operand.get_params(),
+            Self::Binary(_, left, right) => {
+                let mut params = left.get_params();
+                params.extend(right.get_params());
+                params
+            }
+            Self::Aggregate(aggregate) => aggregate
+                .arg
+                .as_ref()
+                .map_or(vec![], |arg| arg.get_params()),
+            Self::Param(param) => vec![param],
+        }
+    }
+
+    /// Infers the types of the parameters in the expression from the
+    /// operands they're combined with, given the type of each column it can
+    /// refer to, adding those not already in `types`. A parameter in
+    /// `price * $1` takes the kind of `price`, without its bounds.
+    pub fn infer_param_types(
+        &self,
+        column_type: &impl Fn(&str) -> Option<SharedDataType>,
+        types: &mut Vec<(Param, SharedDataType)>,
+    ) -> anyhow::Result<()> {
+        match self {
+            Self::Literal(_) | Self::Column(_) | Self::Param(_) => Ok(()),
+            Self::Neg(operand) => operand.infer_param_types(column_type, types),
+            Self::Binary(_, left, right) => {
+                for (operand, other) in [(left, right), (right, left)] {
+                    if let Self::Param(param) = &**operand {
+                        infer_param_type(
+                            param,
+                            other,
+                            None,
+                            column_type,
+                            types,
+                        )?;
+                    }
+                }
+                left.infer_param_types(column_type, types)?;
+                right.infer_param_types(column_type, types)
+            }
+            Self::Aggregate(aggregate) => match &aggregate.arg {
+                Some(arg) => arg.infer_param_types(column_type, types),
+                None => Ok(()),
+            },
+        }
+    }
+
+    /// Returns a copy of the expression with each parameter replaced by its
+    /// value in `args`, if it has one.
+    pub fn bind_params(&self, args: &HashMap<Param, Literal>) -> Self {
+        match self {
+            Self::Param(param) => match args.get(param) {
+                Some(lit) => Self::Literal(lit.clone()),
+                None => self.clone(),
+            },
+            Self::Literal(_) | Self::Column(_) => self.clone(),
+            Self::Neg(operand) => {
+                Self::Neg(Box::new(operand.bind_params(args)))
+            }
+            Self::Binary(op, left, right) => Self::Binary(
+                *op,
+                Box::new(left.bind_params(args)),
+                Box::new(right.bind_params(args)),
+            ),
+            Self::Aggregate(aggregate) => {
+                Self::Aggregate(Box::new(Aggregate {
+                    arg: aggregate
+                        .arg
+                        .as_ref()
+                        .map(|arg| arg.bind_params(args)),
+                    ..**aggregate
+                }))
+            }
+        }
+    }
+}
+
+// Infers the type of `param` from the expression `other` it's combined with,
+// unless `other` has parameters of its own or `param` already has a type.
+// Compared for equality with a column, as by `op`, it takes the column's
+// type, so its value must be one the column can hold; otherwise it takes the
+// unbounded type of `other`'s values. Parameters are never null.
+fn infer_param_type(
+    param: &Param,
+    other: &Expr,
+    op: Option<CompareOp>,
+    column_type: &impl Fn(&str) -> Option<SharedDataType>,
+    types: &mut Vec<(Param, SharedDataType)>,
+) -> anyhow::Result<()> {
+    if !other.get_params().is_empty()
+        || types.iter().any(|(typed, _)| typed == param)
+    {
+        return Ok(());
+    }
+    let other_type = other.infer_type(column_type)?;
+    let dtype = match (other, op) {
+        (Expr::Column(name), Some(CompareOp::Eq | CompareOp::Ne)) => {
+            match column_type(name) {
+                Some(dtype) => with_nullable(&dtype, false),
+                None => other_type.to_data_type(),
+            }
+        }
+        _ => ExprType {
+            nullable: false,
+            ..other_type
+        }
+        .to_data_type(),
+    };
+    types.push((param.clone(), dtype));
+    Ok(())
+}
+
+/// An operator comparing two expressions.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl CompareOp {
+    pub fn get_symbol(&self) -> &'static str {
+        match self {
+            Self::Eq => "=",
+            Self::Ne => "!=",
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+        }
+    }
+
+    /// Returns `true` if two values ordered as `ordering` satisfy the
+    /// operator.
+    pub fn is_satisfied_by(&self, ordering: Ordering) -> bool {
+        match self {
+            Self::Eq => ordering.is_eq(),
+            Self::Ne => ordering.is_ne(),
+            Self::Lt => ordering.is_lt(),
+            Self::Le => ordering.is_le(),
+            Self::Gt => ordering.is_gt(),
+            Self::Ge => ordering.is_ge(),
+        }
+    }
+}
+
+/// Orders two values of the same kind, or `None` if they can't be compared,
+/// such as a string and a number. Numbers of different kinds are compared by
+/// value.
+pub fn compare_literals(a: &Literal, b: &Literal) -> Option<Ordering> {
+    match (a, b) {
+        (Literal::Str(a), Literal::Str(b)) => Some(a.cmp(b)),
+        (Literal::Str(_), _) | (_, Literal::Str(_)) => None,
+        (Literal::Int(a), Literal::Int(b)) => Some(a.cmp(b)),
+        (Literal::Decimal(_), _) | (_, Literal::Decimal(_)) => {
+            let (a, b) = a.get_decimal().zip(b.get_decimal())?;
+            Some(a.cmp(&b))
+        }
+        _ => a.get_f64()?.partial_cmp(&b.get_f64()?),
+    }
+}
+
+/// A condition in the query language, such as the `where` clause of a query.
+/// Conditions follow SQL: comparing with null is neither true nor false, so
+/// evaluating one gives `None` rather than a `bool`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Cond {
+    Compare(CompareOp, Expr, Expr),
+    /// `expr is null`, or `expr is not null` if negated.
+    IsNull {
+        expr: Expr,
+        negated: bool,
+    },
+    Not(Box<Cond>),
+    And(Box<Cond>, Box<Cond>),
+    Or(Box<Cond>, Box<Cond>),
+}
+
+impl Cond {
+    /// Returns the names of the columns the condition refers to, in the
+    /// order they first appear.
+    pub fn get_column_refs(&self) -> Vec<Rc<str>> {
+        let mut refs = vec![];
+        let mut extend = |expr_refs: Vec<Rc<str>>| {
+            for name in expr_refs {
+                if !refs.contains(&name) {
+                    refs.push(name);
+                }
+            }
+        };
+        match self {
+            Self::Compare(_, left, right) => {
+                extend(left.get_column_refs());
+                extend(right.get_column_refs());
+            }
+            Self::IsNull { expr, .. } => extend(expr.get_column_refs()),
+            Self::Not(cond) => extend(cond.get_column_refs()),
+            Self::And(left, right) | Self::Or(left, right) => {
+                extend(left.get_column_refs());
+                extend(right.get_column_refs());
+            }
+        }
+        refs
+    }
+
+    /// Returns the expressions the condition tests, in order.
+    pub fn get_exprs(&self) -> Vec<&Expr> {
+        match self {
+            Self::Compare(_, left, right) => vec![left, right],
+            Self::IsNull { expr, .. } => vec![expr],
+            Self::Not(cond) => cond.get_exprs(),
+            Self::And(left, right) | Self::Or(left, right) => {
+                let mut exprs = left.get_exprs();
+                exprs.extend(right.get_exprs());
+                exprs
+            }
+        }
+    }
+
+    /// Returns the aggregates in the condition, in order.
+    pub fn get_aggregates(&self) -> Vec<&Aggregate> {
+        self.get_exprs()
+            .into_iter()
+            .flat_map(|expr| expr.get_aggregates())
+            .collect()
+    }
+
+    /// Returns the parameters in the condition, in the order they appear.
+    pub fn get_params(&self) -> Vec<&Param> {
+        self.get_exprs()
+            .into_iter()
+            .flat_map(|expr| expr.get_params())
+            .collect()
+    }
+
+    /// Infers the types of the parameters in the condition from the
+    /// expressions they're compared with or combined with, adding those not
+    /// already in `types`.
+    pub fn infer_param_types(
+        &self,
+        column_type: &impl Fn(&str) -> Option<SharedDataType>,
+        types: &mut Vec<(Param, SharedDataType)>,
+    ) -> anyhow::Result<()> {
+        match self {
+            Self::Compare(op, left, right) => {
+                for (operand, other) in [(left, right), (right, left)] {
+                    if let Expr::Param(param) = operand {
+                        infer_param_type(
+                            param,
+                            other,
+                            Some(*op),
+                            column_type,
+                            types,
+                        )?;
+                    }
+                }
+                left.infer_param_types(column_type, types)?;
+                right.infer_param_types(column_type, types)
+            }
+            Self::IsNull { expr, .. } => {
+                expr.infer_param_types(column_type, types)
+            }
+            Self::Not(cond) => cond.infer_param_types(column_type, types),
+            Self::And(left, right) | Self::Or(left, right) => {
+                left.infer_param_types(column_type, types)?;
+                right.infer_param_types(column_type, types)
+            }
+        }
+    }
+
+    /// Returns a copy of the condition with each parameter replaced by its
+    /// value in `args`, if it has one.
+    pub fn bind_params(&self, args: &HashMap<Param, Literal>) -> Self {
+        match self {
+            Self::Compare(op, left, right) => Self::Compare(
+                *op,
+                left.bind_params(args),
+                right.bind_params(args),
+            ),
+            Self::IsNull { expr, negated } => Self::IsNull {
+                expr: expr.bind_params(args),
+                negated: *negated,
+            },
+            Self::Not(cond) => Self::Not(Box::new(cond.bind_params(args))),
+            Self::And(left, right) => Self::And(
+                Box::new(left.bind_params(args)),
+                Box::new(right.bind_params(args)),
+            ),
+            Self::Or(left, right) => Self::Or(
+                Box::new(left.bind_params(args)),
+                Box::new(right.bind_params(args)),
+            ),
+        }
+    }
+
+    /// Checks the condition only compares values of the same kind, given the
+    /// type of each column it can refer to.
+    pub fn check(
+        &self,
+        column_type: &impl Fn(&str) -> Option<SharedDataType>,
+    ) -> anyhow::Result<()> {
+        match self {
+            Self::Compare(op, left, right) => {
+                let left_kind = left.infer_type(column_type)?.kind;
+                let right_kind = right.infer_type(column_type)?.kind;
+                if left_kind.is_number() != right_kind.is_number() {
+                    return Err(anyhow::anyhow!(
+                        "Can't compare {} and {} with {}!",
+                        left_kind.get_name(),
+                        right_kind.get_name(),
+                        op.get_symbol()
+                    ));
+                }
+                Ok(())
+            }
+            Self::IsNull { expr, .. } => {
+                expr.infer_type(column_type)?;
+                Ok(())
+            }
+            Self::Not(cond) => cond.check(column_type),
+            Self::And(left, right) | Self::Or(left, right) => {
+                left.check(column_type)?;
+                right.check(column_type)
+            }
+        }
+    }
+
+    /// Evaluates the condition, given the value of each column it can refer
+    /// to. Returns `None` if the condition is unknown because of a null.
+    pub fn eval(
+        &self,
+        column_value: &impl Fn(&str) -> anyhow::Result<Option<Literal>>,
+    ) -> anyhow::Result<Option<bool>> {
+        match self {
+            Self::Compare(op, left, right) => {
+                match (left.eval(column_value)?, right.eval(column_value)?) {
+                    (Some(left), Some(right)) => {
+                        let ordering = compare_literals(&left, &right).ok_or(
+                            anyhow::anyhow!(
+                                "Can't compare {left} and {right}!"
+                            ),
+                        )?;
+                        Ok(Some(op.is_satisfied_by(ordering)))
+                    }
+                    _ => Ok(None),
+                }
+            }
+            Self::IsNull { expr, negated } => {
+                Ok(Some(expr.eval(column_value)?.is_none() != *negated))
+            }
+            Self::Not(cond) => Ok(cond.eval(column_value)?.map(|val| !val)),
+            Self::And(left, right) => {
+                match (left.eval(column_value)?, right.eval(column_value)?) {
+                    (Some(false), _) | (_, Some(false)) => Ok(Some(false)),
+                    (Some(true), Some(true)) => Ok(Some(true)),
+                    _ => Ok(None),
+                }
+            }
+            Self::Or(left, right) => {
+                match (left.eval(column_value)?, right.eval(column_value)?) {
+                    (Some(true), _) | (_, Some(true)) => Ok(Some(true)),
+                    (Some(false), Some(false)) => Ok(Some(false)),
+                    _ => Ok(None),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,207 @@
+use std::rc::Rc;
+
+use rlrl::parse::TokenQueue;
+
+use crate::ql::{
+    Stmt, SymbolTable,
+    import::{FsLoader, Importer, SchemaLoader, SymbolOrigins},
+    lex::{Token, setup_lexer},
+    parse::Parse,
+};
+
+// A piece of a schema file, as the formatter sees it.
+enum Chunk<'a> {
+    // a statement up to and including its `;`, and whether it has comments
+    // inside it
+    Stmt { text: &'a str, has_comment: bool },
+    // a `//` comment, and whether it follows a statement on the same line
+    Comment { text: &'a str, trailing: bool },
+    // one or more blank lines
+    Blank,
+}
+
+// Splits `src` into statements, comments and blank lines, keeping the text of
+// each.
+fn split_chunks(src: &str) -> Vec<Chunk<'_>> {
+    let mut chunks = vec![];
+    let mut stmt_start = None;
+    let mut has_comment = false;
+    // the newlines seen since the last chunk
+    let mut newlines = 0;
+    let mut chars = src.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\n' if stmt_start.is_none() => newlines += 1,
+            _ if c.is_whitespace() => {}
+            '/' if src[i..].starts_with("//") => {
+                let end = src[i..]
+                    .find(['\n', '\r'])
+                    .map_or(src.len(), |end| i + end);
+                if stmt_start.is_some() {
+                    has_comment = true;
+                } else {
+                    if newlines > 1 && !chunks.is_empty() {
+                        chunks.push(Chunk::Blank);
+                    }
+                    let trailing = newlines == 0
+                        && matches!(chunks.last(), Some(Chunk::Stmt { .. }));
+                    chunks.push(Chunk::Comment {
+                        text: &src[i..end],
+                        trailing,
+                    });
+                    newlines = 0;
+                }
+                while chars.next_if(|(j, _)| *j < end).is_some() {}
+            }
+            _ => {
+                let start = *stmt_start.get_or_insert_with(|| {
+                    if newlines > 1 && !chunks.is_empty() {
+                        chunks.push(Chunk::Blank);
+                    }
+                    newlines = 0;
+                    i
+                });
+                match c {
+                    '"' => {
+                        while let Some((_, c)) = chars.next() {
+                            match c {
+                                '\\' => {
+                                    chars.next();
+                                }
+                                '"' => break,
+                                _ => {}
+                            }
+                        }
+                    }
+                    ';' => {
+                        chunks.push(Chunk::Stmt {
+                            text: &src[start..=i],
+                            has_comment,
+                        });
+                        stmt_start = None;
+                        has_comment = false;
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    if let Some(start) = stmt_start {
+        chunks.push(Chunk::Stmt {
+            text: src[start..].trim_end(),
+            has_comment,
+        });
+    }
+    chunks
+}
+
+// Returns the names and strings in `tokens`, in order.
+fn get_names(tokens: &[Token]) -> Vec<Rc<str>> {
+    tokens
+        .iter()
+        .filter_map(Token::get_ident_or_str_literal)
+        .collect()
+}
+
+// Parses the statement in `src` against `symtable`, returning it in canonical
+// form. Statements are only rewritten when the canonical form means the same
+// and keeps every name, so those using type aliases or with comments inside
+// them are kept as written.
+fn format_stmt(
+    src: &str,
+    has_comment: bool,
+    symtable: &mut SymbolTable,
+) -> anyhow::Result<(Stmt, String)> {
+    let lexer = setup_lexer();
+    let tokens = lexer.lex(src)?;
+    let mut tq = TokenQueue::from(tokens.clone());
+    let mut reparse_symtable = symtable.clone();
+    let stmt = tq.parse_with_mut(Stmt::parse, symtable)?;
+    tq.consume_eq(Token::Semicolon)?;
+    if !tq.is_consumed() {
+        return Err(anyhow::anyhow!("Unexpected input after statement!"));
+    }
+
+    let formatted = stmt.to_string();
+    let is_lossless = || -> anyhow::Result<bool> {
+        let formatted_tokens = lexer.lex(&formatted)?;
+        let reparsed = TokenQueue::from(formatted_tokens.clone())
+            .parse_with_mut(Stmt::parse, &mut reparse_symtable)?;
+        Ok(get_names(&formatted_tokens) == get_names(&tokens)
+            && reparsed.to_string() == formatted)
+    };
+    if has_comment || !is_lossless().unwrap_or(false) {
+        return Ok((stmt, src.to_string()));
+    }
+    Ok((stmt, formatted))
+}
+
+// Formats `src`, resolving its imports as written in the file `from`.
+fn format_module<L: SchemaLoader + ?Sized>(
+    src: &str,
+    from: Option<(&str, &L)>,
+) -> anyhow::Result<String> {
+    let mut symtable = SymbolTable::new();
+    let mut origins = SymbolOrigins::new();
+    let mut importer = from.map(|(key, loader)| (key, Importer::new(loader)));
+    let mut out = String::new();
+
+    for chunk in split_chunks(src) {
+        match chunk {
+            Chunk::Stmt { text, has_comment } => {
+                let (stmt, formatted) =
+                    format_stmt(text, has_comment, &mut symtable)?;
+                if let Stmt::Import(import) = &stmt {
+                    let Some((key, importer)) = &mut importer else {
+                        return Err(anyhow::anyhow!(
+                            "Couldn't resolve import of {} outside a file!",
+                            import.get_path()
+                        ));
+                    };
+                    importer.import(
+                        key,
+                        import,
+                        &mut symtable,
+                        &mut origins,
+                    )?;
+                }
+                out.push_str(&formatted);
+                out.push('\n');
+            }
+            Chunk::Comment {
+                text,
+                trailing: true,
+            } => {
+                out.pop();
+                out.push(' ');
+                out.push_str(text);
+                out.push('\n');
+            }
+            Chunk::Comment { text, .. } => {
+                out.push_str(text);
+                out.push('\n');
+            }
+            Chunk::Blank => out.push('\n'),
+        }
+    }
+    Ok(out)
+}
+
+/// Formats the schema in `src`, printing each statement in canonical form on
+/// its own line. Comments are kept, and runs of blank lines are collapsed
+/// into one.
+pub fn format_schema(src: &str) -> anyhow::Result<String> {
+    format_module::<FsLoader>(src, None)
+}
+
+/// Formats the schema file at `path` as `format_schema` does, resolving its
+/// imports through `loader`.
+pub fn format_schema_with_loader<L: SchemaLoader + ?Sized>(
+    path: &str,
+    loader: &L,
+) -> anyhow::Result<String> {
+    let key = loader.resolve(None, path)?;
+    format_module(&loader.load(&key)?, Some((&key, loader)))
+}
@@ -0,0 +1,314 @@
+use std::{collections::HashMap, fs, path::Path, rc::Rc};
+
+use anyhow::Context;
+use rlrl::parse::TokenQueue;
+
+use crate::{
+    core::schema::SpreadsheetSchema,
+    ql::{
+        Stmt, SymbolTable,
+        lex::{Token, setup_lexer},
+        parse::{Parse, SchemaBuilder},
+    },
+};
+
+/// An `import` statement, bringing the types and tables defined in another
+/// schema file into scope.
+///
+/// `import "common.ft";` imports every symbol, `import { a, b } from
+/// "common.ft";` imports only the listed symbols, and `import "common.ft" as
+/// common;` imports every symbol under the `common.` namespace.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImportStmt {
+    path: Rc<str>,
+    names: Option<Vec<Rc<str>>>,
+    alias: Option<Rc<str>>,
+}
+
+impl ImportStmt {
+    pub fn new(
+        path: Rc<str>,
+        names: Option<Vec<Rc<str>>>,
+        alias: Option<Rc<str>>,
+    ) -> Self {
+        Self { path, names, alias }
+    }
+
+    pub fn get_path(&self) -> Rc<str> {
+        self.path.clone()
+    }
+
+    pub fn get_names(&self) -> Option<&[Rc<str>]> {
+        self.names.as_deref()
+    }
+
+    pub fn get_alias(&self) -> Option<Rc<str>> {
+        self.alias.clone()
+    }
+}
+
+/// Maps symbol names to the file they were defined in.
+pub type SymbolOrigins = HashMap<Rc<str>, Rc<str>>;
+
+/// Locates and reads the files named in `import` statements.
+pub trait SchemaLoader {
+    /// Resolves `path`, as written in a file identified by `from` (or `None`
+    /// for the root file), to a key that uniquely identifies the file.
+    fn resolve(
+        &self,
+        from: Option<&str>,
+        path: &str,
+    ) -> anyhow::Result<Rc<str>>;
+
+    /// Reads the source of the file identified by `key`.
+    fn load(&self, key: &str) -> anyhow::Result<String>;
+}
+
+/// Loads schema files from the filesystem, resolving imports relative to the
+/// importing file.
+pub struct FsLoader;
+
+impl SchemaLoader for FsLoader {
+    fn resolve(
+        &self,
+        from: Option<&str>,
+        path: &str,
+    ) -> anyhow::Result<Rc<str>> {
+        let full_path = match from.and_then(|from| Path::new(from).parent()) {
+            Some(dir) => dir.join(path),
+            None => Path::new(path).to_path_buf(),
+        };
+        let full_path = full_path
+            .canonicalize()
+            .with_context(|| format!("Couldn't find {path}"))?;
+        Ok(full_path.to_string_lossy().into())
+    }
+
+    fn load(&self, key: &str) -> anyhow::Result<String> {
+        fs::read_to_string(key).with_context(|| format!("Couldn't read {key}"))
+    }
+}
+
+/// Loads schema files from memory, keyed by their exact path.
+#[derive(Default)]
+pub struct MemoryLoader {
+    files: HashMap<Rc<str>, String>,
+}
+
+impl MemoryLoader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_file(&mut self, path: &str, src: &str) {
+        self.files.insert(path.into(), src.to_string());
+    }
+}
+
+impl SchemaLoader for MemoryLoader {
+    fn resolve(
+        &self,
+        _from: Option<&str>,
+        path: &str,
+    ) -> anyhow::Result<Rc<str>> {
+        match self.files.get_key_value(path) {
+            Some((key, _)) => Ok(key.clone()),
+            None => Err(anyhow::anyhow!("Couldn't find {path}")),
+        }
+    }
+
+    fn load(&self, key: &str) -> anyhow::Result<String> {
+        self.files
+            .get(key)
+            .cloned()
+            .ok_or(anyhow::anyhow!("Couldn't find {key}"))
+    }
+}
+
+// The symbols a parsed file defines, available for other files to import.
+struct Module {
+    symtable: SymbolTable,
+    origins: SymbolOrigins,
+}
+
+/// Resolves `import` statements through a `SchemaLoader`, parsing each
+/// imported file once and detecting import cycles.
+pub struct Importer<'a, L: SchemaLoader + ?Sized> {
+    loader: &'a L,
+    // files currently being parsed, used to detect cycles
+    stack: Vec<Rc<str>>,
+    modules: HashMap<Rc<str>, Rc<Module>>,
+}
+
+// Returns the name a `type` or `table` statement is about to define.
+fn peek_defined_name(tq: &TokenQueue<Token>) -> Option<Rc<str>> {
+    let mut tq = tq.clone();
+    match tq.consume().ok()? {
+        Token::TypeKwd | Token::TableKwd => {}
+        _ => return None,
+    }
+    tq.consume().ok()?.get_ident_or_str_literal()
+}
+
+impl<'a, L: SchemaLoader + ?Sized> Importer<'a, L> {
+    pub fn new(loader: &'a L) -> Self {
+        Self {
+            loader,
+            stack: vec![],
+            modules: HashMap::new(),
+        }
+    }
+
+    /// Parses and validates the file identified by `key`, resolving its
+    /// imports.
+    pub fn parse_file(
+        &mut self,
+        key: Rc<str>,
+    ) -> anyhow::Result<SpreadsheetSchema> {
+        if self.stack.contains(&key) {
+            let cycle: Vec<&str> = self
+                .stack
+                .iter()
+                .skip_while(|path| **path != key)
+                .chain(std::iter::once(&key))
+                .map(|path| &**path)
+                .collect();
+            return Err(anyhow::anyhow!(
+                "Import cycle: {}",
+                cycle.join(" -> ")
+            ));
+        }
+
+        self.stack.push(key.clone());
+        let result = self.parse_module(&key);
+        self.stack.pop();
+
+        let (schema, module) = result.with_context(|| format!("In {key}"))?;
+        self.modules.insert(key, Rc::new(module));
+        Ok(schema)
+    }
+
+    fn parse_module(
+        &mut self,
+        key: &Rc<str>,
+    ) -> anyhow::Result<(SpreadsheetSchema, Module)> {
+        let src = self.loader.load(key)?;
+        let mut tq = TokenQueue::from(setup_lexer().lex(&src)?);
+        let mut symtable = HashMap::new();
+        let mut origins = HashMap::new();
+        let mut builder = SchemaBuilder::new();
+
+        while !tq.is_consumed() {
+            if let Some(name) = peek_defined_name(&tq)
+                && let Some(origin) = origins.get(&name)
+            {
+                return Err(anyhow::anyhow!(
+                    "Symbol {name} is already defined in {origin}!"
+                ));
+            }
+
+            let stmt = tq.parse_with_mut(Stmt::parse, &mut symtable)?;
+            match &stmt {
+                Stmt::Import(import) => {
+                    self.import(key, import, &mut symtable, &mut origins)?
+                }
+                Stmt::TypeDef(name, _) => {
+                    origins.insert(name.clone(), key.clone());
+                }
+                Stmt::TableSchema(table) => {
+                    origins.insert(table.get_name(), key.clone());
+                }
+                Stmt::Alter(alter) => {
+                    origins.remove(&alter.get_table_name());
+                    if let Some(name) = alter.get_new_table_name() {
+                        origins.insert(name, key.clone());
+                    }
+                }
+                // views aren't exported, as their queries name tables in
+                // the module's own scope
+                Stmt::Schema(_)
+                | Stmt::View(_)
+                | Stmt::Select(_)
+                | Stmt::Insert(_)
+                | Stmt::Update(_)
+                | Stmt::Delete(_) => {}
+            }
+            builder.add_stmt(stmt)?;
+            tq.consume_eq(Token::Semicolon)?;
+        }
+
+        let schema = builder.build();
+        schema.validate_spreadsheet_schema()?;
+        Ok((schema, Module { symtable, origins }))
+    }
+
+    /// Resolves `import`, written in the file `from`, and adds the symbols it
+    /// imports to `symtable`.
+    pub fn import(
+        &mut self,
+        from: &str,
+        import: &ImportStmt,
+        symtable: &mut SymbolTable,
+        origins: &mut SymbolOrigins,
+    ) -> anyhow::Result<()> {
+        let key = self.loader.resolve(Some(from), &import.path)?;
+        if !self.modules.contains_key(&key) {
+            self.parse_file(key.clone())?;
+        }
+        let module = self.modules[&key].clone();
+
+        // only the symbols the module defines itself are exported
+        let mut names: Vec<Rc<str>> = match &import.names {
+            Some(names) => names.clone(),
+            None => module
+                .origins
+                .iter()
+                .filter(|(_, origin)| **origin == key)
+                .map(|(name, _)| name.clone())
+                .collect(),
+        };
+        names.sort();
+
+        for name in names {
+            let symbol = match module.symtable.get(&name) {
+                Some(symbol) if module.origins.get(&name) == Some(&key) => {
+                    symbol.clone()
+                }
+                _ => {
+                    return Err(anyhow::anyhow!("{key} doesn't define {name}"));
+                }
+            };
+            let local_name: Rc<str> = match &import.alias {
+                Some(alias) => format!("{alias}.{name}").into(),
+                None => name.clone(),
+            };
+
+            match origins.get(&local_name) {
+                // the same definition imported along another path
+                Some(origin) if *origin == key => continue,
+                Some(origin) => {
+                    return Err(anyhow::anyhow!(
+                        "Symbol {local_name} imported from {key} is already \
+                        defined in {origin}!"
+                    ));
+                }
+                None => {}
+            }
+
+            symtable.insert(local_name.clone(), symbol);
+            origins.insert(local_name, key.clone());
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses and validates the schema file at `path`, resolving its imports
+/// through `loader`.
+pub fn parse_schema_with_loader<L: SchemaLoader + ?Sized>(
+    path: &str,
+    loader: &L,
+) -> anyhow::Result<SpreadsheetSchema> {
+    let key = loader.resolve(None, path)?;
+    Importer::new(loader).parse_file(key)
+}
@@ -1,11 +1,15 @@
 use rlrl::lex::*;
-use std::{fmt::Display, rc::Rc};
+use rust_decimal::{Decimal, prelude::ToPrimitive};
+use std::{fmt::Display, rc::Rc, str::FromStr};
+
+use crate::ql::expr::Param;
 
 /// A literal in the query language.
 #[derive(Clone, Debug, PartialEq)]
 pub enum Literal {
-    Int(i32),
+    Int(i64),
     Dbl(f64),
+    Decimal(Decimal),
     Str(Rc<str>),
 }
 
@@ -26,7 +30,7 @@ impl Literal {
         }
     }
 
-    pub fn is_i32(&self) -> bool {
+    pub fn is_i64(&self) -> bool {
         match self {
             Self::Int(_) => true,
             _ => false,
@@ -35,7 +39,7 @@ impl Literal {
 
     /// Clones the int value if `self` is a `Literal::Int`, otherwise returns
     /// `None`.
-    pub fn get_i32(&self) -> Option<i32> {
+    pub fn get_i64(&self) -> Option<i64> {
         match self {
             Self::Int(val) => Some(val.clone()),
             _ => None,
@@ -44,7 +48,7 @@ impl Literal {
 
     pub fn is_f64(&self) -> bool {
         match self {
-            Self::Dbl(_) | Self::Int(_) => true,
+            Self::Dbl(_) | Self::Int(_) | Self::Decimal(_) => true,
             _ => false,
         }
     }
@@ -55,9 +59,37 @@ impl Literal {
         match self {
             Self::Dbl(val) => Some(val.clone()),
             Self::Int(val) => Some(*val as f64),
+            Self::Decimal(val) => val.to_f64(),
+            _ => None,
+        }
+    }
+
+    /// Returns the exact decimal value of a number, or `None` if `self` isn't
+    /// a number or is too large for a decimal. Doubles are read as the
+    /// shortest decimal that converts back to them.
+    pub fn get_decimal(&self) -> Option<Decimal> {
+        match self {
+            Self::Int(val) => Some(Decimal::from(*val)),
+            Self::Dbl(val) => Decimal::from_str(&val.to_string()).ok(),
+            Self::Decimal(val) => Some(*val),
             _ => None,
         }
     }
+
+    /// Returns the negation of a number, or `None` if `self` isn't a number
+    /// or its negation overflows. Integers just too large for 64 bits, which
+    /// are lexed as decimals, negate to the smallest 64-bit integer.
+    pub fn checked_neg(&self) -> Option<Self> {
+        match self {
+            Self::Int(val) => val.checked_neg().map(Self::Int),
+            Self::Dbl(val) => Some(Self::Dbl(-val)),
+            Self::Decimal(val) if val.scale() == 0 => {
+                Some(i64::try_from(-val).map_or(Self::Decimal(-val), Self::Int))
+            }
+            Self::Decimal(val) => Some(Self::Decimal(-val)),
+            Self::Str(_) => None,
+        }
+    }
 }
 
 impl Display for Literal {
@@ -65,9 +97,105 @@ impl Display for Literal {
         match self {
             Literal::Int(val) => write!(f, "{val}"),
             Literal::Dbl(val) => write!(f, "{val}"),
-            Literal::Str(val) => write!(f, "{val}"),
+            Literal::Decimal(val) => write!(f, "{val}"),
+            Literal::Str(val) => write!(f, "{}", quote_str(val)),
+        }
+    }
+}
+
+/// Keywords of the query language, which can't be used as bare identifiers.
+/// Other keywords, such as `select` or `order`, are only keywords where a
+/// statement expects them, so they can still name tables and columns.
+pub const KEYWORDS: [&str; 2] = ["type", "table"];
+
+/// Quotes `s` as a string literal, escaping any characters that can't appear
+/// in one as they are.
+pub fn quote_str(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => {
+                quoted.push_str(&format!("\\u{{{:x}}}", c as u32))
+            }
+            c => quoted.push(c),
         }
     }
+    quoted.push('"');
+    quoted
+}
+
+/// Formats a table, column or type name so it lexes back to the same name:
+/// as is if it's a valid identifier, otherwise as a string literal.
+pub fn fmt_name(name: &str) -> String {
+    let mut chars = name.chars();
+    let is_ident = chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !KEYWORDS.contains(&name);
+    if is_ident {
+        name.to_string()
+    } else {
+        quote_str(name)
+    }
+}
+
+/// Decodes the escape sequences in `s`, the contents of a string literal
+/// without its quotes.
+pub fn unescape_str(s: &str) -> anyhow::Result<String> {
+    let mut unescaped = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('"') => unescaped.push('"'),
+            Some('\\') => unescaped.push('\\'),
+            Some('n') => unescaped.push('\n'),
+            Some('t') => unescaped.push('\t'),
+            Some('u') => {
+                if chars.next() != Some('{') {
+                    return Err(anyhow::anyhow!(
+                        "Expected '{{' after \\u in string literal!"
+                    ));
+                }
+                let mut digits = String::new();
+                let mut is_closed = false;
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        is_closed = true;
+                        break;
+                    }
+                    digits.push(c);
+                }
+                let c = u32::from_str_radix(&digits, 16)
+                    .ok()
+                    .filter(|_| is_closed && (1..=6).contains(&digits.len()))
+                    .and_then(char::from_u32)
+                    .ok_or(anyhow::anyhow!(
+                        "Invalid unicode escape \\u{{{digits}}} in string \
+                        literal!"
+                    ))?;
+                unescaped.push(c);
+            }
+            Some(c) => {
+                return Err(anyhow::anyhow!(
+                    "Unrecognised escape sequence \\{c} in string literal!"
+                ));
+            }
+            None => {
+                return Err(anyhow::anyhow!(
+                    "Unterminated escape sequence in string literal!"
+                ));
+            }
+        }
+    }
+    Ok(unescaped)
 }
 
 /// Enum representing the tokens available to the lexer.
@@ -82,18 +210,31 @@ pub enum Token {
     Semicolon,
     Comma,
     Equals,
+    NotEquals,
     QMark,
+    Bang,
+    OBrace,
+    CBrace,
+    OBracket,
+    CBracket,
+    Dot,
+    Plus,
+    Minus,
+    Star,
+    Slash,
 
     // kwds
     TypeKwd,
     TableKwd,
-    SchemaKwd,
 
     // ident
     Ident(Rc<str>),
 
     // literals
     Literal(Literal),
+
+    // positional parameters; named ones are a colon before an ident
+    Param(Param),
 }
 
 impl Token {
@@ -130,6 +271,10 @@ impl Token {
     }
 }
 
+// Digits of a number literal, which may be grouped with `_`.
+const DIGITS: &str = r"[0-9](?:_?[0-9])*";
+const EXPONENT: &str = r"[eE][+\-]?[0-9]+";
+
 /// Function to setup the lexer for testing
 pub fn setup_lexer() -> Lexer<Token> {
     let mut lexer: Lexer<Token> = Lexer::new();
@@ -149,7 +294,18 @@ pub fn setup_lexer() -> Lexer<Token> {
     lexer.add_rule(r";", |_| LexResult::Token(Token::Semicolon));
     lexer.add_rule(r"\,", |_| LexResult::Token(Token::Comma));
     lexer.add_rule(r"=", |_| LexResult::Token(Token::Equals));
+    lexer.add_rule(r"!=", |_| LexResult::Token(Token::NotEquals));
     lexer.add_rule(r"\?", |_| LexResult::Token(Token::QMark));
+    lexer.add_rule(r"!", |_| LexResult::Token(Token::Bang));
+    lexer.add_rule(r"\{", |_| LexResult::Token(Token::OBrace));
+    lexer.add_rule(r"\}", |_| LexResult::Token(Token::CBrace));
+    lexer.add_rule(r"\[", |_| LexResult::Token(Token::OBracket));
+    lexer.add_rule(r"\]", |_| LexResult::Token(Token::CBracket));
+    lexer.add_rule(r"\.", |_| LexResult::Token(Token::Dot));
+    lexer.add_rule(r"\+", |_| LexResult::Token(Token::Plus));
+    lexer.add_rule(r"\-", |_| LexResult::Token(Token::Minus));
+    lexer.add_rule(r"\*", |_| LexResult::Token(Token::Star));
+    lexer.add_rule(r"/", |_| LexResult::Token(Token::Slash));
 
     // kwds
     lexer.add_rule(r"type", |_| LexResult::Token(Token::TypeKwd));
@@ -160,23 +316,77 @@ pub fn setup_lexer() -> Lexer<Token> {
         LexResult::Token(Token::Ident(re_match.as_str().into()))
     });
 
-    // literals
-    lexer.add_rule(r"\-?[0-9]+", |re_match| {
-        match re_match.as_str().parse::<i32>() {
-            Ok(v) => LexResult::Token(Token::Literal(Literal::Int(v))),
-            Err(e) => LexResult::Error(e.into()),
+    // params
+    lexer.add_rule(r"\$[0-9]+", |re_match| {
+        match re_match.as_str()[1..].parse::<usize>() {
+            Ok(idx) if idx > 0 => {
+                LexResult::Token(Token::Param(Param::Positional(idx)))
+            }
+            _ => LexResult::Error(anyhow::anyhow!(
+                "Invalid parameter {}, as they're numbered from 1!",
+                re_match.as_str()
+            )),
+        }
+    });
+
+    // literals, which are unsigned, as `-` is an operator
+    lexer.add_rule(DIGITS, |re_match| {
+        let s = re_match.as_str().replace('_', "");
+        // integers too large for 64 bits are read as decimals
+        match (s.parse::<i64>(), Decimal::from_str_exact(&s)) {
+            (Ok(v), _) => LexResult::Token(Token::Literal(Literal::Int(v))),
+            (_, Ok(v)) => LexResult::Token(Token::Literal(Literal::Decimal(v))),
+            (Err(e), _) => LexResult::Error(e.into()),
         }
     });
-    lexer.add_rule(r"\-?[0-9]+(\.[0-9]+)?", |re_match| {
-        match re_match.as_str().parse::<f64>() {
-            Ok(v) => LexResult::Token(Token::Literal(Literal::Dbl(v))),
-            Err(e) => LexResult::Error(e.into()),
+    lexer.add_rule(r"0[xX][0-9a-fA-F](?:_?[0-9a-fA-F])*", |re_match| {
+        let s = re_match.as_str().replace('_', "");
+        // like decimal integers, those too large for 64 bits are read as
+        // decimals
+        match u64::from_str_radix(&s[2..], 16) {
+            Ok(v) => LexResult::Token(Token::Literal(
+                i64::try_from(v)
+                    .map_or(Literal::Decimal(Decimal::from(v)), Literal::Int),
+            )),
+            Err(_) => LexResult::Error(anyhow::anyhow!(
+                "Hex literal {} is too large for a 64-bit integer!",
+                re_match.as_str()
+            )),
         }
     });
-    lexer.add_rule("\"[^\"]*\"", |re_match| {
-        LexResult::Token(Token::Literal(Literal::Str(
-            re_match.as_str()[1..re_match.len() - 1].into(),
-        )))
+    lexer.add_rule(
+        &format!(r"(?:{DIGITS})?\.{DIGITS}(?:{EXPONENT})?|{DIGITS}{EXPONENT}"),
+        |re_match| {
+            let s = re_match.as_str().replace('_', "");
+            let v = match s.parse::<f64>() {
+                Ok(v) if v.is_finite() => v,
+                _ => {
+                    return LexResult::Error(anyhow::anyhow!(
+                        "Number literal {} is out of range!",
+                        re_match.as_str()
+                    ));
+                }
+            };
+            let exact = if s.contains(['e', 'E']) {
+                Decimal::from_scientific(&s)
+            } else {
+                Decimal::from_str_exact(&s)
+            };
+            // literals with more digits than a double holds are read as
+            // decimals
+            match exact {
+                Ok(exact) if Decimal::from_str(&v.to_string()) != Ok(exact) => {
+                    LexResult::Token(Token::Literal(Literal::Decimal(exact)))
+                }
+                _ => LexResult::Token(Token::Literal(Literal::Dbl(v))),
+            }
+        },
+    );
+    lexer.add_rule(r#""(?:[^"\\]|\\[\s\S])*""#, |re_match| match unescape_str(
+        &re_match.as_str()[1..re_match.len() - 1],
+    ) {
+        Ok(v) => LexResult::Token(Token::Literal(Literal::Str(v.into()))),
+        Err(e) => LexResult::Error(e),
     });
 
     lexer
@@ -1,14 +1,33 @@
+pub mod alter;
+pub mod dml;
+pub mod expr;
+pub mod format;
+pub mod import;
 pub mod lex;
 pub mod parse;
+pub mod prepare;
+pub mod query;
 
 use std::{collections::HashMap, rc::Rc};
 
-use crate::core::schema::{SharedDataType, SharedTableSchema};
+use crate::{
+    core::schema::{
+        SchemaHeader, SharedDataType, SharedTableSchema, SharedViewSchema,
+    },
+    ql::{
+        alter::AlterStmt,
+        dml::{DeleteStmt, InsertStmt, UpdateStmt},
+        import::ImportStmt,
+        query::SelectStmt,
+    },
+};
 
 /// A symbol in the symbol table; the value of a variable.
+#[derive(Clone)]
 pub enum Symbol {
     TableSchema(SharedTableSchema),
     DataType(SharedDataType),
+    View(SharedViewSchema),
 }
 
 /// A symbol table whose keys are identifiers and values are of type `Symbol`.
@@ -16,8 +35,16 @@ pub type SymbolTable = HashMap<Rc<str>, Symbol>;
 
 /// A statement in the query language.
 pub enum Stmt {
+    Schema(SchemaHeader),
+    Import(ImportStmt),
     TableSchema(SharedTableSchema),
     // Note Stmt::TypeDef stores a name for the type while Symbol::DataType
     // does not
     TypeDef(Rc<str>, SharedDataType),
+    View(SharedViewSchema),
+    Alter(AlterStmt),
+    Select(SelectStmt),
+    Insert(InsertStmt),
+    Update(UpdateStmt),
+    Delete(DeleteStmt),
 }
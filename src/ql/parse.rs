@@ -2,10 +2,20 @@ use std::collections::HashMap;
 use std::rc::Rc;
 
 use crate::core::schema::{
-    ColumnSchema, DBL_TYPE_NAME, DataType, DblDataType, INT_TYPE_NAME,
-    IntDataType, STR_TYPE_NAME, SpreadsheetSchema, StrDataType, TableSchema,
+    ColumnSchema, DBL_TYPE_NAME, DECIMAL_TYPE_NAME, DataType, DblDataType,
+    DecimalDataType, INT_TYPE_NAME, INT64_TYPE_NAME, IntDataType, IntWidth,
+    LengthUnit, STR_TYPE_NAME, SchemaHeader, SharedViewSchema,
+    SpreadsheetSchema, StrDataType, TableSchema, ViewSchema,
+};
+use crate::ql::{
+    Stmt, Symbol, SymbolTable,
+    alter::AlterStmt,
+    dml::{Assignment, DeleteStmt, InsertStmt, UpdateStmt},
+    expr::{Aggregate, AggregateFunc, BinaryOp, CompareOp, Cond, Expr, Param},
+    import::ImportStmt,
+    lex::{Literal, Token},
+    query::{Join, JoinKind, OrderBy, SelectItem, SelectStmt},
 };
-use crate::ql::{Stmt, Symbol, SymbolTable, lex::Token};
 use rlrl::parse::{ParseResult, TokenQueue};
 pub trait Parse: Sized {
     fn parse(
@@ -14,6 +24,60 @@ pub trait Parse: Sized {
     ) -> ParseResult<Self>;
 }
 
+// Consumes the bracket opening a number type's bounds, returning whether the
+// min is exclusive, or `None` if the type has no bounds. `<` and `[` include
+// the min, `(` excludes it.
+fn consume_open_bound(tq: &mut TokenQueue<Token>) -> Option<bool> {
+    if tq.consume_eq(Token::OAngle).is_ok()
+        || tq.consume_eq(Token::OBracket).is_ok()
+    {
+        Some(false)
+    } else if tq.consume_eq(Token::OParen).is_ok() {
+        Some(true)
+    } else {
+        None
+    }
+}
+
+// Consumes a literal, which may be a number negated with `-`, if one comes
+// next.
+fn consume_signed_literal(
+    tq: &mut TokenQueue<Token>,
+) -> anyhow::Result<Option<Literal>> {
+    let mut attempt = tq.clone();
+    let negated = attempt.consume_eq(Token::Minus).is_ok();
+    let Ok(token) = attempt.consume_matching(|token| token.is_literal()) else {
+        if negated {
+            return Err(anyhow::anyhow!("Expected a number after '-'!"));
+        }
+        return Ok(None);
+    };
+    let literal = token.get_literal().unwrap().clone();
+    let literal = if negated {
+        literal
+            .checked_neg()
+            .ok_or(anyhow::anyhow!("Couldn't negate {literal}!"))?
+    } else {
+        literal
+    };
+    *tq = attempt;
+    Ok(Some(literal))
+}
+
+// Consumes the bracket closing a number type's bounds, returning whether the
+// max is exclusive.
+fn consume_close_bound(tq: &mut TokenQueue<Token>) -> anyhow::Result<bool> {
+    if tq.consume_eq(Token::CAngle).is_ok()
+        || tq.consume_eq(Token::CBracket).is_ok()
+    {
+        Ok(false)
+    } else if tq.consume_eq(Token::CParen).is_ok() {
+        Ok(true)
+    } else {
+        Err(anyhow::anyhow!("Expected '>', ']' or ')' after bounds!"))
+    }
+}
+
 impl Parse for IntDataType {
     fn parse(
         tq: &TokenQueue<Token>,
@@ -22,55 +86,49 @@ impl Parse for IntDataType {
         // create a mutable copy
         let mut tq = tq.clone();
 
-        let (min, max) = if tq.consume_eq(Token::OAngle).is_err() {
-            (None, None)
-        } else {
+        let (min, max, exclusive) = if let Some(min_exclusive) =
+            consume_open_bound(&mut tq)
+        {
             // consume min
-            let min = match tq.clone().peek_matching(|token| token.is_literal())
-            {
-                Ok(token) => {
-                    tq.increment()?;
-                    let literal = token.get_literal().unwrap();
-                    if literal.is_i32() {
-                        Some(literal.get_i32().unwrap())
-                    } else {
-                        return Err(anyhow::anyhow!(
-                            "Couldn't parse int literal!"
-                        ));
-                    }
+            let min = match consume_signed_literal(&mut tq)? {
+                Some(literal) if literal.is_i64() => {
+                    Some(literal.get_i64().unwrap())
                 }
-                Err(_) => None,
+                Some(_) => {
+                    return Err(anyhow::anyhow!("Couldn't parse int literal!"));
+                }
+                None => None,
             };
 
             tq.consume_eq(Token::Comma)?;
 
             // consume max
-            let max = match tq.clone().peek_matching(|token| token.is_literal())
-            {
-                Ok(token) => {
-                    tq.increment()?;
-                    let literal = token.get_literal().unwrap();
-                    if literal.is_i32() {
-                        Some(literal.get_i32().unwrap())
-                    } else {
-                        return Err(anyhow::anyhow!(
-                            "Couldn't parse int literal!"
-                        ));
-                    }
+            let max = match consume_signed_literal(&mut tq)? {
+                Some(literal) if literal.is_i64() => {
+                    Some(literal.get_i64().unwrap())
                 }
-                Err(_) => None,
+                Some(_) => {
+                    return Err(anyhow::anyhow!("Couldn't parse int literal!"));
+                }
+                None => None,
             };
 
-            tq.consume_eq(Token::CAngle)?;
+            let max_exclusive = consume_close_bound(&mut tq)?;
 
-            (min, max)
+            (min, max, (min_exclusive, max_exclusive))
+        } else {
+            (None, None, (false, false))
         };
 
         // consume ?
         let nullable = tq.consume_eq(Token::QMark).is_ok();
 
         // done
-        Ok((IntDataType::new(min, max, nullable), tq.get_idx()))
+        Ok((
+            IntDataType::new(min, max, nullable)
+                .with_exclusive_bounds(exclusive.0, exclusive.1),
+            tq.get_idx(),
+        ))
     }
 }
 
@@ -82,58 +140,72 @@ impl Parse for DblDataType {
         // create a mutable copy
         let mut tq = tq.clone();
 
-        let (min, max) = if tq.consume_eq(Token::OAngle).is_err() {
-            (None, None)
-        } else {
-            let min = match tq.clone().peek_matching(|token| token.is_literal())
-            {
-                Ok(token) => {
-                    tq.increment()?;
-                    let literal = token.get_literal().unwrap();
-                    if literal.is_f64() {
-                        Some(literal.get_f64().unwrap())
-                    } else {
-                        return Err(anyhow::anyhow!(
-                            "Couldn't parse dbl literal!"
-                        ));
-                    }
+        let (min, max, exclusive) = if let Some(min_exclusive) =
+            consume_open_bound(&mut tq)
+        {
+            let min = match consume_signed_literal(&mut tq)? {
+                Some(literal) if literal.is_f64() => {
+                    Some(literal.get_f64().unwrap())
                 }
-                Err(_) => None,
+                Some(_) => {
+                    return Err(anyhow::anyhow!("Couldn't parse dbl literal!"));
+                }
+                None => None,
             };
 
             tq.consume_eq(Token::Comma)?;
 
             // consume max
-            let max = match tq.clone().peek_matching(|token| token.is_literal())
-            {
-                Ok(token) => {
-                    tq.increment()?;
-                    let literal = token.get_literal().unwrap();
-                    if literal.is_f64() {
-                        Some(literal.get_f64().unwrap())
-                    } else {
-                        return Err(anyhow::anyhow!(
-                            "Couldn't parse dbl literal!"
-                        ));
-                    }
+            let max = match consume_signed_literal(&mut tq)? {
+                Some(literal) if literal.is_f64() => {
+                    Some(literal.get_f64().unwrap())
                 }
-                Err(_) => None,
+                Some(_) => {
+                    return Err(anyhow::anyhow!("Couldn't parse dbl literal!"));
+                }
+                None => None,
             };
 
-            tq.consume_eq(Token::CAngle)?;
+            let max_exclusive = consume_close_bound(&mut tq)?;
 
-            (min, max)
+            (min, max, (min_exclusive, max_exclusive))
+        } else {
+            (None, None, (false, false))
         };
 
         // consume ?
         let nullable = tq.consume_eq(Token::QMark).is_ok();
 
         // done
-        Ok((DblDataType::new(min, max, nullable), tq.get_idx()))
+        Ok((
+            DblDataType::new(min, max, nullable)
+                .with_exclusive_bounds(exclusive.0, exclusive.1),
+            tq.get_idx(),
+        ))
     }
 }
 
-impl Parse for StrDataType {
+// Consumes a decimal's precision or scale, if one is given.
+fn consume_digits(
+    tq: &mut TokenQueue<Token>,
+    what: &str,
+) -> anyhow::Result<Option<u32>> {
+    match tq.clone().peek_matching(|token| token.is_literal()) {
+        Ok(token) => {
+            tq.increment()?;
+            let digits = token
+                .get_literal()
+                .unwrap()
+                .get_i64()
+                .and_then(|val| u32::try_from(val).ok())
+                .ok_or(anyhow::anyhow!("Couldn't parse decimal {what}!"))?;
+            Ok(Some(digits))
+        }
+        Err(_) => Ok(None),
+    }
+}
+
+impl Parse for DecimalDataType {
     fn parse(
         tq: &TokenQueue<Token>,
         _symtable: &mut SymbolTable,
@@ -141,8 +213,37 @@ impl Parse for StrDataType {
         // create a mutable copy
         let mut tq = tq.clone();
 
-        let (min, max) = if tq.consume_eq(Token::OAngle).is_err() {
+        let (precision, scale) = if tq.consume_eq(Token::OAngle).is_err() {
             (None, None)
+        } else {
+            let precision = consume_digits(&mut tq, "precision")?;
+            tq.consume_eq(Token::Comma)?;
+            let scale = consume_digits(&mut tq, "scale")?;
+            tq.consume_eq(Token::CAngle)?;
+            (precision, scale)
+        };
+
+        // consume ?
+        let nullable = tq.consume_eq(Token::QMark).is_ok();
+
+        // done
+        Ok((
+            DecimalDataType::new(precision, scale, nullable),
+            tq.get_idx(),
+        ))
+    }
+}
+
+impl Parse for StrDataType {
+    fn parse(
+        tq: &TokenQueue<Token>,
+        _symtable: &mut SymbolTable,
+    ) -> ParseResult<Self> {
+        // create a mutable copy
+        let mut tq = tq.clone();
+
+        let (min, max, unit) = if tq.consume_eq(Token::OAngle).is_err() {
+            (None, None, None)
         } else {
             // consume min
             let min = match tq.clone().peek_matching(|token| token.is_literal())
@@ -152,7 +253,7 @@ impl Parse for StrDataType {
                     let literal = token.get_literal().unwrap();
                     Some(
                         literal
-                            .get_i32()
+                            .get_i64()
                             .ok_or(anyhow::anyhow!(
                                 "Couldn't get size literal!"
                             ))?
@@ -172,7 +273,7 @@ impl Parse for StrDataType {
                     let literal = token.get_literal().unwrap();
                     Some(
                         literal
-                            .get_i32()
+                            .get_i64()
                             .ok_or(anyhow::anyhow!(
                                 "Couldn't get size literal!"
                             ))?
@@ -182,16 +283,55 @@ impl Parse for StrDataType {
                 Err(_) => None,
             };
 
+            // consume the length unit
+            let unit = match tq
+                .clone()
+                .peek_matching(|token| matches!(token, Token::Ident(_)))
+            {
+                Ok(token) => {
+                    tq.increment()?;
+                    let name = token.get_ident_or_str_literal().unwrap();
+                    Some(LengthUnit::from_name(&name).ok_or(
+                        anyhow::anyhow!("Unrecognised length unit {name}!"),
+                    )?)
+                }
+                Err(_) => None,
+            };
+
             tq.consume_eq(Token::CAngle)?;
 
-            (min, max)
+            (min, max, unit)
         };
 
         // consume ?
         let nullable = tq.consume_eq(Token::QMark).is_ok();
 
         // done
-        Ok((StrDataType::new(min, max, nullable), tq.get_idx()))
+        let dtype = StrDataType::new(min, max, nullable);
+        Ok((
+            match unit {
+                Some(unit) => dtype.with_unit(unit),
+                None => dtype,
+            },
+            tq.get_idx(),
+        ))
+    }
+}
+
+// Consumes the `!` that narrows a refinement of a nullable type to a
+// non-nullable one. Returns whether the refinement is nullable, or `None` if
+// it inherits its base's nullability.
+fn consume_refined_nullable(
+    tq: &mut TokenQueue<Token>,
+    nullable: bool,
+) -> anyhow::Result<Option<bool>> {
+    match (nullable, tq.consume_eq(Token::Bang).is_ok()) {
+        (true, true) => Err(anyhow::anyhow!(
+            "A type can't be both nullable and non-nullable!"
+        )),
+        (true, false) => Ok(Some(true)),
+        (false, true) => Ok(Some(false)),
+        (false, false) => Ok(None),
     }
 }
 
@@ -203,36 +343,324 @@ fn parse_data_type(
 
     let ident_tok =
         tq.consume_matching(|tok| tok.is_ident_or_str_literal_tok())?;
-    let ident = ident_tok
+    let mut ident = ident_tok
         .get_ident_or_str_literal()
         .ok_or(anyhow::anyhow!("Expected an identifier!"))?;
 
+    // qualified name of a type imported under a namespace
+    if tq.consume_eq(Token::Dot).is_ok() {
+        let member = tq
+            .consume_matching(|tok| tok.is_ident_or_str_literal_tok())?
+            .get_ident_or_str_literal()
+            .ok_or(anyhow::anyhow!("Expected an identifier!"))?;
+        ident = format!("{ident}.{member}").into();
+    }
+
     match &ident as &str {
         INT_TYPE_NAME => {
             let (dtype, end) = IntDataType::parse(&tq, symtable)?;
             return Ok((Rc::new(dtype), end));
         }
+        INT64_TYPE_NAME => {
+            let (dtype, end) = IntDataType::parse(&tq, symtable)?;
+            return Ok((Rc::new(dtype.with_width(IntWidth::Bits64)), end));
+        }
         DBL_TYPE_NAME => {
             let (dtype, end) = DblDataType::parse(&tq, symtable)?;
             return Ok((Rc::new(dtype), end));
         }
+        DECIMAL_TYPE_NAME => {
+            let (dtype, end) = DecimalDataType::parse(&tq, symtable)?;
+            return Ok((Rc::new(dtype), end));
+        }
         STR_TYPE_NAME => {
             let (dtype, end) = StrDataType::parse(&tq, symtable)?;
             return Ok((Rc::new(dtype), end));
         }
         _ => {
-            if let Some(Symbol::DataType(dtype)) = symtable.get(&ident) {
-                return Ok((dtype.clone(), tq.get_idx()));
-            } else {
+            let Some(Symbol::DataType(base)) = symtable.get(&ident) else {
                 return Err(anyhow::anyhow!(
                     "Unrecognised type name {}",
                     ident
                 ));
+            };
+            let base = base.clone();
+
+            // a named type followed by bounds, `?` or `!` is a refinement
+            let is_refinement = [
+                Token::OAngle,
+                Token::OBracket,
+                Token::OParen,
+                Token::QMark,
+                Token::Bang,
+            ]
+            .into_iter()
+            .any(|tok| tq.clone().consume_eq(tok).is_ok());
+            if !is_refinement {
+                return Ok((base, tq.get_idx()));
+            }
+
+            let any = base.as_any();
+            if let Some(base) = any.downcast_ref::<IntDataType>() {
+                let refinement =
+                    tq.parse_with_mut(IntDataType::parse, symtable)?;
+                let nullable = consume_refined_nullable(
+                    &mut tq,
+                    refinement.get_nullable(),
+                )?;
+                let refined = base.refine(ident, &refinement, nullable)?;
+                return Ok((Rc::new(refined), tq.get_idx()));
             }
+            if let Some(base) = any.downcast_ref::<DblDataType>() {
+                let refinement =
+                    tq.parse_with_mut(DblDataType::parse, symtable)?;
+                let nullable = consume_refined_nullable(
+                    &mut tq,
+                    refinement.get_nullable(),
+                )?;
+                let refined = base.refine(ident, &refinement, nullable)?;
+                return Ok((Rc::new(refined), tq.get_idx()));
+            }
+            if let Some(base) = any.downcast_ref::<DecimalDataType>() {
+                let refinement =
+                    tq.parse_with_mut(DecimalDataType::parse, symtable)?;
+                let nullable = consume_refined_nullable(
+                    &mut tq,
+                    refinement.get_nullable(),
+                )?;
+                let refined = base.refine(ident, &refinement, nullable)?;
+                return Ok((Rc::new(refined), tq.get_idx()));
+            }
+            if let Some(base) = any.downcast_ref::<StrDataType>() {
+                let refinement =
+                    tq.parse_with_mut(StrDataType::parse, symtable)?;
+                let nullable = consume_refined_nullable(
+                    &mut tq,
+                    refinement.get_nullable(),
+                )?;
+                let refined = base.refine(ident, &refinement, nullable)?;
+                return Ok((Rc::new(refined), tq.get_idx()));
+            }
+            Err(anyhow::anyhow!("Type {ident} can't be refined"))
         }
     }
 }
 
+// Number literals are read as doubles unless they have more digits than a
+// double holds, so convert them to the kind of number the column holds.
+fn convert_default(lit: Literal, dtype: &dyn DataType) -> Literal {
+    let any = dtype.as_any();
+    match &lit {
+        Literal::Int(_) | Literal::Dbl(_) if any.is::<DecimalDataType>() => {
+            lit.get_decimal().map_or(lit, Literal::Decimal)
+        }
+        Literal::Decimal(_) if any.is::<DblDataType>() => {
+            lit.get_f64().map_or(lit, Literal::Dbl)
+        }
+        _ => lit,
+    }
+}
+
+// Consumes the bracketed argument of the aggregate function `func`, such as
+// the `(distinct userId)` of `count(distinct userId)`.
+fn parse_aggregate(
+    tq: &mut TokenQueue<Token>,
+    func: AggregateFunc,
+) -> anyhow::Result<Aggregate> {
+    tq.consume_eq(Token::OParen)
+        .map_err(|_| anyhow::anyhow!("Couldn't get '('"))?;
+    let aggregate =
+        if func == AggregateFunc::Count && tq.consume_eq(Token::Star).is_ok() {
+            Aggregate::new(func, None, false)
+        } else {
+            let distinct = consume_contextual_kwd(tq, "distinct").is_ok();
+            Aggregate::new(func, Some(parse_expr(tq)?), distinct)
+        };
+    tq.consume_eq(Token::CParen)
+        .map_err(|_| anyhow::anyhow!("Couldn't get ')'"))?;
+    Ok(aggregate)
+}
+
+// Consumes a literal, column name, parameter, aggregate, negation or
+// bracketed expression. Aggregate function names are only keywords before a
+// bracket, so columns can still be called `min` or `count`. A column
+// qualified with its table, such as `Movies.movieName`, is named
+// `Table.column`.
+fn parse_operand(tq: &mut TokenQueue<Token>) -> anyhow::Result<Expr> {
+    let token = tq
+        .consume()
+        .map_err(|_| anyhow::anyhow!("Expected an expression!"))?
+        .clone();
+    match token {
+        Token::Literal(lit) => Ok(Expr::Literal(lit)),
+        Token::Ident(name) if tq.clone().consume_eq(Token::OParen).is_ok() => {
+            let func = AggregateFunc::from_name(&name)
+                .ok_or(anyhow::anyhow!("Unknown function {name}!"))?;
+            Ok(Expr::Aggregate(Box::new(parse_aggregate(tq, func)?)))
+        }
+        Token::Ident(name) if tq.clone().consume_eq(Token::Dot).is_ok() => {
+            tq.increment()?;
+            match tq.consume()? {
+                Token::Ident(column_name) => {
+                    Ok(Expr::Column(format!("{name}.{column_name}").into()))
+                }
+                _ => Err(anyhow::anyhow!("Couldn't get column name!")),
+            }
+        }
+        Token::Ident(name) => Ok(Expr::Column(name)),
+        Token::Param(param) => Ok(Expr::Param(param)),
+        Token::Colon => match tq.consume()? {
+            Token::Ident(name) => Ok(Expr::Param(Param::Named(name.clone()))),
+            _ => Err(anyhow::anyhow!("Couldn't get parameter name!")),
+        },
+        Token::Minus => match parse_operand(tq)? {
+            Expr::Literal(lit) if lit.checked_neg().is_some() => {
+                Ok(Expr::Literal(lit.checked_neg().unwrap()))
+            }
+            operand => Ok(Expr::Neg(Box::new(operand))),
+        },
+        Token::OParen => {
+            let first = parse_operand(tq)?;
+            let expr = parse_binary(tq, first, 0)?;
+            tq.consume_eq(Token::CParen)
+                .map_err(|_| anyhow::anyhow!("Couldn't get ')'"))?;
+            Ok(expr)
+        }
+        _ => Err(anyhow::anyhow!("Expected an expression!")),
+    }
+}
+
+// Returns the binary operator at the front of `tq`.
+fn peek_binary_op(tq: &TokenQueue<Token>) -> Option<BinaryOp> {
+    match tq.clone().consume().ok()? {
+        Token::Plus => Some(BinaryOp::Add),
+        Token::Minus => Some(BinaryOp::Sub),
+        Token::Star => Some(BinaryOp::Mul),
+        Token::Slash => Some(BinaryOp::Div),
+        _ => None,
+    }
+}
+
+// Consumes the operators binding at least as tightly as `min_precedence`
+// that follow `left`, along with their operands.
+fn parse_binary(
+    tq: &mut TokenQueue<Token>,
+    mut left: Expr,
+    min_precedence: u8,
+) -> anyhow::Result<Expr> {
+    while let Some(op) = peek_binary_op(tq)
+        && op.get_precedence() >= min_precedence
+    {
+        tq.increment()?;
+        let right = parse_operand(tq)?;
+        let right = parse_binary(tq, right, op.get_precedence() + 1)?;
+        left = Expr::Binary(op, Box::new(left), Box::new(right));
+    }
+    Ok(left)
+}
+
+fn parse_expr(tq: &mut TokenQueue<Token>) -> anyhow::Result<Expr> {
+    let first = parse_operand(tq)?;
+    parse_binary(tq, first, 0)
+}
+
+impl Parse for Expr {
+    fn parse(
+        tq: &TokenQueue<Token>,
+        _symtable: &mut SymbolTable,
+    ) -> ParseResult<Self> {
+        let mut tq = tq.clone();
+        let expr = parse_expr(&mut tq)?;
+        Ok((expr, tq.get_idx()))
+    }
+}
+
+// Consumes a comparison operator. `<=`, `>=` and `<>` are lexed as two
+// tokens, so `<` and `>` can still close a type's bounds before a default.
+fn consume_compare_op(tq: &mut TokenQueue<Token>) -> Option<CompareOp> {
+    if tq.consume_eq(Token::Equals).is_ok() {
+        Some(CompareOp::Eq)
+    } else if tq.consume_eq(Token::NotEquals).is_ok() {
+        Some(CompareOp::Ne)
+    } else if tq.consume_eq(Token::OAngle).is_ok() {
+        if tq.consume_eq(Token::Equals).is_ok() {
+            Some(CompareOp::Le)
+        } else if tq.consume_eq(Token::CAngle).is_ok() {
+            Some(CompareOp::Ne)
+        } else {
+            Some(CompareOp::Lt)
+        }
+    } else if tq.consume_eq(Token::CAngle).is_ok() {
+        if tq.consume_eq(Token::Equals).is_ok() {
+            Some(CompareOp::Ge)
+        } else {
+            Some(CompareOp::Gt)
+        }
+    } else {
+        None
+    }
+}
+
+// Consumes a comparison, null test, negation or bracketed condition.
+fn parse_cond_operand(tq: &mut TokenQueue<Token>) -> anyhow::Result<Cond> {
+    if consume_contextual_kwd(tq, "not").is_ok() {
+        return Ok(Cond::Not(Box::new(parse_cond_operand(tq)?)));
+    }
+
+    // a bracket can hold a condition or start an expression, as in
+    // `(a + 1) > b`
+    let mut attempt = tq.clone();
+    if attempt.consume_eq(Token::OParen).is_ok()
+        && let Ok(cond) = parse_cond(&mut attempt)
+        && attempt.consume_eq(Token::CParen).is_ok()
+    {
+        *tq = attempt;
+        return Ok(cond);
+    }
+
+    let left = parse_expr(tq)?;
+    if consume_contextual_kwd(tq, "is").is_ok() {
+        let negated = consume_contextual_kwd(tq, "not").is_ok();
+        consume_contextual_kwd(tq, "null")?;
+        return Ok(Cond::IsNull {
+            expr: left,
+            negated,
+        });
+    }
+    let op = consume_compare_op(tq)
+        .ok_or(anyhow::anyhow!("Expected a comparison!"))?;
+    let right = parse_expr(tq)?;
+    Ok(Cond::Compare(op, left, right))
+}
+
+// Consumes conditions joined by `and`, which binds tighter than `or`.
+fn parse_cond_and(tq: &mut TokenQueue<Token>) -> anyhow::Result<Cond> {
+    let mut cond = parse_cond_operand(tq)?;
+    while consume_contextual_kwd(tq, "and").is_ok() {
+        cond = Cond::And(Box::new(cond), Box::new(parse_cond_operand(tq)?));
+    }
+    Ok(cond)
+}
+
+fn parse_cond(tq: &mut TokenQueue<Token>) -> anyhow::Result<Cond> {
+    let mut cond = parse_cond_and(tq)?;
+    while consume_contextual_kwd(tq, "or").is_ok() {
+        cond = Cond::Or(Box::new(cond), Box::new(parse_cond_and(tq)?));
+    }
+    Ok(cond)
+}
+
+impl Parse for Cond {
+    fn parse(
+        tq: &TokenQueue<Token>,
+        _symtable: &mut SymbolTable,
+    ) -> ParseResult<Self> {
+        let mut tq = tq.clone();
+        let cond = parse_cond(&mut tq)?;
+        Ok((cond, tq.get_idx()))
+    }
+}
+
 impl Parse for ColumnSchema {
     fn parse(
         tq: &TokenQueue<Token>,
@@ -250,18 +678,21 @@ impl Parse for ColumnSchema {
 
         let column_type = tq.parse_with_mut(parse_data_type, symtable)?;
 
-        let default_value = match tq.consume_eq(Token::Equals) {
-            Ok(_) => Some(
-                tq.consume()?
-                    .get_literal()
-                    .ok_or(anyhow::anyhow!("Couldn't get default value!"))?
-                    .clone(),
-            ),
-            Err(_) => None,
+        // a lone literal is the column's default, any other expression is
+        // the formula it's computed with
+        let (default_value, formula) = match tq.consume_eq(Token::Equals) {
+            Ok(_) => match tq.parse_with_mut(Expr::parse, symtable)? {
+                Expr::Literal(lit) => {
+                    (Some(convert_default(lit, &*column_type)), None)
+                }
+                formula => (None, Some(formula)),
+            },
+            Err(_) => (None, None),
         };
 
         Ok((
-            ColumnSchema::new(column_name, column_type, default_value),
+            ColumnSchema::new(column_name, column_type, default_value)
+                .with_formula(formula),
             tq.get_idx(),
         ))
     }
@@ -299,6 +730,431 @@ impl Parse for TableSchema {
     }
 }
 
+impl Parse for SchemaHeader {
+    fn parse(
+        tq: &TokenQueue<Token>,
+        _symtable: &mut SymbolTable,
+    ) -> ParseResult<Self> {
+        let mut tq = tq.clone();
+
+        let name = tq
+            .consume_matching(|tok| tok.is_ident_or_str_literal_tok())?
+            .get_ident_or_str_literal()
+            .ok_or(anyhow::anyhow!("Couldn't get schema name!"))?;
+
+        // `version` and `description` are only keywords here, so they can
+        // still be used as column names
+        let mut version = None;
+        let mut description = None;
+        loop {
+            if version.is_none()
+                && tq.consume_eq(Token::Ident("version".into())).is_ok()
+            {
+                version = Some(
+                    match tq.consume()?.get_literal().ok_or(anyhow::anyhow!(
+                        "Couldn't get schema version!"
+                    ))? {
+                        Literal::Str(val) => val.clone(),
+                        literal => literal.to_string().into(),
+                    },
+                );
+            } else if description.is_none()
+                && tq.consume_eq(Token::Ident("description".into())).is_ok()
+            {
+                description = Some(
+                    tq.consume()?
+                        .get_literal()
+                        .and_then(|literal| literal.get_str())
+                        .ok_or(anyhow::anyhow!(
+                            "Couldn't get schema description!"
+                        ))?,
+                );
+            } else {
+                break;
+            }
+        }
+
+        Ok((SchemaHeader::new(name, version, description), tq.get_idx()))
+    }
+}
+
+impl Parse for ImportStmt {
+    fn parse(
+        tq: &TokenQueue<Token>,
+        _symtable: &mut SymbolTable,
+    ) -> ParseResult<Self> {
+        let mut tq = tq.clone();
+
+        let names = if tq.consume_eq(Token::OBrace).is_ok() {
+            let mut names = vec![];
+            loop {
+                names.push(
+                    tq.consume_matching(|tok| {
+                        tok.is_ident_or_str_literal_tok()
+                    })?
+                    .get_ident_or_str_literal()
+                    .ok_or(anyhow::anyhow!("Couldn't get imported name!"))?,
+                );
+                if tq.consume_eq(Token::Comma).is_err() {
+                    break;
+                }
+            }
+            tq.consume_eq(Token::CBrace)
+                .map_err(|_| anyhow::anyhow!("Couldn't get '}}'"))?;
+            consume_contextual_kwd(&mut tq, "from")?;
+            Some(names)
+        } else {
+            None
+        };
+
+        let path = tq
+            .consume()?
+            .get_literal()
+            .and_then(|literal| literal.get_str())
+            .ok_or(anyhow::anyhow!("Couldn't get import path!"))?;
+
+        let alias = if names.is_none()
+            && consume_contextual_kwd(&mut tq, "as").is_ok()
+        {
+            Some(
+                tq.consume_matching(|tok| tok.is_ident_or_str_literal_tok())?
+                    .get_ident_or_str_literal()
+                    .ok_or(anyhow::anyhow!("Couldn't get import alias!"))?,
+            )
+        } else {
+            None
+        };
+
+        Ok((ImportStmt::new(path, names, alias), tq.get_idx()))
+    }
+}
+
+// Consumes an identifier or string literal naming a table or column.
+fn consume_name(
+    tq: &mut TokenQueue<Token>,
+    what: &str,
+) -> anyhow::Result<Rc<str>> {
+    tq.consume_matching(|tok| tok.is_ident_or_str_literal_tok())
+        .map_err(|_| anyhow::anyhow!("Couldn't get {what} name!"))?
+        .get_ident_or_str_literal()
+        .ok_or(anyhow::anyhow!("Couldn't get {what} name!"))
+}
+
+// Consumes a word that is only a keyword in this position, such as `column`.
+fn consume_contextual_kwd(
+    tq: &mut TokenQueue<Token>,
+    kwd: &str,
+) -> anyhow::Result<()> {
+    tq.consume_eq(Token::Ident(kwd.into()))
+        .map_err(|_| anyhow::anyhow!("Couldn't get '{kwd}'"))?;
+    Ok(())
+}
+
+impl Parse for AlterStmt {
+    fn parse(
+        tq: &TokenQueue<Token>,
+        symtable: &mut SymbolTable,
+    ) -> ParseResult<Self> {
+        let mut tq = tq.clone();
+
+        let kwd = match tq.consume()? {
+            Token::Ident(kwd) => kwd.clone(),
+            _ => return Err(anyhow::anyhow!("Couldn't parse statement!")),
+        };
+        tq.consume_eq(Token::TableKwd)
+            .map_err(|_| anyhow::anyhow!("Couldn't get 'table'"))?;
+        let table_name = consume_name(&mut tq, "table")?;
+
+        let alter = match &*kwd {
+            "drop" => AlterStmt::DropTable(table_name),
+            "rename" => {
+                consume_contextual_kwd(&mut tq, "to")?;
+                AlterStmt::RenameTable {
+                    old_name: table_name,
+                    new_name: consume_name(&mut tq, "table")?,
+                }
+            }
+            "alter" => match tq.consume()?.clone() {
+                Token::Ident(action) if &*action == "add" => {
+                    consume_contextual_kwd(&mut tq, "column")?;
+                    AlterStmt::AddColumn {
+                        table_name,
+                        column: tq
+                            .parse_with_mut(ColumnSchema::parse, symtable)?,
+                    }
+                }
+                Token::Ident(action) if &*action == "drop" => {
+                    consume_contextual_kwd(&mut tq, "column")?;
+                    AlterStmt::DropColumn {
+                        table_name,
+                        column_name: consume_name(&mut tq, "column")?,
+                    }
+                }
+                Token::Ident(action) if &*action == "rename" => {
+                    consume_contextual_kwd(&mut tq, "column")?;
+                    let old_name = consume_name(&mut tq, "column")?;
+                    consume_contextual_kwd(&mut tq, "to")?;
+                    AlterStmt::RenameColumn {
+                        table_name,
+                        old_name,
+                        new_name: consume_name(&mut tq, "column")?,
+                    }
+                }
+                Token::Ident(action) if &*action == "alter" => {
+                    consume_contextual_kwd(&mut tq, "column")?;
+                    let column_name = consume_name(&mut tq, "column")?;
+                    tq.consume_eq(Token::TypeKwd)
+                        .map_err(|_| anyhow::anyhow!("Couldn't get 'type'"))?;
+                    AlterStmt::AlterColumnType {
+                        table_name,
+                        column_name,
+                        data_type: tq
+                            .parse_with_mut(parse_data_type, symtable)?,
+                    }
+                }
+                _ => {
+                    return Err(anyhow::anyhow!(
+                        "Expected add, drop, rename or alter column!"
+                    ));
+                }
+            },
+            _ => return Err(anyhow::anyhow!("Couldn't parse statement!")),
+        };
+
+        Ok((alter, tq.get_idx()))
+    }
+}
+
+impl Parse for SelectStmt {
+    fn parse(
+        tq: &TokenQueue<Token>,
+        _symtable: &mut SymbolTable,
+    ) -> ParseResult<Self> {
+        let mut tq = tq.clone();
+
+        consume_contextual_kwd(&mut tq, "select")?;
+
+        let items = if tq.consume_eq(Token::Star).is_ok() {
+            None
+        } else {
+            let mut items = vec![];
+            loop {
+                let expr = parse_expr(&mut tq)?;
+                let alias = match consume_contextual_kwd(&mut tq, "as") {
+                    Ok(_) => Some(consume_name(&mut tq, "column")?),
+                    Err(_) => None,
+                };
+                items.push(SelectItem::new(expr, alias));
+                if tq.consume_eq(Token::Comma).is_err() {
+                    break;
+                }
+            }
+            Some(items)
+        };
+
+        consume_contextual_kwd(&mut tq, "from")?;
+        let table_name = consume_name(&mut tq, "table")?;
+
+        let mut joins = vec![];
+        loop {
+            let kind = if consume_contextual_kwd(&mut tq, "left").is_ok() {
+                let _ = consume_contextual_kwd(&mut tq, "outer");
+                Some(JoinKind::Left)
+            } else if consume_contextual_kwd(&mut tq, "inner").is_ok() {
+                Some(JoinKind::Inner)
+            } else {
+                None
+            };
+            if consume_contextual_kwd(&mut tq, "join").is_err() {
+                if kind.is_some() {
+                    return Err(anyhow::anyhow!("Couldn't get 'join'"));
+                }
+                break;
+            }
+            let kind = kind.unwrap_or(JoinKind::Inner);
+            let joined_name = consume_name(&mut tq, "table")?;
+            consume_contextual_kwd(&mut tq, "on")?;
+            joins.push(Join::new(kind, joined_name, parse_cond(&mut tq)?));
+        }
+
+        let filter = match consume_contextual_kwd(&mut tq, "where") {
+            Ok(_) => Some(parse_cond(&mut tq)?),
+            Err(_) => None,
+        };
+
+        let mut group_by = vec![];
+        if consume_contextual_kwd(&mut tq, "group").is_ok() {
+            consume_contextual_kwd(&mut tq, "by")?;
+            loop {
+                group_by.push(parse_expr(&mut tq)?);
+                if tq.consume_eq(Token::Comma).is_err() {
+                    break;
+                }
+            }
+        }
+
+        let having = match consume_contextual_kwd(&mut tq, "having") {
+            Ok(_) => Some(parse_cond(&mut tq)?),
+            Err(_) => None,
+        };
+
+        let mut order_by = vec![];
+        if consume_contextual_kwd(&mut tq, "order").is_ok() {
+            consume_contextual_kwd(&mut tq, "by")?;
+            loop {
+                let expr = parse_expr(&mut tq)?;
+                let descending =
+                    consume_contextual_kwd(&mut tq, "desc").is_ok();
+                if !descending {
+                    let _ = consume_contextual_kwd(&mut tq, "asc");
+                }
+                order_by.push(OrderBy::new(expr, descending));
+                if tq.consume_eq(Token::Comma).is_err() {
+                    break;
+                }
+            }
+        }
+
+        let limit = match consume_contextual_kwd(&mut tq, "limit") {
+            Ok(_) => Some(
+                tq.consume()?
+                    .get_literal()
+                    .and_then(|literal| literal.get_i64())
+                    .and_then(|val| usize::try_from(val).ok())
+                    .ok_or(anyhow::anyhow!("Couldn't get limit!"))?,
+            ),
+            Err(_) => None,
+        };
+
+        Ok((
+            SelectStmt::new(items, table_name)
+                .with_joins(joins)
+                .with_filter(filter)
+                .with_group_by(group_by)
+                .with_having(having)
+                .with_order_by(order_by)
+                .with_limit(limit),
+            tq.get_idx(),
+        ))
+    }
+}
+
+// Consumes an expression, or `null`, giving a column its value.
+fn parse_value(tq: &mut TokenQueue<Token>) -> anyhow::Result<Option<Expr>> {
+    match consume_contextual_kwd(tq, "null") {
+        Ok(_) => Ok(None),
+        Err(_) => Ok(Some(parse_expr(tq)?)),
+    }
+}
+
+// Consumes a bracketed, comma-separated list, parsing each item with `item`.
+fn parse_bracketed<T>(
+    tq: &mut TokenQueue<Token>,
+    item: impl Fn(&mut TokenQueue<Token>) -> anyhow::Result<T>,
+) -> anyhow::Result<Vec<T>> {
+    tq.consume_eq(Token::OParen)
+        .map_err(|_| anyhow::anyhow!("Expected '('"))?;
+    let mut items = vec![];
+    loop {
+        items.push(item(tq)?);
+        if tq.consume_eq(Token::Comma).is_err() {
+            break;
+        }
+    }
+    tq.consume_eq(Token::CParen)
+        .map_err(|_| anyhow::anyhow!("Expected ')'"))?;
+    Ok(items)
+}
+
+impl Parse for InsertStmt {
+    fn parse(
+        tq: &TokenQueue<Token>,
+        _symtable: &mut SymbolTable,
+    ) -> ParseResult<Self> {
+        let mut tq = tq.clone();
+
+        consume_contextual_kwd(&mut tq, "insert")?;
+        consume_contextual_kwd(&mut tq, "into")?;
+        let table_name = consume_name(&mut tq, "table")?;
+
+        let column_names = match tq.clone().consume_eq(Token::OParen) {
+            Ok(_) => {
+                Some(parse_bracketed(&mut tq, |tq| consume_name(tq, "column"))?)
+            }
+            Err(_) => None,
+        };
+
+        consume_contextual_kwd(&mut tq, "values")?;
+        let mut rows = vec![];
+        loop {
+            rows.push(parse_bracketed(&mut tq, parse_value)?);
+            if tq.consume_eq(Token::Comma).is_err() {
+                break;
+            }
+        }
+
+        Ok((
+            InsertStmt::new(table_name, column_names, rows),
+            tq.get_idx(),
+        ))
+    }
+}
+
+impl Parse for UpdateStmt {
+    fn parse(
+        tq: &TokenQueue<Token>,
+        _symtable: &mut SymbolTable,
+    ) -> ParseResult<Self> {
+        let mut tq = tq.clone();
+
+        consume_contextual_kwd(&mut tq, "update")?;
+        let table_name = consume_name(&mut tq, "table")?;
+        consume_contextual_kwd(&mut tq, "set")?;
+
+        let mut assignments = vec![];
+        loop {
+            let column_name = consume_name(&mut tq, "column")?;
+            tq.consume_eq(Token::Equals)
+                .map_err(|_| anyhow::anyhow!("Expected '='"))?;
+            assignments
+                .push(Assignment::new(column_name, parse_value(&mut tq)?));
+            if tq.consume_eq(Token::Comma).is_err() {
+                break;
+            }
+        }
+
+        let filter = match consume_contextual_kwd(&mut tq, "where") {
+            Ok(_) => Some(parse_cond(&mut tq)?),
+            Err(_) => None,
+        };
+
+        Ok((
+            UpdateStmt::new(table_name, assignments, filter),
+            tq.get_idx(),
+        ))
+    }
+}
+
+impl Parse for DeleteStmt {
+    fn parse(
+        tq: &TokenQueue<Token>,
+        _symtable: &mut SymbolTable,
+    ) -> ParseResult<Self> {
+        let mut tq = tq.clone();
+
+        consume_contextual_kwd(&mut tq, "delete")?;
+        consume_contextual_kwd(&mut tq, "from")?;
+        let table_name = consume_name(&mut tq, "table")?;
+
+        let filter = match consume_contextual_kwd(&mut tq, "where") {
+            Ok(_) => Some(parse_cond(&mut tq)?),
+            Err(_) => None,
+        };
+
+        Ok((DeleteStmt::new(table_name, filter), tq.get_idx()))
+    }
+}
+
 impl Parse for Stmt {
     fn parse(
         tq: &TokenQueue<Token>,
@@ -306,6 +1162,71 @@ impl Parse for Stmt {
     ) -> ParseResult<Self> {
         let mut tq = tq.clone();
 
+        let kwd = match tq.clone().consume() {
+            Ok(Token::Ident(kwd)) => Some(kwd.clone()),
+            _ => None,
+        };
+
+        match kwd.as_deref() {
+            Some("alter" | "drop" | "rename") => {
+                let alter = tq.parse_with_mut(AlterStmt::parse, symtable)?;
+                alter.apply_to_symtable(symtable)?;
+                return Ok((Stmt::Alter(alter), tq.get_idx()));
+            }
+            Some("select") => {
+                let select = tq.parse_with_mut(SelectStmt::parse, symtable)?;
+                select.check(symtable)?;
+                return Ok((Stmt::Select(select), tq.get_idx()));
+            }
+            Some("insert") => {
+                let insert = tq.parse_with_mut(InsertStmt::parse, symtable)?;
+                insert.check(symtable)?;
+                return Ok((Stmt::Insert(insert), tq.get_idx()));
+            }
+            Some("update") => {
+                let update = tq.parse_with_mut(UpdateStmt::parse, symtable)?;
+                update.check(symtable)?;
+                return Ok((Stmt::Update(update), tq.get_idx()));
+            }
+            Some("delete") => {
+                let delete = tq.parse_with_mut(DeleteStmt::parse, symtable)?;
+                delete.check(symtable)?;
+                return Ok((Stmt::Delete(delete), tq.get_idx()));
+            }
+            Some("schema") => {
+                tq.increment()?;
+                let header =
+                    tq.parse_with_mut(SchemaHeader::parse, symtable)?;
+                return Ok((Stmt::Schema(header), tq.get_idx()));
+            }
+            Some("import") => {
+                tq.increment()?;
+                let import = tq.parse_with_mut(ImportStmt::parse, symtable)?;
+                return Ok((Stmt::Import(import), tq.get_idx()));
+            }
+            Some("view") => {
+                tq.increment()?;
+                let view_name = consume_name(&mut tq, "view")?;
+                consume_contextual_kwd(&mut tq, "as")?;
+                let query = tq.parse_with_mut(SelectStmt::parse, symtable)?;
+                let view = Rc::new(ViewSchema::new(
+                    view_name.clone(),
+                    query,
+                    symtable,
+                )?);
+
+                if symtable.contains_key(&view_name) {
+                    return Err(anyhow::anyhow!(
+                        "Symbol {} is already assigned!",
+                        &view_name
+                    ));
+                }
+                symtable.insert(view_name, Symbol::View(view.clone()));
+                return Ok((Stmt::View(view), tq.get_idx()));
+            }
+            _ => {}
+        }
+
         match tq.consume() {
             Ok(Token::TypeKwd) => {
                 let type_name: Rc<str> = tq
@@ -316,15 +1237,16 @@ impl Parse for Stmt {
 
                 let data_type = tq.parse_with_mut(parse_data_type, symtable)?;
 
-                if let Some(_) = symtable.insert(
-                    type_name.clone(),
-                    Symbol::DataType(data_type.clone()),
-                ) {
+                if symtable.contains_key(&type_name) {
                     return Err(anyhow::anyhow!(
                         "Symbol {} is already assigned!",
                         &type_name
                     ));
                 }
+                symtable.insert(
+                    type_name.clone(),
+                    Symbol::DataType(data_type.clone()),
+                );
 
                 Ok((Stmt::TypeDef(type_name, data_type), tq.get_idx()))
             }
@@ -332,15 +1254,16 @@ impl Parse for Stmt {
                 let table_schema =
                     Rc::new(tq.parse_with_mut(TableSchema::parse, symtable)?);
 
-                if let Some(_) = symtable.insert(
-                    table_schema.get_name().clone(),
-                    Symbol::TableSchema(table_schema.clone()),
-                ) {
+                if symtable.contains_key(&table_schema.get_name()) {
                     return Err(anyhow::anyhow!(
                         "Symbol {} is already assigned!",
-                        &table_schema.get_name().clone()
+                        &table_schema.get_name()
                     ));
                 }
+                symtable.insert(
+                    table_schema.get_name(),
+                    Symbol::TableSchema(table_schema.clone()),
+                );
                 Ok((Stmt::TableSchema(table_schema), tq.get_idx()))
             }
             Ok(_) => Err(anyhow::anyhow!("Couldn't parse statement!")),
@@ -349,26 +1272,99 @@ impl Parse for Stmt {
     }
 }
 
+/// Assembles a `SpreadsheetSchema` from its statements, checking that the
+/// schema header only appears once, as the first statement.
+#[derive(Default)]
+pub struct SchemaBuilder {
+    header: Option<SchemaHeader>,
+    tables: Vec<Rc<TableSchema>>,
+    views: Vec<SharedViewSchema>,
+    num_stmts: usize,
+}
+
+impl SchemaBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a parsed statement to the schema. Import statements are only
+    /// counted; resolving them is up to the caller. Queries and data
+    /// statements aren't part of a schema, so they're rejected.
+    pub fn add_stmt(&mut self, stmt: Stmt) -> anyhow::Result<()> {
+        match stmt {
+            Stmt::Schema(schema_header) => {
+                if self.header.is_some() {
+                    return Err(anyhow::anyhow!(
+                        "Schema header is already defined!"
+                    ));
+                }
+                if self.num_stmts > 0 {
+                    return Err(anyhow::anyhow!(
+                        "Schema header must be the first statement!"
+                    ));
+                }
+                self.header = Some(schema_header);
+            }
+            Stmt::TableSchema(schema) => {
+                self.tables.push(schema);
+            }
+            Stmt::View(view) => {
+                self.views.push(view);
+            }
+            Stmt::Alter(alter) => {
+                let table_name = alter.get_table_name();
+                let idx = self
+                    .tables
+                    .iter()
+                    .position(|table| table.get_name() == table_name)
+                    .ok_or(anyhow::anyhow!(
+                        "Table {table_name} is not defined in this schema!"
+                    ))?;
+                match alter.apply(&self.tables[idx])? {
+                    Some(table) => self.tables[idx] = Rc::new(table),
+                    None => {
+                        self.tables.remove(idx);
+                    }
+                }
+            }
+            Stmt::TypeDef(_, _) | Stmt::Import(_) => {}
+            // only the REPL runs queries and changes data
+            Stmt::Select(_)
+            | Stmt::Insert(_)
+            | Stmt::Update(_)
+            | Stmt::Delete(_) => {
+                return Err(anyhow::anyhow!(
+                    "Queries and data statements aren't allowed in a schema!"
+                ));
+            }
+        }
+        self.num_stmts += 1;
+        Ok(())
+    }
+
+    pub fn build(self) -> SpreadsheetSchema {
+        SpreadsheetSchema::new(self.header, self.tables).with_views(self.views)
+    }
+}
+
 impl Parse for SpreadsheetSchema {
     fn parse(
         tq: &TokenQueue<Token>,
         symtable: &mut SymbolTable,
     ) -> ParseResult<Self> {
         let mut tq: TokenQueue<Token> = tq.clone();
-        let mut tables = vec![];
+        let mut builder = SchemaBuilder::new();
         while let Ok(stmt) = tq.parse_with_mut(Stmt::parse, symtable) {
-            match stmt {
-                Stmt::TableSchema(schema) => {
-                    tables.push(schema);
-                }
-                Stmt::TypeDef(_, _) => {}
+            if let Stmt::Import(import) = &stmt {
+                return Err(anyhow::anyhow!(
+                    "Couldn't resolve import of {} without a schema loader!",
+                    import.get_path()
+                ));
             }
+            builder.add_stmt(stmt)?;
             tq.consume_eq(Token::Semicolon)?;
         }
-        Ok((
-            SpreadsheetSchema::new("ss_name".into(), tables),
-            tq.get_idx(),
-        ))
+        Ok((builder.build(), tq.get_idx()))
     }
 }
 
@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+
+use rlrl::parse::TokenQueue;
+
+use crate::{
+    core::{
+        data::Dataset,
+        schema::{SharedDataType, computed::convert_result},
+    },
+    ql::{
+        SymbolTable,
+        expr::Param,
+        lex::{Literal, Token, setup_lexer},
+        parse::Parse,
+        query::{ResultColumn, ResultSet, SelectStmt},
+    },
+};
+
+/// A parameter of a prepared query, along with the type of the values that
+/// can be bound to it.
+#[derive(Clone)]
+pub struct QueryParam {
+    pub param: Param,
+    pub data_type: SharedDataType,
+}
+
+impl QueryParam {
+    pub fn new(param: Param, data_type: SharedDataType) -> Self {
+        Self { param, data_type }
+    }
+}
+
+/// A query that's parsed and checked once, then run with different values
+/// for its parameters, such as `select * from Users where userId = $1;` or
+/// `select * from Users where userName = :name;`. Values are bound as
+/// literals, so can't change what the query does.
+pub struct PreparedQuery {
+    query: SelectStmt,
+    params: Vec<QueryParam>,
+    columns: Vec<ResultColumn>,
+}
+
+impl PreparedQuery {
+    /// Parses the query in `src`, which may end with `;`, and checks it
+    /// against the tables in `symtable`, inferring the type of each
+    /// parameter from where it's used.
+    ///
+    /// A query's parameters are either positional, numbered from `$1`
+    /// without gaps, or named, and are ordered by number or by where they
+    /// first appear.
+    pub fn prepare(src: &str, symtable: &SymbolTable) -> anyhow::Result<Self> {
+        let mut tq = TokenQueue::from(setup_lexer().lex(src)?);
+        let query =
+            tq.parse_with_mut(SelectStmt::parse, &mut symtable.clone())?;
+        tq.consume_eq(Token::Semicolon).ok();
+        if !tq.is_consumed() {
+            return Err(anyhow::anyhow!("Unexpected input after query!"));
+        }
+
+        let mut params = query.get_params();
+        let is_positional =
+            |param: &&Param| matches!(param, Param::Positional(_));
+        if params.iter().any(is_positional) && !params.iter().all(is_positional)
+        {
+            return Err(anyhow::anyhow!(
+                "Can't mix positional and named parameters!"
+            ));
+        }
+        // the sort is stable, so named parameters keep their order
+        params.sort_by_key(|param| match param {
+            Param::Positional(idx) => *idx,
+            Param::Named(_) => 0,
+        });
+        for (i, param) in params.iter().enumerate() {
+            if let Param::Positional(idx) = param
+                && *idx != i + 1
+            {
+                return Err(anyhow::anyhow!(
+                    "Parameter ${} isn't used!",
+                    i + 1
+                ));
+            }
+        }
+
+        let types = query.infer_param_types(symtable)?;
+        let params = params
+            .into_iter()
+            .map(|param| {
+                let (_, data_type) = types
+                    .iter()
+                    .find(|(typed, _)| typed == param)
+                    .ok_or(anyhow::anyhow!(
+                        "Can't infer the type of parameter {param}!"
+                    ))?;
+                Ok(QueryParam::new(param.clone(), data_type.clone()))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let columns = query.check_with_params(symtable, &types)?;
+        Ok(Self {
+            query,
+            params,
+            columns,
+        })
+    }
+
+    pub fn get_query(&self) -> &SelectStmt {
+        &self.query
+    }
+
+    /// Returns the query's parameters, in the order their values are given
+    /// to `execute`.
+    pub fn get_params(&self) -> &[QueryParam] {
+        &self.params
+    }
+
+    /// Returns the columns of the query's result.
+    pub fn get_columns(&self) -> &[ResultColumn] {
+        &self.columns
+    }
+
+    /// Runs the query over the rows in `data` with `args` as the values of
+    /// its parameters, in order, checking each against its parameter's type
+    /// first. Integers can be given for any number parameter.
+    pub fn execute(
+        &self,
+        symtable: &SymbolTable,
+        data: &Dataset,
+        args: &[Literal],
+    ) -> anyhow::Result<ResultSet> {
+        if args.len() != self.params.len() {
+            return Err(anyhow::anyhow!(
+                "Expected {} arguments, got {}!",
+                self.params.len(),
+                args.len()
+            ));
+        }
+        let mut bound = HashMap::new();
+        for (param, arg) in self.params.iter().zip(args) {
+            let arg = convert_result(arg.clone(), &*param.data_type);
+            param.data_type.validate_literal(Some(&arg)).map_err(|e| {
+                anyhow::anyhow!("In parameter {}: {e}", param.param)
+            })?;
+            bound.insert(param.param.clone(), arg);
+        }
+        // the query was checked with its parameters' types when prepared
+        self.query.bind_params(&bound).execute_checked(
+            symtable,
+            data,
+            self.columns.clone(),
+        )
+    }
+
+    /// Runs the query as `execute` does, with the values of its named
+    /// parameters given by name.
+    pub fn execute_named(
+        &self,
+        symtable: &SymbolTable,
+        data: &Dataset,
+        args: &HashMap<&str, Literal>,
+    ) -> anyhow::Result<ResultSet> {
+        for name in args.keys() {
+            let param = Param::Named((*name).into());
+            if !self.params.iter().any(|known| known.param == param) {
+                return Err(anyhow::anyhow!("Unknown parameter {param}!"));
+            }
+        }
+        let args = self
+            .params
+            .iter()
+            .map(|param| match &param.param {
+                Param::Named(name) => args.get(&**name).cloned().ok_or(
+                    anyhow::anyhow!("No value given for parameter :{name}!"),
+                ),
+                Param::Positional(_) => Err(anyhow::anyhow!(
+                    "Parameter {} can't be given by name!",
+                    param.param
+                )),
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        self.execute(symtable, data, &args)
+    }
+}
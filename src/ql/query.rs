@@ -0,0 +1,1060 @@
+use std::{cmp::Ordering, collections::HashMap, rc::Rc};
+
+use serde_json::{Value, json};
+
+use crate::{
+    core::{
+        data::{Dataset, Row},
+        schema::{
+            SharedDataType, SharedTableSchema, TableSchema, compat::to_nullable,
+        },
+    },
+    json::ToJson,
+    ql::{
+        Symbol, SymbolTable,
+        expr::{Aggregate, CompareOp, Cond, Expr, Param, compare_literals},
+        lex::Literal,
+    },
+};
+
+/// An expression in the column list of a query, optionally named with `as`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SelectItem {
+    expr: Expr,
+    alias: Option<Rc<str>>,
+}
+
+impl SelectItem {
+    pub fn new(expr: Expr, alias: Option<Rc<str>>) -> Self {
+        Self { expr, alias }
+    }
+
+    pub fn get_expr(&self) -> &Expr {
+        &self.expr
+    }
+
+    pub fn get_alias(&self) -> Option<Rc<str>> {
+        self.alias.clone()
+    }
+
+    /// Returns the name of the item's column in the result: its alias, else
+    /// the column it refers to, without its table, else the expression
+    /// itself.
+    pub fn get_name(&self) -> Rc<str> {
+        match (&self.alias, &self.expr) {
+            (Some(alias), _) => alias.clone(),
+            (None, Expr::Column(name)) => match name.split_once('.') {
+                Some((_, column_name)) => column_name.into(),
+                None => name.clone(),
+            },
+            (None, expr) => expr.to_string().into(),
+        }
+    }
+}
+
+/// An expression the rows of a query's result are sorted by.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OrderBy {
+    expr: Expr,
+    descending: bool,
+}
+
+impl OrderBy {
+    pub fn new(expr: Expr, descending: bool) -> Self {
+        Self { expr, descending }
+    }
+
+    pub fn get_expr(&self) -> &Expr {
+        &self.expr
+    }
+
+    pub fn is_descending(&self) -> bool {
+        self.descending
+    }
+}
+
+/// How a join combines rows: an inner join keeps only the rows with a match
+/// in the joined table, and a left join keeps every row, with nulls for the
+/// joined table's columns where there is no match.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum JoinKind {
+    Inner,
+    Left,
+}
+
+/// A table joined to the rows of a query, such as `join Movies on
+/// Movies.movieId = MovieRatings.movieId`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Join {
+    kind: JoinKind,
+    table_name: Rc<str>,
+    on: Cond,
+}
+
+impl Join {
+    pub fn new(kind: JoinKind, table_name: Rc<str>, on: Cond) -> Self {
+        Self {
+            kind,
+            table_name,
+            on,
+        }
+    }
+
+    pub fn get_kind(&self) -> JoinKind {
+        self.kind
+    }
+
+    pub fn get_table_name(&self) -> Rc<str> {
+        self.table_name.clone()
+    }
+
+    /// Returns the condition rows of the joined table must meet to match.
+    pub fn get_on(&self) -> &Cond {
+        &self.on
+    }
+}
+
+/// A `select` query over a table, such as `select userId, userName from Users
+/// where userId > 10 order by userName desc limit 5;`. `select *` selects
+/// every column. A query that groups rows with `group by`, or uses
+/// aggregates, gives one row per group, such as `select movieId, avg(rating)
+/// from MovieRatings group by movieId having count(*) > 1;`. Other tables can
+/// be joined to the rows of the first, and their columns referred to as
+/// `Table.column` where a name alone would be ambiguous.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SelectStmt {
+    // `None` for `select *`
+    items: Option<Vec<SelectItem>>,
+    table_name: Rc<str>,
+    joins: Vec<Join>,
+    filter: Option<Cond>,
+    group_by: Vec<Expr>,
+    having: Option<Cond>,
+    order_by: Vec<OrderBy>,
+    limit: Option<usize>,
+}
+
+/// A column of a query's result.
+#[derive(Clone)]
+pub struct ResultColumn {
+    name: Rc<str>,
+    data_type: SharedDataType,
+}
+
+impl ResultColumn {
+    pub fn new(name: Rc<str>, data_type: SharedDataType) -> Self {
+        Self { name, data_type }
+    }
+
+    pub fn get_name(&self) -> Rc<str> {
+        self.name.clone()
+    }
+
+    pub fn get_type(&self) -> SharedDataType {
+        self.data_type.clone()
+    }
+}
+
+// A column the expressions of a query can refer to.
+struct ScopeColumn {
+    table_name: Rc<str>,
+    column_name: Rc<str>,
+    data_type: SharedDataType,
+}
+
+// The columns the expressions of a query can refer to, in the order of the
+// cells of the rows it reads. Joined rows hold the cells of each table in
+// turn. The types of a prepared query's parameters are looked up by their
+// text, like columns.
+struct Scope {
+    columns: Vec<ScopeColumn>,
+    params: Vec<(Param, SharedDataType)>,
+}
+
+impl Scope {
+    fn of_table(table: &TableSchema) -> Self {
+        let mut scope = Self {
+            columns: vec![],
+            params: vec![],
+        };
+        scope.join(table, false);
+        scope
+    }
+
+    // Adds the columns of `table` after those already in scope, allowing
+    // nulls in each if `nullable`.
+    fn join(&mut self, table: &TableSchema, nullable: bool) {
+        for col in table.get_columns() {
+            self.columns.push(ScopeColumn {
+                table_name: table.get_name(),
+                column_name: col.get_name(),
+                data_type: match nullable {
+                    true => to_nullable(&col.get_type()),
+                    false => col.get_type(),
+                },
+            });
+        }
+    }
+
+    fn has_table(&self, table_name: &str) -> bool {
+        self.columns
+            .iter()
+            .any(|col| &*col.table_name == table_name)
+    }
+
+    // Returns the index of the cell holding the column `name`, which may be
+    // qualified with its table as `Table.column`.
+    fn resolve(&self, name: &str) -> anyhow::Result<usize> {
+        let matches: Vec<usize> = (0..self.columns.len())
+            .filter(|idx| {
+                let col = &self.columns[*idx];
+                match name.split_once('.') {
+                    Some((table_name, column_name)) => {
+                        &*col.table_name == table_name
+                            && &*col.column_name == column_name
+                    }
+                    None => &*col.column_name == name,
+                }
+            })
+            .collect();
+        match matches[..] {
+            [idx] => Ok(idx),
+            [] => Err(anyhow::anyhow!("Unknown column {name}!")),
+            _ => {
+                let table_names: Vec<&str> = matches
+                    .iter()
+                    .map(|idx| &*self.columns[*idx].table_name)
+                    .collect();
+                Err(anyhow::anyhow!(
+                    "Column {name} is ambiguous, as it's in tables {}!",
+                    table_names.join(" and ")
+                ))
+            }
+        }
+    }
+
+    // Checks every column `exprs` refer to resolves, so ambiguous names are
+    // reported as such, rather than as unknown columns. Names of the
+    // result's `columns` may be referred to as well.
+    fn check_refs<'a>(
+        &self,
+        exprs: impl IntoIterator<Item = &'a Expr>,
+        columns: &[ResultColumn],
+    ) -> anyhow::Result<()> {
+        for name in exprs.into_iter().flat_map(|expr| expr.get_column_refs()) {
+            if let Err(e) = self.resolve(&name)
+                && !columns.iter().any(|col| col.name == name)
+            {
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    fn column_type(&self, name: &str) -> Option<SharedDataType> {
+        match self.resolve(name) {
+            Ok(idx) => Some(self.columns[idx].data_type.clone()),
+            Err(_) => self
+                .params
+                .iter()
+                .find(|(param, _)| param.to_string() == name)
+                .map(|(_, dtype)| dtype.clone()),
+        }
+    }
+}
+
+// Returns a key that's equal for equal values, or `None` if any value is
+// null, as null equals nothing. Numbers of different kinds compare by value,
+// so they're keyed by their value as a double.
+fn hash_key(vals: &[Option<Literal>]) -> Option<Vec<String>> {
+    vals.iter()
+        .map(|val| match val.as_ref()? {
+            Literal::Str(s) => Some(format!("s{s}")),
+            // adding zero turns -0.0 into 0.0
+            lit => Some(format!("n{}", lit.get_f64()? + 0.0)),
+        })
+        .collect()
+}
+
+// Returns the pairs of expressions `on` requires to be equal, each as the
+// expression over the rows being joined to, then over the joined table,
+// whose cells start at `left_width` in `scope`.
+fn get_join_keys<'a>(
+    on: &'a Cond,
+    scope: &Scope,
+    left_width: usize,
+) -> Vec<(&'a Expr, &'a Expr)> {
+    // whether an expression only refers to the joined table, or `None` if it
+    // refers to both sides or neither
+    let is_right = |expr: &Expr| {
+        let idxs: Vec<usize> = expr
+            .get_column_refs()
+            .iter()
+            .map(|name| scope.resolve(name).ok())
+            .collect::<Option<_>>()?;
+        if idxs.is_empty() {
+            None
+        } else if idxs.iter().all(|idx| *idx >= left_width) {
+            Some(true)
+        } else if idxs.iter().all(|idx| *idx < left_width) {
+            Some(false)
+        } else {
+            None
+        }
+    };
+    match on {
+        Cond::And(left, right) => {
+            let mut keys = get_join_keys(left, scope, left_width);
+            keys.extend(get_join_keys(right, scope, left_width));
+            keys
+        }
+        Cond::Compare(CompareOp::Eq, a, b) => {
+            match (is_right(a), is_right(b)) {
+                (Some(false), Some(true)) => vec![(a, b)],
+                (Some(true), Some(false)) => vec![(b, a)],
+                _ => vec![],
+            }
+        }
+        _ => vec![],
+    }
+}
+
+// Joins each of `rows` to the rows of the joined table in `right`, given the
+// scope of the joined rows, in which the joined table's cells start at
+// `left_width`. Rows are matched by hashing the values `join.on` requires to
+// be equal, if any, and then checked against the whole condition.
+fn join_rows(
+    join: &Join,
+    scope: &Scope,
+    left_width: usize,
+    rows: Vec<Row>,
+    right: &[Row],
+) -> anyhow::Result<Vec<Row>> {
+    let keys = get_join_keys(&join.on, scope, left_width);
+    let mut buckets: HashMap<Vec<String>, Vec<&Row>> = HashMap::new();
+    for row in right {
+        let vals = keys
+            .iter()
+            .map(|(_, expr)| {
+                expr.eval(&|name: &str| {
+                    scope.resolve(name).map(|idx| row[idx - left_width].clone())
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        if let Some(key) = hash_key(&vals) {
+            buckets.entry(key).or_default().push(row);
+        }
+    }
+
+    let mut joined = vec![];
+    for row in rows {
+        let vals = keys
+            .iter()
+            .map(|(expr, _)| {
+                expr.eval(&|name: &str| {
+                    scope.resolve(name).map(|idx| row[idx].clone())
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let candidates = hash_key(&vals)
+            .and_then(|key| buckets.get(&key))
+            .map_or(&[] as &[&Row], |bucket| bucket.as_slice());
+
+        let mut matched = false;
+        for right_row in candidates {
+            let out: Row =
+                row.iter().chain(right_row.iter()).cloned().collect();
+            if join.on.eval(&|name: &str| {
+                scope.resolve(name).map(|idx| out[idx].clone())
+            })? == Some(true)
+            {
+                joined.push(out);
+                matched = true;
+            }
+        }
+        if !matched && join.kind == JoinKind::Left {
+            let width = scope.columns.len() - left_width;
+            joined.push(row.into_iter().chain(vec![None; width]).collect());
+        }
+    }
+    Ok(joined)
+}
+
+pub(crate) fn get_table(
+    symtable: &SymbolTable,
+    name: &str,
+) -> anyhow::Result<SharedTableSchema> {
+    match symtable.get(name) {
+        Some(Symbol::TableSchema(table)) => Ok(table.clone()),
+        Some(Symbol::View(view)) => Ok(view.get_table_schema()),
+        _ => Err(anyhow::anyhow!("Unknown table {name}!")),
+    }
+}
+
+// Orders two cells of a sorted column, with nulls after every value, as in
+// SQL's default ascending order.
+fn compare_cells(a: &Option<Literal>, b: &Option<Literal>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => compare_literals(a, b).unwrap_or(Ordering::Equal),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+// Orders two rows by their sort keys, the values of the `order by`
+// expressions.
+fn compare_keys(
+    a: &[Option<Literal>],
+    b: &[Option<Literal>],
+    order_by: &[OrderBy],
+) -> Ordering {
+    for ((a, b), order) in a.iter().zip(b).zip(order_by) {
+        let ordering = match compare_cells(a, b) {
+            ordering if order.descending => ordering.reverse(),
+            ordering => ordering,
+        };
+        if ordering.is_ne() {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+impl SelectStmt {
+    pub fn new(items: Option<Vec<SelectItem>>, table_name: Rc<str>) -> Self {
+        Self {
+            items,
+            table_name,
+            joins: vec![],
+            filter: None,
+            group_by: vec![],
+            having: None,
+            order_by: vec![],
+            limit: None,
+        }
+    }
+
+    /// Sets the tables joined to the rows of the query's first table, in
+    /// order.
+    pub fn with_joins(mut self, joins: Vec<Join>) -> Self {
+        self.joins = joins;
+        self
+    }
+
+    /// Sets the `where` condition rows must satisfy to be selected.
+    pub fn with_filter(mut self, filter: Option<Cond>) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Sets the expressions rows are grouped by, so each group gives one row
+    /// of the result.
+    pub fn with_group_by(mut self, group_by: Vec<Expr>) -> Self {
+        self.group_by = group_by;
+        self
+    }
+
+    /// Sets the `having` condition groups must satisfy to be selected.
+    pub fn with_having(mut self, having: Option<Cond>) -> Self {
+        self.having = having;
+        self
+    }
+
+    pub fn with_order_by(mut self, order_by: Vec<OrderBy>) -> Self {
+        self.order_by = order_by;
+        self
+    }
+
+    pub fn with_limit(mut self, limit: Option<usize>) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Returns the selected expressions, or `None` for `select *`.
+    pub fn get_items(&self) -> Option<&[SelectItem]> {
+        self.items.as_deref()
+    }
+
+    pub fn get_table_name(&self) -> Rc<str> {
+        self.table_name.clone()
+    }
+
+    pub fn get_joins(&self) -> &[Join] {
+        &self.joins
+    }
+
+    /// Returns the names of the tables the query reads from: its own and
+    /// those it joins.
+    pub fn get_table_names(&self) -> Vec<Rc<str>> {
+        std::iter::once(self.table_name.clone())
+            .chain(self.joins.iter().map(|join| join.table_name.clone()))
+            .collect()
+    }
+
+    pub fn get_filter(&self) -> Option<&Cond> {
+        self.filter.as_ref()
+    }
+
+    pub fn get_group_by(&self) -> &[Expr] {
+        &self.group_by
+    }
+
+    pub fn get_having(&self) -> Option<&Cond> {
+        self.having.as_ref()
+    }
+
+    pub fn get_order_by(&self) -> &[OrderBy] {
+        &self.order_by
+    }
+
+    pub fn get_limit(&self) -> Option<usize> {
+        self.limit
+    }
+
+    // Returns the expressions of the query outside of its conditions, in the
+    // order they appear.
+    fn get_exprs(&self) -> impl Iterator<Item = &Expr> {
+        let items = self.items.iter().flatten().map(|item| &item.expr);
+        let order_by = self.order_by.iter().map(|order| &order.expr);
+        items.chain(&self.group_by).chain(order_by)
+    }
+
+    // Returns the conditions of the query, in the order they appear.
+    fn get_conds(&self) -> impl Iterator<Item = &Cond> {
+        let ons = self.joins.iter().map(|join| &join.on);
+        ons.chain(&self.filter).chain(&self.having)
+    }
+
+    /// Returns the parameters of the query, without duplicates, in the order
+    /// they first appear.
+    pub fn get_params(&self) -> Vec<&Param> {
+        let exprs = self.get_exprs().flat_map(|expr| expr.get_params());
+        let conds = self.get_conds().flat_map(|cond| cond.get_params());
+        let mut params: Vec<&Param> = vec![];
+        for param in exprs.chain(conds) {
+            if !params.contains(&param) {
+                params.push(param);
+            }
+        }
+        params
+    }
+
+    /// Infers the types of the parameters of the query from where they're
+    /// used, given the tables in `symtable`. Parameters whose type can't be
+    /// inferred, such as one compared with another or one in `order by`,
+    /// where result columns can be referred to as well, are left out.
+    pub fn infer_param_types(
+        &self,
+        symtable: &SymbolTable,
+    ) -> anyhow::Result<Vec<(Param, SharedDataType)>> {
+        let mut scope =
+            Scope::of_table(&get_table(symtable, &self.table_name)?);
+        for join in &self.joins {
+            scope.join(
+                &get_table(symtable, &join.table_name)?,
+                join.kind == JoinKind::Left,
+            );
+        }
+        let items = self.items.iter().flatten().map(|item| &item.expr);
+        let exprs: Vec<&Expr> = items.chain(&self.group_by).collect();
+        let conds: Vec<&Cond> = self.get_conds().collect();
+        // names that don't resolve are reported as such, rather than as
+        // unknown columns
+        scope.check_refs(
+            exprs
+                .iter()
+                .copied()
+                .chain(conds.iter().flat_map(|cond| cond.get_exprs())),
+            &[],
+        )?;
+
+        let column_type = |name: &str| scope.column_type(name);
+        let mut types = vec![];
+        for expr in exprs {
+            expr.infer_param_types(&column_type, &mut types)?;
+        }
+        for cond in conds {
+            cond.infer_param_types(&column_type, &mut types)?;
+        }
+        Ok(types)
+    }
+
+    /// Returns a copy of the query with each parameter replaced by its value
+    /// in `args`, if it has one. Selected expressions keep the names they
+    /// had with their parameters.
+    pub fn bind_params(&self, args: &HashMap<Param, Literal>) -> Self {
+        let items = self.items.as_ref().map(|items| {
+            items
+                .iter()
+                .map(|item| match item.expr.get_params().is_empty() {
+                    true => item.clone(),
+                    false => SelectItem::new(
+                        item.expr.bind_params(args),
+                        Some(item.get_name()),
+                    ),
+                })
+                .collect()
+        });
+        Self {
+            items,
+            table_name: self.table_name.clone(),
+            joins: self
+                .joins
+                .iter()
+                .map(|join| {
+                    Join::new(
+                        join.kind,
+                        join.table_name.clone(),
+                        join.on.bind_params(args),
+                    )
+                })
+                .collect(),
+            filter: self.filter.as_ref().map(|cond| cond.bind_params(args)),
+            group_by: self
+                .group_by
+                .iter()
+                .map(|expr| expr.bind_params(args))
+                .collect(),
+            having: self.having.as_ref().map(|cond| cond.bind_params(args)),
+            order_by: self
+                .order_by
+                .iter()
+                .map(|order| {
+                    OrderBy::new(order.expr.bind_params(args), order.descending)
+                })
+                .collect(),
+            limit: self.limit,
+        }
+    }
+
+    /// Returns `true` if the query gives one row per group of rows, as it
+    /// groups them or uses aggregates.
+    pub fn is_aggregate(&self) -> bool {
+        !self.group_by.is_empty()
+            || self.having.is_some()
+            || !self.get_aggregates().is_empty()
+    }
+
+    // Returns the aggregates evaluated for each group, without duplicates.
+    fn get_aggregates(&self) -> Vec<&Aggregate> {
+        let items = self.items.iter().flatten().map(|item| &item.expr);
+        let order_by = self.order_by.iter().map(|order| &order.expr);
+        let having = self.having.iter().flat_map(|having| having.get_exprs());
+
+        let mut aggregates: Vec<&Aggregate> = vec![];
+        for aggregate in items
+            .chain(order_by)
+            .chain(having)
+            .flat_map(|expr| expr.get_aggregates())
+        {
+            if !aggregates.contains(&aggregate) {
+                aggregates.push(aggregate);
+            }
+        }
+        aggregates
+    }
+
+    // Checks `expr` has one value per group: it's grouped by, or only refers
+    // to grouped columns or `columns` of the result outside of aggregates.
+    fn check_grouped(
+        &self,
+        scope: &Scope,
+        expr: &Expr,
+        columns: &[ResultColumn],
+    ) -> anyhow::Result<()> {
+        if self.group_by.contains(expr) {
+            return Ok(());
+        }
+        // a column may be qualified with its table in one place and not the
+        // other
+        let is_grouped = |name: &str| {
+            let idx = scope.resolve(name).ok();
+            idx.is_some()
+                && self.group_by.iter().any(|expr| {
+                    matches!(expr, Expr::Column(grouped)
+                        if scope.resolve(grouped).ok() == idx)
+                })
+        };
+        for name in expr.get_unaggregated_refs() {
+            if !is_grouped(&name) && !columns.iter().any(|col| col.name == name)
+            {
+                return Err(anyhow::anyhow!(
+                    "Column {name} must be grouped by or aggregated!"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn get_result_columns(
+        &self,
+        scope: &Scope,
+    ) -> anyhow::Result<Vec<ResultColumn>> {
+        let Some(items) = &self.items else {
+            return Ok(scope
+                .columns
+                .iter()
+                .map(|col| {
+                    ResultColumn::new(
+                        col.column_name.clone(),
+                        col.data_type.clone(),
+                    )
+                })
+                .collect());
+        };
+        items
+            .iter()
+            .map(|item| {
+                // selected columns keep their type, other expressions get
+                // the unbounded type of their values
+                let data_type = match &item.expr {
+                    Expr::Column(name) => scope
+                        .column_type(name)
+                        .ok_or(anyhow::anyhow!("Unknown column {name}!"))?,
+                    expr => expr
+                        .infer_type(&|name: &str| scope.column_type(name))?
+                        .to_data_type(),
+                };
+                Ok(ResultColumn::new(item.get_name(), data_type))
+            })
+            .collect()
+    }
+
+    /// Checks the query against the tables in `symtable`, returning the
+    /// columns of its result.
+    pub fn check(
+        &self,
+        symtable: &SymbolTable,
+    ) -> anyhow::Result<Vec<ResultColumn>> {
+        self.check_with_params(symtable, &[])
+    }
+
+    /// Checks the query as `check` does, given the type of each of its
+    /// parameters.
+    pub fn check_with_params(
+        &self,
+        symtable: &SymbolTable,
+        params: &[(Param, SharedDataType)],
+    ) -> anyhow::Result<Vec<ResultColumn>> {
+        let table = get_table(symtable, &self.table_name)?;
+        let mut scope = Scope::of_table(&table);
+        scope.params = params.to_vec();
+        for join in &self.joins {
+            // a table joined to itself would need an alias for each side
+            if scope.has_table(&join.table_name) {
+                return Err(anyhow::anyhow!(
+                    "Table {} is already in the query!",
+                    join.table_name
+                ));
+            }
+            let joined = get_table(symtable, &join.table_name)?;
+            scope.join(&joined, join.kind == JoinKind::Left);
+            if !join.on.get_aggregates().is_empty() {
+                return Err(anyhow::anyhow!(
+                    "Aggregate functions can't be used in on!"
+                ));
+            }
+            scope.check_refs(join.on.get_exprs(), &[])?;
+            join.on.check(&|name: &str| scope.column_type(name))?;
+        }
+        let items = self.items.iter().flatten().map(|item| &item.expr);
+        let filter = self.filter.iter().flat_map(|filter| filter.get_exprs());
+        let having = self.having.iter().flat_map(|having| having.get_exprs());
+        scope.check_refs(
+            items.chain(filter).chain(&self.group_by).chain(having),
+            &[],
+        )?;
+
+        let column_type = |name: &str| scope.column_type(name);
+        if let Some(filter) = &self.filter {
+            if !filter.get_aggregates().is_empty() {
+                return Err(anyhow::anyhow!(
+                    "Aggregate functions can't be used in where!"
+                ));
+            }
+            filter.check(&column_type)?;
+        }
+        for expr in &self.group_by {
+            if !expr.get_aggregates().is_empty() {
+                return Err(anyhow::anyhow!(
+                    "Aggregate functions can't be used in group by!"
+                ));
+            }
+            expr.infer_type(&column_type)?;
+        }
+        if let Some(having) = &self.having {
+            having.check(&column_type)?;
+        }
+
+        let columns = self.get_result_columns(&scope)?;
+        if self.is_aggregate() {
+            let Some(items) = &self.items else {
+                return Err(anyhow::anyhow!(
+                    "Can't select * from a query that aggregates!"
+                ));
+            };
+            for item in items {
+                self.check_grouped(&scope, &item.expr, &[])?;
+            }
+            for expr in self.having.iter().flat_map(|having| having.get_exprs())
+            {
+                self.check_grouped(&scope, expr, &[])?;
+            }
+            for order in &self.order_by {
+                self.check_grouped(&scope, &order.expr, &columns)?;
+            }
+        }
+        // rows can be sorted by the table's columns or the result's
+        scope.check_refs(
+            self.order_by.iter().map(|order| &order.expr),
+            &columns,
+        )?;
+        for order in &self.order_by {
+            order.expr.infer_type(&|name: &str| {
+                scope.column_type(name).or_else(|| {
+                    columns
+                        .iter()
+                        .find(|col| &*col.name == name)
+                        .map(|col| col.data_type.clone())
+                })
+            })?;
+        }
+        Ok(columns)
+    }
+
+    /// Runs the query over the rows in `data`, checking it against the
+    /// tables in `symtable` first.
+    pub fn execute(
+        &self,
+        symtable: &SymbolTable,
+        data: &Dataset,
+    ) -> anyhow::Result<ResultSet> {
+        let columns = self.check(symtable)?;
+        self.execute_checked(symtable, data, columns)
+    }
+
+    /// Runs the query as `execute` does, without checking it, given the
+    /// result columns it was already checked to have.
+    pub(crate) fn execute_checked(
+        &self,
+        symtable: &SymbolTable,
+        data: &Dataset,
+        columns: Vec<ResultColumn>,
+    ) -> anyhow::Result<ResultSet> {
+        // a view's rows are the results of its query
+        let get_rows = |table_name: &str| -> anyhow::Result<Vec<Row>> {
+            if let Some(Symbol::View(view)) = symtable.get(table_name) {
+                let results = view.get_query().execute(symtable, data)?;
+                return Ok(results.get_rows().to_vec());
+            }
+            data.get(table_name)
+                .map(|table| table.get_rows().to_vec())
+                .ok_or(anyhow::anyhow!("Table {table_name} has no data!"))
+        };
+        let table = get_table(symtable, &self.table_name)?;
+        let mut scope = Scope::of_table(&table);
+        let mut rows = get_rows(&self.table_name)?;
+        for join in &self.joins {
+            let left_width = scope.columns.len();
+            scope.join(
+                &get_table(symtable, &join.table_name)?,
+                join.kind == JoinKind::Left,
+            );
+            rows = join_rows(
+                join,
+                &scope,
+                left_width,
+                rows,
+                &get_rows(&join.table_name)?,
+            )?;
+        }
+
+        let mut filtered = vec![];
+        for row in &rows {
+            if let Some(filter) = &self.filter
+                && filter.eval(&|name: &str| {
+                    scope.resolve(name).map(|idx| row[idx].clone())
+                })? != Some(true)
+            {
+                continue;
+            }
+            filtered.push(row);
+        }
+
+        // each selected row along with its sort keys
+        let mut selected = vec![];
+        if self.is_aggregate() {
+            let aggregates = self.get_aggregates();
+            for group in self.group_rows(&scope, &filtered)? {
+                // the value of each aggregate over the group, keyed by its
+                // text
+                let mut values = HashMap::new();
+                for aggregate in &aggregates {
+                    let args = group
+                        .iter()
+                        .map(|row| match &aggregate.arg {
+                            Some(arg) => arg.eval(&|name: &str| {
+                                scope.resolve(name).map(|idx| row[idx].clone())
+                            }),
+                            // `count(*)` counts rows whatever they hold
+                            None => Ok(None),
+                        })
+                        .collect::<anyhow::Result<Vec<_>>>()?;
+                    values.insert(aggregate.to_string(), aggregate.eval(args)?);
+                }
+                // grouped columns hold the same value in every row of the
+                // group
+                let value = |name: &str| match values.get(name) {
+                    Some(val) => Ok(val.clone()),
+                    None => {
+                        let idx = scope.resolve(name)?;
+                        Ok(group.first().and_then(|row| row[idx].clone()))
+                    }
+                };
+                if let Some(having) = &self.having
+                    && having.eval(&value)? != Some(true)
+                {
+                    continue;
+                }
+                selected.push(self.eval_result_row(&columns, None, &value)?);
+            }
+        } else {
+            for row in filtered {
+                let value = |name: &str| {
+                    scope.resolve(name).map(|idx| row[idx].clone())
+                };
+                selected.push(self.eval_result_row(
+                    &columns,
+                    Some(row),
+                    &value,
+                )?);
+            }
+        }
+
+        selected.sort_by(|(_, a), (_, b)| compare_keys(a, b, &self.order_by));
+        let mut rows: Vec<Row> =
+            selected.into_iter().map(|(row, _)| row).collect();
+        if let Some(limit) = self.limit {
+            rows.truncate(limit);
+        }
+        Ok(ResultSet::new(columns, rows))
+    }
+
+    // Splits `rows` into groups with equal values of the `group by`
+    // expressions, in the order each group first appears. Nulls are grouped
+    // together, as in SQL. Without `group by`, every row is in one group,
+    // even if there are none.
+    fn group_rows<'a>(
+        &self,
+        scope: &Scope,
+        rows: &[&'a Row],
+    ) -> anyhow::Result<Vec<Vec<&'a Row>>> {
+        if self.group_by.is_empty() {
+            return Ok(vec![rows.to_vec()]);
+        }
+        let mut groups: Vec<(Row, Vec<&Row>)> = vec![];
+        for &row in rows {
+            let key: Row = self
+                .group_by
+                .iter()
+                .map(|expr| {
+                    expr.eval(&|name: &str| {
+                        scope.resolve(name).map(|idx| row[idx].clone())
+                    })
+                })
+                .collect::<anyhow::Result<_>>()?;
+            let group = groups.iter_mut().find(|(group_key, _)| {
+                group_key
+                    .iter()
+                    .zip(&key)
+                    .all(|(a, b)| compare_cells(a, b).is_eq())
+            });
+            match group {
+                Some((_, group)) => group.push(row),
+                None => groups.push((key, vec![row])),
+            }
+        }
+        Ok(groups.into_iter().map(|(_, group)| group).collect())
+    }
+
+    // Evaluates a row of the result along with its sort keys, given the
+    // value of each column the query can refer to. `select *` copies
+    // `source`.
+    fn eval_result_row(
+        &self,
+        columns: &[ResultColumn],
+        source: Option<&Row>,
+        value: &impl Fn(&str) -> anyhow::Result<Option<Literal>>,
+    ) -> anyhow::Result<(Row, Vec<Option<Literal>>)> {
+        let out: Row = match &self.items {
+            Some(items) => items
+                .iter()
+                .map(|item| item.expr.eval(value))
+                .collect::<anyhow::Result<_>>()?,
+            None => source.cloned().unwrap_or_default(),
+        };
+        // rows can be sorted by the table's columns or the result's
+        let keys = self
+            .order_by
+            .iter()
+            .map(|order| {
+                order.expr.eval(&|name: &str| match value(name) {
+                    Ok(val) => Ok(val),
+                    Err(e) => columns
+                        .iter()
+                        .position(|col| &*col.name == name)
+                        .map(|idx| out[idx].clone())
+                        .ok_or(e),
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok((out, keys))
+    }
+}
+
+/// The rows returned by a query, along with the name and type of each of
+/// their columns.
+#[derive(Clone)]
+pub struct ResultSet {
+    columns: Vec<ResultColumn>,
+    rows: Vec<Row>,
+}
+
+impl ResultSet {
+    pub fn new(columns: Vec<ResultColumn>, rows: Vec<Row>) -> Self {
+        Self { columns, rows }
+    }
+
+    pub fn get_columns(&self) -> &[ResultColumn] {
+        &self.columns
+    }
+
+    pub fn get_column_idx(&self, name: &str) -> Option<usize> {
+        self.columns.iter().position(|col| &*col.name == name)
+    }
+
+    pub fn get_rows(&self) -> &[Row] {
+        &self.rows
+    }
+
+    pub fn get_num_rows(&self) -> usize {
+        self.rows.len()
+    }
+}
+
+impl ToJson for ResultSet {
+    fn to_json(&self) -> Value {
+        json!({
+            "columns": Value::Array(self.columns.iter().map(|col| json!({
+                "name": *col.name,
+                "type": col.data_type.to_json()
+            })).collect()),
+            "rows": Value::Array(self.rows.iter().map(|row| Value::Array(
+                row.iter()
+                    .map(|cell| cell.as_ref().map_or(Value::Null, |val| val.to_json()))
+                    .collect()
+            )).collect())
+        })
+    }
+}
@@ -0,0 +1,302 @@
+use std::{collections::HashMap, fs, rc::Rc};
+
+use rlrl::parse::TokenQueue;
+
+use crate::{
+    json::ToJson,
+    ql::{
+        Stmt, Symbol, SymbolTable,
+        alter::AlterStmt,
+        import::{FsLoader, Importer, SymbolOrigins},
+        lex::{Token, setup_lexer},
+        parse::Parse,
+    },
+};
+
+const HELP: &str = ".tables             list the tables
+.types              list the type definitions
+.views              list the views
+.describe <name>    show the definition of a table, type or view
+.json <name>        show the JSON representation of a table, type or view
+.drop <name>        remove a table, type or view
+.save <file>        write the current definitions to a file
+.help               show this message";
+
+/// An interactive session that keeps a live symbol table across inputs.
+///
+/// Statements may span several lines; input is buffered until a line ends with
+/// `;`. Lines starting with `.` are meta-commands.
+pub struct Repl {
+    symtable: SymbolTable,
+    origins: SymbolOrigins,
+    // statements in definition order, used to list and save definitions
+    stmts: Vec<Stmt>,
+    buffer: String,
+}
+
+impl Default for Repl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Repl {
+    pub fn new() -> Self {
+        Self {
+            symtable: HashMap::new(),
+            origins: HashMap::new(),
+            stmts: vec![],
+            buffer: String::new(),
+        }
+    }
+
+    /// Returns `true` if the REPL is waiting for the rest of a statement.
+    pub fn is_continuation(&self) -> bool {
+        !self.buffer.is_empty()
+    }
+
+    /// Feeds a line of input to the REPL. Returns `None` if the line didn't
+    /// complete a statement, otherwise returns the result of evaluating it.
+    pub fn feed_line(&mut self, line: &str) -> Option<anyhow::Result<String>> {
+        let trimmed = line.trim();
+
+        if !self.is_continuation() {
+            if trimmed.is_empty() {
+                return None;
+            }
+            if trimmed.starts_with('.') {
+                return Some(self.eval_meta(trimmed));
+            }
+        }
+
+        self.buffer.push_str(line);
+        self.buffer.push('\n');
+
+        if !trimmed.ends_with(';') {
+            return None;
+        }
+
+        let input = std::mem::take(&mut self.buffer);
+        Some(self.eval_stmts(&input))
+    }
+
+    /// Evaluates one or more `;`-terminated statements.
+    pub fn eval_stmts(&mut self, input: &str) -> anyhow::Result<String> {
+        let mut tq = TokenQueue::from(setup_lexer().lex(input)?);
+        let mut out = vec![];
+
+        while !tq.is_consumed() {
+            let stmt = tq.parse_with_mut(Stmt::parse, &mut self.symtable)?;
+            out.push(match &stmt {
+                Stmt::Schema(header) => {
+                    if self
+                        .stmts
+                        .iter()
+                        .any(|stmt| matches!(stmt, Stmt::Schema(_)))
+                    {
+                        return Err(anyhow::anyhow!(
+                            "Schema header is already defined!"
+                        ));
+                    }
+                    format!("Defined schema {}", header.get_name())
+                }
+                Stmt::Import(import) => {
+                    // imports are resolved relative to the working directory
+                    Importer::new(&FsLoader).import(
+                        "<repl>",
+                        import,
+                        &mut self.symtable,
+                        &mut self.origins,
+                    )?;
+                    format!("Imported {}", import.get_path())
+                }
+                Stmt::TypeDef(type_name, _) => {
+                    format!("Defined type {type_name}")
+                }
+                Stmt::TableSchema(table) => {
+                    format!("Defined table {}", table.get_name())
+                }
+                Stmt::View(view) => {
+                    format!("Defined view {}", view.get_name())
+                }
+                Stmt::Select(select) => {
+                    // the REPL holds no rows, so only the result's columns
+                    // are shown
+                    let columns: Vec<String> = select
+                        .check(&self.symtable)?
+                        .iter()
+                        .map(|col| {
+                            format!("{}: {}", col.get_name(), col.get_type())
+                        })
+                        .collect();
+                    format!("Query returns ({})", columns.join(", "))
+                }
+                // statements that change rows are checked as they're parsed
+                Stmt::Insert(insert) => {
+                    format!("Checked insert into {}", insert.get_table_name())
+                }
+                Stmt::Update(update) => {
+                    format!("Checked update of {}", update.get_table_name())
+                }
+                Stmt::Delete(delete) => {
+                    format!("Checked delete from {}", delete.get_table_name())
+                }
+                Stmt::Alter(alter) => {
+                    self.apply_alter(alter);
+                    match alter.get_new_table_name() {
+                        Some(name) => format!("Altered table {name}"),
+                        None => {
+                            format!("Dropped table {}", alter.get_table_name())
+                        }
+                    }
+                }
+            });
+            // the symbol table already holds the definition, so keep the
+            // statement in sync with it before checking for the ';'
+            match stmt {
+                // the header has to come first when saved
+                Stmt::Schema(_) => self.stmts.insert(0, stmt),
+                // alterations are folded into the tables they change, and
+                // queries aren't definitions
+                Stmt::Alter(_)
+                | Stmt::Select(_)
+                | Stmt::Insert(_)
+                | Stmt::Update(_)
+                | Stmt::Delete(_) => {}
+                _ => self.stmts.push(stmt),
+            }
+
+            tq.consume_eq(Token::Semicolon)
+                .map_err(|_| anyhow::anyhow!("Expected ';'"))?;
+        }
+
+        Ok(out.join("\n"))
+    }
+
+    // Replaces the definition of the table `alter` changes with the altered
+    // table from the symbol table, or removes it if the table was dropped.
+    fn apply_alter(&mut self, alter: &AlterStmt) {
+        let table_name = alter.get_table_name();
+        let altered = alter.get_new_table_name().and_then(|name| {
+            match self.symtable.get(&name) {
+                Some(Symbol::TableSchema(table)) => Some(table.clone()),
+                _ => None,
+            }
+        });
+        let idx = self.stmts.iter().position(|stmt| {
+            matches!(stmt, Stmt::TableSchema(table) if table.get_name() == table_name)
+        });
+        match (idx, altered) {
+            (Some(idx), Some(table)) => {
+                self.stmts[idx] = Stmt::TableSchema(table)
+            }
+            (Some(idx), None) => {
+                self.stmts.remove(idx);
+            }
+            // imported tables aren't saved with the session
+            (None, _) => {}
+        }
+    }
+
+    fn stmt_name(stmt: &Stmt) -> Rc<str> {
+        match stmt {
+            Stmt::Schema(header) => header.get_name(),
+            Stmt::Import(import) => import.get_path(),
+            Stmt::TypeDef(type_name, _) => type_name.clone(),
+            Stmt::TableSchema(table) => table.get_name(),
+            Stmt::View(view) => view.get_name(),
+            Stmt::Alter(alter) => alter.get_table_name(),
+            Stmt::Select(select) => select.get_table_name(),
+            Stmt::Insert(insert) => insert.get_table_name(),
+            Stmt::Update(update) => update.get_table_name(),
+            Stmt::Delete(delete) => delete.get_table_name(),
+        }
+    }
+
+    fn get_stmt(&self, name: &str) -> anyhow::Result<&Stmt> {
+        self.stmts
+            .iter()
+            .find(|stmt| &*Self::stmt_name(stmt) == name)
+            .ok_or(anyhow::anyhow!("Unrecognised table, type or view {name}"))
+    }
+
+    /// Evaluates a meta-command such as `.tables` or `.describe Users`.
+    pub fn eval_meta(&mut self, cmd: &str) -> anyhow::Result<String> {
+        let mut parts = cmd.split_whitespace();
+        let name = parts.next().unwrap_or("");
+        let arg = parts.next();
+
+        match (name, arg) {
+            (".tables", None) => Ok(self
+                .stmts
+                .iter()
+                .filter(|stmt| matches!(stmt, Stmt::TableSchema(_)))
+                .map(|stmt| Self::stmt_name(stmt).to_string())
+                .collect::<Vec<String>>()
+                .join("\n")),
+            (".types", None) => Ok(self
+                .stmts
+                .iter()
+                .filter(|stmt| matches!(stmt, Stmt::TypeDef(_, _)))
+                .map(|stmt| Self::stmt_name(stmt).to_string())
+                .collect::<Vec<String>>()
+                .join("\n")),
+            (".views", None) => Ok(self
+                .stmts
+                .iter()
+                .filter(|stmt| matches!(stmt, Stmt::View(_)))
+                .map(|stmt| Self::stmt_name(stmt).to_string())
+                .collect::<Vec<String>>()
+                .join("\n")),
+            (".describe", Some(arg)) => Ok(self.get_stmt(arg)?.to_string()),
+            (".json", Some(arg)) => {
+                let json = match self.get_stmt(arg)? {
+                    Stmt::Schema(header) => header.to_json(),
+                    Stmt::Import(import) => {
+                        serde_json::Value::String(import.to_string())
+                    }
+                    Stmt::TypeDef(_, data_type) => data_type.to_json(),
+                    Stmt::TableSchema(table) => table.to_json(),
+                    Stmt::View(view) => view.to_json(),
+                    Stmt::Alter(alter) => {
+                        serde_json::Value::String(alter.to_string())
+                    }
+                    Stmt::Select(select) => {
+                        serde_json::Value::String(select.to_string())
+                    }
+                    Stmt::Insert(insert) => {
+                        serde_json::Value::String(insert.to_string())
+                    }
+                    Stmt::Update(update) => {
+                        serde_json::Value::String(update.to_string())
+                    }
+                    Stmt::Delete(delete) => {
+                        serde_json::Value::String(delete.to_string())
+                    }
+                };
+                Ok(serde_json::to_string_pretty(&json)?)
+            }
+            (".drop", Some(arg)) => {
+                self.get_stmt(arg)?;
+                self.stmts.retain(|stmt| &*Self::stmt_name(stmt) != arg);
+                self.symtable.remove(arg);
+                Ok(format!("Dropped {arg}"))
+            }
+            (".save", Some(arg)) => {
+                let mut s = String::new();
+                for stmt in &self.stmts {
+                    s.push_str(&format!("{stmt}\n"));
+                }
+                fs::write(arg, s)?;
+                Ok(format!("Saved {} statements to {arg}", self.stmts.len()))
+            }
+            (".help", None) => Ok(HELP.to_string()),
+            _ => Err(anyhow::anyhow!("Unrecognised command {cmd} (try .help)")),
+        }
+    }
+
+    /// Returns the current symbol table.
+    pub fn get_symtable(&self) -> &SymbolTable {
+        &self.symtable
+    }
+}
@@ -1,16 +1,40 @@
 use crate::{
-    core::schema::SpreadsheetSchema,
+    core::{
+        address::{
+            CellAddress, RangeAddress, Reference, column_letters,
+            parse_column_letters,
+        },
+        data::{Dataset, Row, TableData},
+        migrate::{Migration, MigrationOp, ViolationPolicy},
+        recalc::{CellRef, ColumnRef, DependencyGraph, Recalculator},
+        schema::{
+            DataType, SharedDataType, SpreadsheetSchema, StrDataType,
+            coerce::Strictness,
+            diff::diff_schemas,
+            export::{to_json_schema, to_sql, to_ts},
+            validate::ViolationKind,
+        },
+    },
+    json::ToJson,
+    lsp::analysis::{Analysis, Position},
+    parse_valid_schema_from_str,
     ql::{
-        Stmt,
-        lex::{Token, setup_lexer},
+        Stmt, Symbol, SymbolTable,
+        expr::{Cond, Expr},
+        format::{format_schema, format_schema_with_loader},
+        import::{MemoryLoader, parse_schema_with_loader},
+        lex::{Literal, Token, setup_lexer},
         parse::{Parse, parse_spreadsheet_schema},
+        prepare::PreparedQuery,
+        query::ResultSet,
     },
+    repl::Repl,
 };
 use rlrl::parse::TokenQueue;
 use std::{collections::HashMap, fs};
 
-const NUM_VALID_TEST_SCHEMA: usize = 3;
-const NUM_INVALID_TEST_SCHEMA: usize = 5;
+const NUM_VALID_TEST_SCHEMA: usize = 5;
+const NUM_INVALID_TEST_SCHEMA: usize = 8;
 
 fn lex_file(path: &str) -> anyhow::Result<TokenQueue<Token>> {
     let s = fs::read_to_string(path)?;
@@ -83,10 +107,14 @@ fn parse_valid_schema_from_file(
 fn test_ddl_1() -> anyhow::Result<()> {
     let stmt1 = "table T (a: int<, >);";
     let stmt2 = "type myType int<1, 5>;";
+    let stmt3 =
+        "schema \"Movie Reviews\" version \"1.0\" description \"Ratings\";";
 
     // test the program parses simple statements
     assert_maps_to_stmt(stmt1)?;
     assert_maps_to_stmt(stmt2)?;
+    assert_maps_to_stmt(stmt3)?;
+    assert_maps_to_stmt("schema \"Movie Reviews\";")?;
 
     // test the program parses simple single-statement schemas
     assert_maps_to_schema(stmt1)?;
@@ -135,6 +163,12 @@ table T2 (b: int<, >);",
     // schemas with default values
     assert_maps_to_schema("table T (a: int<, > = 0);")?;
 
+    // schemas with a header
+    assert_maps_to_schema(
+        "schema \"Movie Reviews\" version \"2\";
+table T (a: int<, >);",
+    )?;
+
     Ok(())
 }
 
@@ -197,3 +231,2474 @@ fn test_schema_validation() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_schema_header() -> anyhow::Result<()> {
+    let schema = parse_valid_schema_from_str(
+        "schema \"Movie Reviews\" version 2 description \"Ratings\";
+        table T (version: int, description: str);",
+    )?;
+    assert_eq!(schema.get_name().as_deref(), Some("Movie Reviews"));
+
+    let json = schema.to_json();
+    assert_eq!(json["ss_name"], "Movie Reviews");
+    assert_eq!(json["version"], "2");
+    assert_eq!(json["description"], "Ratings");
+
+    // schemas without a header have no name
+    let json = parse_valid_schema_from_str("table T (a: int);")?.to_json();
+    assert!(json["ss_name"].is_null());
+
+    Ok(())
+}
+
+#[test]
+fn test_type_refinement() -> anyhow::Result<()> {
+    let schema = parse_valid_schema_from_str(
+        "type uIntType int<0, >?;
+        type smallUInt uIntType<, 100>;
+        type percent smallUInt<1, >!;
+        table T (a: smallUInt, b: uIntType?, c: percent);",
+    )?;
+    assert_eq!(
+        schema.to_string().trim(),
+        "table T (a: int<0, 100>?, b: int<0, >?, c: int<1, 100>);"
+    );
+
+    let json = schema.get_table(0).unwrap().get_columns()[2]
+        .get_type()
+        .to_json();
+    assert_eq!(
+        json["lineage"],
+        serde_json::json!(["smallUInt", "uIntType"])
+    );
+
+    // a refined type definition prints the alias it was derived from
+    let mut symtable = HashMap::new();
+    lex("type uIntType int<0, >")?
+        .parse_with_mut(Stmt::parse, &mut symtable)?;
+    let stmt = lex("type smallUInt uIntType<, 100>")?
+        .parse_with_mut(Stmt::parse, &mut symtable)?;
+    assert_eq!(stmt.to_string(), "type smallUInt uIntType<0, 100>!;");
+
+    // refinements are as nullable as their base unless narrowed with `!`
+    lex("type maybeUInt int<0, >?")?
+        .parse_with_mut(Stmt::parse, &mut symtable)?;
+    for (src, expected) in [
+        ("type a maybeUInt<, 100>", "type a maybeUInt<0, 100>?;"),
+        ("type b maybeUInt!", "type b maybeUInt<0, >!;"),
+        ("type c maybeUInt<, 5>!", "type c maybeUInt<0, 5>!;"),
+    ] {
+        let mut reparse_symtable = symtable.clone();
+        let stmt = lex(src)?.parse_with_mut(Stmt::parse, &mut symtable)?;
+        assert_eq!(stmt.to_string(), expected);
+        assert_eq!(
+            lex(expected)?
+                .parse_with_mut(Stmt::parse, &mut reparse_symtable)?
+                .to_string(),
+            expected
+        );
+    }
+
+    // refinements can't widen the base type
+    for src in [
+        "type uIntType int<0, >; type t uIntType<-1, 5>;",
+        "type small int<, 10>; type t small<, 11>;",
+        "type uIntType int<0, >; type t uIntType?;",
+        "type n int?; type t n?!;",
+        "type uIntType int<0, >; type t uIntType<5, 3>;",
+    ] {
+        assert!(parse_valid_schema_from_str(src).is_err());
+    }
+
+    Ok(())
+}
+
+fn data_type(s: &str) -> SharedDataType {
+    let mut symtable = HashMap::new();
+    let src = format!("type t {s}");
+    match lex(&src)
+        .and_then(|mut tq| tq.parse_with_mut(Stmt::parse, &mut symtable))
+    {
+        Ok(Stmt::TypeDef(_, dtype)) => dtype,
+        _ => panic!("Couldn't parse type {s}"),
+    }
+}
+
+#[test]
+fn test_subtyping() -> anyhow::Result<()> {
+    for (from, to) in [
+        ("int<0, 10>", "int"),
+        ("int<0, 10>", "int<0, 10>"),
+        ("int<0, 10>", "dbl"),
+        ("int<0, 10>", "dbl<-0.5, 10.0>"),
+        ("int<0, 10>", "int<, 10>?"),
+        ("dbl<0.5, 1.5>", "dbl<0.0, >"),
+        ("str<1, 5>", "str<0, 10>"),
+    ] {
+        assert!(
+            data_type(from).is_assignable_to(&*data_type(to)),
+            "{from} should be assignable to {to}"
+        );
+    }
+    for (from, to) in [
+        ("int", "int<0, >"),
+        ("int<0, 10>?", "int"),
+        ("int<0, 11>", "dbl<0.0, 10.5>"),
+        ("dbl<0.0, 1.0>", "int"),
+        ("str", "int"),
+        ("str<0, 10>", "str<1, 10>"),
+    ] {
+        assert!(
+            !data_type(from).is_assignable_to(&*data_type(to)),
+            "{from} shouldn't be assignable to {to}"
+        );
+    }
+
+    for (a, b, expected) in [
+        ("int<0, >", "int<, 10>", "int<0, 10>"),
+        ("int<0, 10>?", "int<5, 20>?", "int<5, 10>?"),
+        ("int<0, 10>?", "int<5, 20>", "int<5, 10>"),
+        ("int<0, 10>", "dbl<0.5, 7.5>", "int<1, 7>"),
+        ("dbl<0.5, 7.5>", "dbl<, 2.0>", "dbl<0.5, 2>"),
+        ("str<1, >", "str<, 8>", "str<1, 8>"),
+    ] {
+        let intersection = data_type(a).intersect(&*data_type(b))?;
+        assert_eq!(intersection.to_string(), expected);
+    }
+    for (a, b) in [
+        ("int<0, 10>", "int<11, >"),
+        ("int<0, 10>", "dbl<0.2, 0.8>"),
+        ("int", "dbl(1e19, >"),
+        ("int", "dbl<, -1e19)"),
+        ("int", "str"),
+    ] {
+        let e = data_type(a).intersect(&*data_type(b)).err().unwrap();
+        assert!(e.to_string().starts_with("Empty type"));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_str_length() -> anyhow::Result<()> {
+    let dtype = StrDataType::new(Some(2), Some(4), false);
+    let validate =
+        |s: &str| dtype.validate_literal(Some(&Literal::Str(s.into())));
+
+    assert!(validate("a").is_err());
+    assert!(validate("ab").is_ok());
+    assert!(validate("abcd").is_ok());
+    assert!(
+        validate("abcde")
+            .unwrap_err()
+            .to_string()
+            .contains("Maximum length 4")
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_schema_diff() -> anyhow::Result<()> {
+    let old = parse_valid_schema_from_str(
+        "table Users (id: int<0, >, name: str, age: int<0, 150>, score: dbl = 0.0);
+        table Old (a: int);
+        table Before (x: str, y: str);",
+    )?;
+    let new = parse_valid_schema_from_str(
+        "table Users (id: int, name: str?, score: dbl = 1.0, email: str<3, >?);
+        table After (x: str, y: str);
+        table Reviews (rating: int<1, 5>);",
+    )?;
+
+    let diff = diff_schemas(&old, &new);
+    assert_eq!(
+        diff.to_string(),
+        "~ table Users
+    ~ column id: int<0, > -> int<, > (widening)
+    ~ column name: now nullable
+    - column age
+    ~ column score: default 0 -> 1
+    + column email: str<3, >?
+- table Old
+~ table Before renamed to After
++ table Reviews"
+    );
+
+    let json = diff.to_json();
+    assert_eq!(json["changes"][0]["columns"][0]["kind"], "widening");
+    assert_eq!(json["changes"][2]["change"], "rename_table");
+
+    assert!(diff_schemas(&new, &new).is_empty());
+    // types are compared by their values, not by how they're written
+    assert!(
+        diff_schemas(
+            &parse_valid_schema_from_str("table T (x: int);")?,
+            &parse_valid_schema_from_str("table T (x: int<, >);")?,
+        )
+        .is_empty()
+    );
+    assert!(
+        diff_schemas(&new, &old)
+            .to_string()
+            .contains("~ column id: int<, > -> int<0, > (narrowing)")
+    );
+
+    Ok(())
+}
+
+fn dataset(
+    schema: &SpreadsheetSchema,
+    rows: Vec<(&str, Vec<Row>)>,
+) -> anyhow::Result<Dataset> {
+    let mut data = Dataset::new();
+    for table in schema.get_tables() {
+        data.insert(table.get_name(), TableData::new(table.clone()));
+    }
+    for (name, rows) in rows {
+        let table = schema.get_table_by_name(name).unwrap().clone();
+        data.insert(name.into(), TableData::with_rows(table, rows)?);
+    }
+    Ok(data)
+}
+
+#[test]
+fn test_migrations() -> anyhow::Result<()> {
+    let old = parse_valid_schema_from_str(
+        "table Users (id: int<0, >, name: str, age: int<0, 200>);
+        table Before (x: int);",
+    )?;
+    let new = parse_valid_schema_from_str(
+        "table Users (id: int<0, >, score: dbl = 0.5, name: str<, 4>, age: dbl<0.0, 150.0>?);
+        table After (x: int);",
+    )?;
+    let rows = vec![
+        vec![
+            Some(Literal::Int(1)),
+            Some(Literal::Str("Ann".into())),
+            Some(Literal::Int(30)),
+        ],
+        vec![
+            Some(Literal::Int(2)),
+            Some(Literal::Str("Bartholomew".into())),
+            Some(Literal::Int(180)),
+        ],
+    ];
+    let mut data = dataset(
+        &old,
+        vec![
+            ("Users", rows),
+            ("Before", vec![vec![Some(Literal::Int(7))]]),
+        ],
+    )?;
+
+    // the long name and large age fail by default
+    let migration = Migration::between(&old, &new);
+    let report = migration.dry_run(&data)?;
+    assert_eq!(report.get_failures().len(), 2);
+    assert_eq!(report.get_failures()[0].row_idx, 1);
+    assert!(migration.apply(&mut data).is_err());
+    assert_eq!(data["Users"].get_rows()[1][2], Some(Literal::Int(180)));
+
+    let report = migration
+        .with_policy(ViolationPolicy::Truncate)
+        .apply(&mut data)?;
+    assert!(report.is_ok());
+    assert_eq!(report.get_truncations().len(), 2);
+    assert!(!data.contains_key("Before"));
+    assert_eq!(data["After"].get_num_rows(), 1);
+    assert_eq!(
+        data["Users"].get_schema().to_string(),
+        new.get_table_by_name("Users").unwrap().to_string()
+    );
+    assert_eq!(
+        data["Users"].get_rows()[1],
+        vec![
+            Some(Literal::Int(2)),
+            Some(Literal::Dbl(0.5)),
+            Some(Literal::Str("Bart".into())),
+            Some(Literal::Dbl(150.0)),
+        ]
+    );
+
+    // a column's new type and default are applied together, so nulls take the
+    // new default and the default is checked against the new type
+    let old = parse_valid_schema_from_str("table T (bio: str?, n: int);")?;
+    let new = parse_valid_schema_from_str(
+        "table T (bio: str = \"x\", n: dbl<0.0, 1.0> = 0.5);",
+    )?;
+    let mut altered =
+        dataset(&old, vec![("T", vec![vec![None, Some(Literal::Int(1))]])])?;
+    Migration::between(&old, &new).apply(&mut altered)?;
+    assert_eq!(
+        altered["T"].get_rows()[0],
+        vec![Some(Literal::Str("x".into())), Some(Literal::Dbl(1.0))]
+    );
+
+    // explicit operations
+    let ops = vec![
+        MigrationOp::RenameColumn {
+            table_name: "Users".into(),
+            old_name: "score".into(),
+            new_name: "rating".into(),
+        },
+        MigrationOp::ChangeType {
+            table_name: "Users".into(),
+            column_name: "rating".into(),
+            new_type: data_type("int<0, 10>"),
+        },
+        MigrationOp::DropColumn {
+            table_name: "Users".into(),
+            column_name: "age".into(),
+        },
+    ];
+    let report = Migration::new(ops).dry_run(&data)?;
+    assert_eq!(
+        report.get_failures()[0].message,
+        "Can't convert 0.5 to an integer without losing precision"
+    );
+
+    let missing = Migration::new(vec![MigrationOp::DropColumn {
+        table_name: "Users".into(),
+        column_name: "email".into(),
+    }]);
+    assert!(missing.dry_run(&data).is_err());
+
+    // doubles outside the integer range fail, or are clamped and reported
+    let old = parse_valid_schema_from_str("table T (x: dbl);")?;
+    let new = parse_valid_schema_from_str("table T (x: int64);")?;
+    let data =
+        dataset(&old, vec![("T", vec![vec![Some(Literal::Dbl(1e300))]])])?;
+    let migration = Migration::between(&old, &new);
+    let report = migration.dry_run(&data)?;
+    assert!(report.get_failures()[0].message.contains("too large"));
+    let mut clamped = data.clone();
+    let report = migration
+        .with_policy(ViolationPolicy::Truncate)
+        .apply(&mut clamped)?;
+    assert_eq!(report.get_truncations().len(), 1);
+    assert_eq!(clamped["T"].get_rows()[0][0], Some(Literal::Int(i64::MAX)));
+
+    // reordering columns moves the cells of stored rows, also in renamed
+    // tables
+    let old = parse_valid_schema_from_str(
+        "table T (a: int, b: str); table U (x: int, y: str);",
+    )?;
+    let new = parse_valid_schema_from_str(
+        "table T (b: str, a: int); table V (y: str, x: int);",
+    )?;
+    assert_eq!(
+        diff_schemas(&old, &new).to_string(),
+        "~ table T\n    ~ columns reordered: b, a\n\
+         ~ table U renamed to V\n\
+         ~ table V\n    ~ columns reordered: y, x"
+    );
+    let row = vec![Some(Literal::Int(1)), Some(Literal::Str("s".into()))];
+    let mut reordered =
+        dataset(&old, vec![("T", vec![row.clone()]), ("U", vec![row])])?;
+    Migration::between(&old, &new).apply(&mut reordered)?;
+    let expected = vec![Some(Literal::Str("s".into())), Some(Literal::Int(1))];
+    assert_eq!(reordered["T"].get_rows()[0], expected);
+    assert_eq!(reordered["V"].get_rows()[0], expected);
+
+    Ok(())
+}
+
+#[test]
+fn test_alter() -> anyhow::Result<()> {
+    let mut symtable = HashMap::new();
+    lex("table Users (id: int, name: str)")?
+        .parse_with_mut(Stmt::parse, &mut symtable)?;
+    for stmt in [
+        "alter table Users add column email: int<3, >? = 5;",
+        "alter table Users rename column name to fullName;",
+        "alter table Users alter column id type int<0, >;",
+        "alter table Users drop column email;",
+        "rename table Users to People;",
+        "drop table People;",
+    ] {
+        let parsed = lex(stmt)?.parse_with_mut(Stmt::parse, &mut symtable)?;
+        assert_eq!(parsed.to_string(), stmt);
+    }
+    assert!(symtable.is_empty());
+
+    // `column` and `to` are only keywords inside alter statements
+    let schema = parse_valid_schema_from_str(
+        "table T (column: int, to: str);
+        alter table T rename column to to from;",
+    );
+    assert!(schema.is_err());
+    let schema = parse_valid_schema_from_str(
+        "table T (column: int, to: str);
+        alter table T rename column to to dest;",
+    )?;
+    assert_eq!(
+        schema.to_string().trim(),
+        "table T (column: int<, >, dest: str<, >);"
+    );
+
+    for src in [
+        "alter table Missing drop column a;",
+        "table T (a: int); alter table T drop column b;",
+        "table T (a: int); alter table T add column a: str;",
+        "table T (a: int); table U (b: int); rename table T to U;",
+        "table T (a: int); alter table T alter column a type str<5, 1>;",
+    ] {
+        assert!(parse_valid_schema_from_str(src).is_err(), "{src}");
+    }
+
+    // the altered table is validated, so defaults must fit a column's new
+    // type and formulas can't use dropped columns
+    let schema = parse_valid_schema_from_str(
+        "table T (a: int = 50); alter table T alter column a type int<0, 10>;",
+    );
+    assert!(
+        format!("{:#}", schema.err().unwrap())
+            .contains("Default value 50 of column a is invalid")
+    );
+    let schema = parse_valid_schema_from_str(
+        "table T (a: int, b: int = a + 1); alter table T drop column a;",
+    );
+    assert!(schema.is_err());
+
+    let mut loader = MemoryLoader::new();
+    loader.add_file("common.ft", "table Shared (a: int);");
+    loader.add_file(
+        "main.ft",
+        "import \"common.ft\"; alter table Shared drop column a;",
+    );
+    assert!(
+        import_error("main.ft", &loader)
+            .contains("Table Shared is not defined in this schema!")
+    );
+
+    let mut repl = Repl::new();
+    repl.feed_line("table Users (id: int);").unwrap()?;
+    assert_eq!(
+        repl.feed_line("alter table Users add column bio: str?;")
+            .unwrap()?,
+        "Altered table Users"
+    );
+    assert_eq!(
+        repl.feed_line(".describe Users").unwrap()?,
+        "table Users (id: int<, >, bio: str<, >?);"
+    );
+    repl.feed_line("drop table Users;").unwrap()?;
+    assert_eq!(repl.feed_line(".tables").unwrap()?, "");
+
+    Ok(())
+}
+
+#[test]
+fn test_validate_row() -> anyhow::Result<()> {
+    let schema = parse_valid_schema_from_str(
+        "table T (id: int<0, 10>, name: str<2, 5>, score: dbl, bio: str?, rank: int = 3);",
+    )?;
+    let table = schema.get_table(0).unwrap();
+    let kinds =
+        |row: &[Option<Literal>]| -> Vec<(Option<String>, ViolationKind)> {
+            table
+                .validate_row(row)
+                .into_iter()
+                .map(|v| {
+                    (
+                        v.get_column_name().map(|name| name.to_string()),
+                        v.get_kind(),
+                    )
+                })
+                .collect()
+        };
+    let col = |name: &str| Some(name.to_string());
+
+    assert_eq!(
+        kinds(&[
+            Some(Literal::Int(-1)),
+            Some(Literal::Str("a".into())),
+            Some(Literal::Int(2)),
+            None,
+            None,
+            Some(Literal::Int(9)),
+        ]),
+        vec![
+            (col("id"), ViolationKind::TooSmall),
+            (col("name"), ViolationKind::TooShort),
+            (col("score"), ViolationKind::WrongType),
+            (None, ViolationKind::ExtraValue),
+        ]
+    );
+    assert_eq!(
+        kinds(&[Some(Literal::Int(11)), Some(Literal::Str("abcdef".into())),]),
+        vec![
+            (col("id"), ViolationKind::TooLarge),
+            (col("name"), ViolationKind::TooLong),
+            (col("score"), ViolationKind::MissingValue),
+        ]
+    );
+
+    let row = vec![
+        Some(Literal::Int(1)),
+        Some(Literal::Str("abc".into())),
+        Some(Literal::Dbl(0.5)),
+    ];
+    assert!(table.validate_row(&row).is_empty());
+
+    // missing cells are filled from defaults when the row is stored
+    let data = TableData::with_rows(table.clone(), vec![row])?;
+    assert_eq!(data.get_rows()[0][3], None);
+    assert_eq!(data.get_rows()[0][4], Some(Literal::Int(3)));
+
+    let json = table.validate_row(&[None]).first().unwrap().to_json();
+    assert_eq!(json["kind"], "NullNotAllowed");
+    assert_eq!(json["column_name"], "id");
+
+    // short rows are only padded with optional cells
+    let violations = table.validate_row(&[Some(Literal::Int(1))]);
+    assert_eq!(violations.len(), 2);
+    assert_eq!(
+        violations[1].to_string(),
+        "In column score: Required value is missing!"
+    );
+    assert_eq!(violations[1].to_json()["kind"], "MissingValue");
+    assert!(TableData::with_rows(table.clone(), vec![vec![]]).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_coercion() -> anyhow::Result<()> {
+    let lenient = Strictness::Lenient;
+    let coerce =
+        |dtype: &str, text: &str| data_type(dtype).coerce(text, lenient);
+
+    assert_eq!(coerce("int", "42")?, Some(Literal::Int(42)));
+    assert_eq!(coerce("int", " +1,000 ")?, Some(Literal::Int(1000)));
+    assert_eq!(coerce("int", "3.0")?, Some(Literal::Int(3)));
+    assert_eq!(coerce("dbl", " 3.5 ")?, Some(Literal::Dbl(3.5)));
+    assert_eq!(coerce("dbl", "42")?, Some(Literal::Dbl(42.0)));
+    assert_eq!(coerce("dbl", "-1,234.5")?, Some(Literal::Dbl(-1234.5)));
+    assert_eq!(coerce("str", " Ann ")?, Some(Literal::Str("Ann".into())));
+    assert_eq!(coerce("int?", "")?, None);
+    assert_eq!(coerce("str?", "  ")?, None);
+    assert_eq!(coerce("str", "")?, Some(Literal::Str("".into())));
+
+    for (dtype, text, kind) in [
+        ("int", "3.5", ViolationKind::WrongType),
+        ("int", "1,00", ViolationKind::WrongType),
+        ("int", "abc", ViolationKind::WrongType),
+        ("int", "99999999999", ViolationKind::TooLarge),
+        ("int64", "9223372036854775808.0", ViolationKind::TooLarge),
+        ("int64", "-9.3e18", ViolationKind::TooSmall),
+        ("int", "", ViolationKind::NullNotAllowed),
+        ("int<0, 10>", "11", ViolationKind::TooLarge),
+        ("dbl", "NaN", ViolationKind::WrongType),
+        ("str<2, >", "a", ViolationKind::TooShort),
+    ] {
+        assert_eq!(
+            coerce(dtype, text).unwrap_err().get_kind(),
+            kind,
+            "{dtype} {text}"
+        );
+    }
+    assert_eq!(
+        coerce("int", "abc").unwrap_err().to_string(),
+        "Couldn't read \"abc\" as an integer"
+    );
+
+    let strict = |dtype: &str, text: &str| {
+        data_type(dtype).coerce(text, Strictness::Strict)
+    };
+    assert_eq!(strict("str", " Ann ")?, Some(Literal::Str(" Ann ".into())));
+    assert!(strict("int", " 42").is_err());
+    assert!(strict("int", "1,000").is_err());
+    assert!(strict("int", "3.0").is_err());
+    assert!(strict("int", "+1").is_err());
+    assert!(strict("dbl", "+1.5").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_string_length_units() -> anyhow::Result<()> {
+    let str_lit = |s: &str| Literal::Str(s.into());
+    let name = "山田太郎さんです";
+    let family = "👨\u{200d}👩\u{200d}👧";
+
+    // lengths are measured in chars by default
+    let dtype = data_type("str<, 10>");
+    assert!(dtype.check_literal(Some(&str_lit(name))).is_ok());
+    assert_eq!(dtype.to_string(), "str<, 10>");
+
+    let dtype = data_type("str<, 10 bytes>");
+    assert_eq!(
+        dtype
+            .check_literal(Some(&str_lit(name)))
+            .unwrap_err()
+            .get_kind(),
+        ViolationKind::TooLong
+    );
+    assert_eq!(dtype.to_string(), "str<, 10 bytes>");
+    assert_eq!(dtype.to_json()["length_unit"], "bytes");
+
+    let dtype = data_type("str<1, 1 graphemes>");
+    assert!(dtype.check_literal(Some(&str_lit(family))).is_ok());
+    assert!(
+        data_type("str<1, 1>")
+            .check_literal(Some(&str_lit(family)))
+            .is_err()
+    );
+
+    assert_maps_to_stmt("type t str<1, graphemes>?;")?;
+    assert!(parse_valid_schema_from_str("type t str<1, 5 words>;").is_err());
+
+    // refinements keep the unit of their base
+    assert!(
+        parse_valid_schema_from_str("type a str<, 10 bytes>; type b a<, 5>;")
+            .is_ok()
+    );
+    assert!(
+        parse_valid_schema_from_str(
+            "type a str<, 10 bytes>; type b a<, 5 chars>;"
+        )
+        .is_err()
+    );
+
+    assert!(
+        !data_type("str<, 4 bytes>").is_assignable_to(&*data_type("str<, 4>"))
+    );
+    assert!(data_type("str<, 4 bytes>").is_assignable_to(&*data_type("str")));
+
+    Ok(())
+}
+
+#[test]
+fn test_string_escapes() -> anyhow::Result<()> {
+    let str_tok = |s: &str| Token::Literal(Literal::Str(s.into()));
+    assert_eq!(
+        setup_lexer()
+            .lex(r#""say \"hi\"" "a\\b" "1\n2\t3" "\u{e9}\u{1F600}""#)?,
+        vec![
+            str_tok("say \"hi\""),
+            str_tok("a\\b"),
+            str_tok("1\n2\t3"),
+            str_tok("é😀"),
+        ]
+    );
+
+    for malformed in [
+        r#""\q""#,
+        r#""\u41""#,
+        r#""\u{}""#,
+        r#""\u{41""#,
+        r#""\u{110000}""#,
+        r#""\u{zz}""#,
+    ] {
+        assert!(setup_lexer().lex(malformed).is_err(), "{malformed}");
+    }
+
+    // string defaults and names that aren't identifiers are quoted
+    assert_eq!(
+        Literal::Str("a \"b\"\n".into()).to_string(),
+        r#""a \"b\"\n""#
+    );
+    assert_maps_to_stmt(r#"table t (name: str<, > = "Jane \"JD\" Doe\t");"#)?;
+    assert_maps_to_stmt(
+        r#"table "my table" ("first name": str<, >, "type": int<, >);"#,
+    )?;
+    assert_maps_to_stmt(r#"type "a\u{7}b" str<, 10>;"#)?;
+    assert_maps_to_schema(
+        r#"schema "Sales \"2024\"" version "1" description "line\nbreak";"#,
+    )?;
+
+    Ok(())
+}
+
+#[test]
+fn test_int64_and_decimal() -> anyhow::Result<()> {
+    let decimal = |s: &str| Literal::Decimal(s.parse().unwrap());
+
+    // plain ints are 32-bit, int64 holds larger values
+    assert_eq!(
+        data_type("int")
+            .check_literal(Some(&Literal::Int(1 << 40)))
+            .unwrap_err()
+            .get_kind(),
+        ViolationKind::TooLarge
+    );
+    let dtype = data_type("int64<0, >");
+    assert!(dtype.check_literal(Some(&Literal::Int(1 << 40))).is_ok());
+    assert_eq!(dtype.to_string(), "int64<0, >");
+    assert!(
+        parse_valid_schema_from_str("table T (a: int<0, 3000000000>);")
+            .is_err()
+    );
+    assert!(
+        parse_valid_schema_from_str("table T (a: int64<0, 3000000000>);")
+            .is_ok()
+    );
+    assert!(data_type("int").is_assignable_to(&*data_type("int64")));
+    assert!(!data_type("int64").is_assignable_to(&*data_type("int")));
+
+    // literals too precise for a double are read as decimals
+    assert_eq!(
+        setup_lexer().lex("9223372036854775807 0.5 12345678901234567.89")?,
+        vec![
+            Token::Literal(Literal::Int(i64::MAX)),
+            Token::Literal(Literal::Dbl(0.5)),
+            Token::Literal(decimal("12345678901234567.89")),
+        ]
+    );
+
+    let dtype = data_type("decimal<5, 2>");
+    assert!(dtype.check_literal(Some(&decimal("999.99"))).is_ok());
+    assert!(dtype.check_literal(Some(&decimal("1.50"))).is_ok());
+    assert_eq!(
+        dtype
+            .check_literal(Some(&decimal("1000")))
+            .unwrap_err()
+            .get_kind(),
+        ViolationKind::TooLarge
+    );
+    assert_eq!(
+        dtype
+            .check_literal(Some(&decimal("-1000")))
+            .unwrap_err()
+            .get_kind(),
+        ViolationKind::TooSmall
+    );
+    assert_eq!(
+        dtype
+            .check_literal(Some(&decimal("0.125")))
+            .unwrap_err()
+            .get_kind(),
+        ViolationKind::TooPrecise
+    );
+    assert_eq!(
+        dtype
+            .check_literal(Some(&Literal::Dbl(1.5)))
+            .unwrap_err()
+            .get_kind(),
+        ViolationKind::WrongType
+    );
+    assert_eq!(
+        dtype
+            .coerce("1,234.5", Strictness::Lenient)
+            .unwrap_err()
+            .get_kind(),
+        ViolationKind::TooLarge
+    );
+    assert_eq!(
+        dtype.coerce("123.45", Strictness::Lenient)?,
+        Some(decimal("123.45"))
+    );
+    assert!(
+        parse_valid_schema_from_str("table T (a: decimal<2, 3>);").is_err()
+    );
+    assert!(
+        parse_valid_schema_from_str("table T (a: decimal<29, 0>);").is_err()
+    );
+
+    // defaults are converted to decimals and survive a round trip
+    assert_maps_to_stmt("table T (price: decimal<10, 2> = 19.99);")?;
+    let schema = parse_valid_schema_from_str(
+        "type money decimal<10, 2>; type cents money<8, 0>;
+        table T (price: money = 0.1, total: cents);",
+    )?;
+    let table = schema.get_table(0).unwrap();
+    assert_eq!(
+        table.get_columns()[0].get_default_value(),
+        Some(&decimal("0.1"))
+    );
+    assert_eq!(
+        table.get_columns()[1].get_type().to_string(),
+        "decimal<8, 0>"
+    );
+    // a smaller scale would leave room for more digits before the point
+    for src in [
+        "type m decimal<10, 2>; type n m<12, >;",
+        "type m decimal<10, 2>; type n m<, 0>;",
+    ] {
+        assert!(parse_valid_schema_from_str(src).is_err(), "{src}");
+    }
+
+    // precision is kept in JSON by writing large values as strings
+    assert_eq!(Literal::Int(1 << 60).to_json(), "1152921504606846976");
+    assert_eq!(Literal::Int(42).to_json(), 42);
+    assert_eq!(Literal::Int(i64::MIN).to_json(), "-9223372036854775808");
+    assert_eq!(decimal("19.99").to_json(), "19.99");
+    let json = data_type("decimal<10, 2>?").to_json();
+    assert_eq!(json["precision"], 10);
+    assert_eq!(json["scale"], 2);
+
+    let sql = to_sql(&parse_valid_schema_from_str(
+        "table T (id: int64<0, >, price: decimal<10, 2>);",
+    )?);
+    assert!(sql.contains("\"id\" BIGINT NOT NULL CHECK (\"id\" >= 0)"));
+    assert!(sql.contains("\"price\" DECIMAL(10, 2) NOT NULL"));
+
+    Ok(())
+}
+
+#[test]
+fn test_numeric_literals() -> anyhow::Result<()> {
+    let num = |lit: Literal| Token::Literal(lit);
+    assert_eq!(
+        setup_lexer()
+            .lex("1e6 1.5E-3 .5 -.25 0xFF -0x1_0 1_000_000 2_5.0_5")?,
+        vec![
+            num(Literal::Dbl(1e6)),
+            num(Literal::Dbl(1.5e-3)),
+            num(Literal::Dbl(0.5)),
+            Token::Minus,
+            num(Literal::Dbl(0.25)),
+            num(Literal::Int(255)),
+            Token::Minus,
+            num(Literal::Int(16)),
+            num(Literal::Int(1_000_000)),
+            num(Literal::Dbl(25.05)),
+        ]
+    );
+    for malformed in ["1e999", "0x1_0000_0000_0000_0000", "1__0", "1_"] {
+        assert!(setup_lexer().lex(malformed).is_err(), "{malformed}");
+    }
+
+    // `-` is always an operator, negating the number after it
+    let expr = |s: &str| -> anyhow::Result<String> {
+        Ok(lex(s)?
+            .parse_with_mut(Expr::parse, &mut HashMap::new())?
+            .to_string())
+    };
+    assert_eq!(expr("a-0")?, "a - 0");
+    assert_eq!(expr("a-9223372036854775808")?, "a - 9223372036854775808");
+    assert_eq!(expr("-9223372036854775808")?, "-9223372036854775808");
+    assert_eq!(expr("-0x8000_0000_0000_0000")?, "-9223372036854775808");
+    assert_eq!(data_type("int<-5, -1>").to_string(), "int<-5, -1>");
+    parse_valid_schema_from_str("table T (a: int, total: int = a-0);")?;
+    assert_eq!(
+        lex("x-0 > 1")?
+            .parse_with_mut(Cond::parse, &mut HashMap::new())?
+            .to_string(),
+        "x - 0 > 1"
+    );
+
+    // `(` and `)` exclude a bound, `[` and `]` include it
+    let dtype = data_type("dbl(0, 1]");
+    assert_eq!(dtype.to_string(), "dbl(0, 1]");
+    assert!(dtype.check_literal(Some(&Literal::Dbl(1.0))).is_ok());
+    assert_eq!(
+        dtype
+            .check_literal(Some(&Literal::Dbl(0.0)))
+            .unwrap_err()
+            .get_kind(),
+        ViolationKind::TooSmall
+    );
+    assert_eq!(
+        dtype
+            .check_literal(Some(&Literal::Dbl(f64::NAN)))
+            .unwrap_err()
+            .get_kind(),
+        ViolationKind::WrongType
+    );
+    let json = dtype.to_json();
+    assert_eq!(json["min_exclusive"], true);
+    assert_eq!(json["max_exclusive"], false);
+
+    let dtype = data_type("int[0, 10)");
+    assert!(dtype.check_literal(Some(&Literal::Int(9))).is_ok());
+    assert_eq!(
+        dtype
+            .check_literal(Some(&Literal::Int(10)))
+            .unwrap_err()
+            .get_message(),
+        "Value must be below 10 (entered 10)"
+    );
+    assert_maps_to_stmt("type t int[0, 10)?;")?;
+    assert_maps_to_stmt("type t dbl<0.5, 1000000>;")?;
+    for src in ["table T (a: dbl(1, 1]);", "table T (a: int(0, 1));"] {
+        assert!(parse_valid_schema_from_str(src).is_err(), "{src}");
+    }
+
+    // exclusive bounds narrow refinements and intersections
+    assert!(
+        parse_valid_schema_from_str("type p dbl(0, >; type q p<1e-3, 1e3>;")
+            .is_ok()
+    );
+    assert!(
+        parse_valid_schema_from_str("type p dbl(0, >; type q p<0, >;").is_err()
+    );
+    assert!(
+        parse_valid_schema_from_str("type p int(0, >; type q p<1, >;").is_ok()
+    );
+    assert!(data_type("dbl(0, 1)").is_assignable_to(&*data_type("dbl<0, 1>")));
+    assert!(!data_type("dbl<0, 1>").is_assignable_to(&*data_type("dbl(0, 1)")));
+    assert_eq!(
+        data_type("dbl<0, 1>")
+            .intersect(&*data_type("dbl(0, 2>"))?
+            .to_string(),
+        "dbl(0, 1]"
+    );
+    assert_eq!(
+        data_type("int")
+            .intersect(&*data_type("dbl(0.5, 3)"))?
+            .to_string(),
+        "int<1, 2>"
+    );
+
+    let schema = parse_valid_schema_from_str("table T (p: dbl(0, 1]);")?;
+    assert!(to_sql(&schema).contains("CHECK (\"p\" > 0 AND \"p\" <= 1)"));
+    let json = to_json_schema(&schema);
+    assert_eq!(
+        json["$defs"]["T"]["properties"]["p"]["exclusiveMinimum"],
+        0.0
+    );
+    assert_eq!(json["$defs"]["T"]["properties"]["p"]["maximum"], 1.0);
+
+    Ok(())
+}
+
+#[test]
+fn test_computed_columns() -> anyhow::Result<()> {
+    assert_maps_to_stmt(
+        "table Orders (price: dbl<, >, quantity: int<, >, total: dbl<, > = price * quantity);",
+    )?;
+    assert_maps_to_stmt(
+        "table People (first: str<, >, last: str<, >, fullName: str<, > = first + \" \" + last);",
+    )?;
+    // brackets are only kept where they're needed
+    assert_maps_to_stmt(
+        "table T (a: int<, >, b: int<, >, c: dbl<, > = (a - (b - 1)) * -a / 2.0);",
+    )?;
+    let schema = parse_schema_from_str("table T (a: int, b: int = a-1*a);")?;
+    assert_eq!(
+        schema
+            .get_table(0)
+            .unwrap()
+            .get_column(1)
+            .unwrap()
+            .to_string(),
+        "b: int<, > = a - 1 * a"
+    );
+
+    let error =
+        |s: &str| parse_valid_schema_from_str(s).err().unwrap().to_string();
+    assert_eq!(
+        error("table T (a: int = b + 1);"),
+        "Computed column a refers to unknown column b!"
+    );
+    assert_eq!(
+        error("table U (x: int); table T (a: int = U.x);"),
+        "Computed column a can't refer to U.x, as formulas can only use \
+        columns of their own table!"
+    );
+    assert_eq!(
+        error("table T (a: int = c, b: int = a, c: int = b + 1);"),
+        "Computed columns refer to each other in a cycle: a -> c -> b -> a!"
+    );
+    assert_eq!(
+        error("table T (a: str, b: int = a * 2);"),
+        "In computed column b: Can't apply * to str and int!"
+    );
+    assert_eq!(
+        error("table T (a: int, b: int = a / 2);"),
+        "Computed column b is dbl, which can't be stored as int<, >!"
+    );
+    assert_eq!(
+        error("table T (a: int?, b: int = a + 1);"),
+        "Computed column b is int?, which can't be stored as int<, >!"
+    );
+    assert!(
+        parse_valid_schema_from_str(
+            "table T (price: decimal<10, 2>, taxed: decimal = price * 1.2);"
+        )
+        .is_ok()
+    );
+
+    // computed cells are evaluated when rows are written
+    let schema = parse_valid_schema_from_str(
+        "table Orders (price: dbl, quantity: int<1, >, total: dbl<, 100> = price * quantity, half: dbl = total / 2);",
+    )?;
+    let table = schema.get_table(0).unwrap();
+    let data = TableData::with_rows(
+        table.clone(),
+        vec![vec![Some(Literal::Dbl(2.5)), Some(Literal::Int(4))]],
+    )?;
+    assert_eq!(
+        data.get_rows()[0],
+        vec![
+            Some(Literal::Dbl(2.5)),
+            Some(Literal::Int(4)),
+            Some(Literal::Dbl(10.0)),
+            Some(Literal::Dbl(5.0)),
+        ]
+    );
+    let violations =
+        table.validate_row(&[Some(Literal::Dbl(30.0)), Some(Literal::Int(4))]);
+    assert_eq!(violations.len(), 1);
+    assert_eq!(violations[0].get_kind(), ViolationKind::TooLarge);
+    assert_eq!(violations[0].get_column_name().as_deref(), Some("total"));
+
+    let schema = parse_valid_schema_from_str(
+        "table T (a: int, b: int, c: dbl = a / b);",
+    )?;
+    let violations = schema
+        .get_table(0)
+        .unwrap()
+        .validate_row(&[Some(Literal::Int(1)), Some(Literal::Int(0))]);
+    assert_eq!(violations[0].get_kind(), ViolationKind::ComputeError);
+    assert_eq!(violations[0].get_message(), "Division by zero in 1 / 0!");
+
+    // renaming a column updates the formulas that use it, dropping it fails
+    let mut symtable = HashMap::new();
+    let mut tq = lex(
+        "table Orders (price: dbl, quantity: int, total: dbl = price * quantity);
+        alter table Orders rename column price to unitPrice;",
+    )?;
+    tq.parse_with_mut(Stmt::parse, &mut symtable)?;
+    tq.consume_eq(Token::Semicolon)?;
+    tq.parse_with_mut(Stmt::parse, &mut symtable)?;
+    let Some(Symbol::TableSchema(table)) = symtable.get("Orders") else {
+        panic!("Orders should be defined");
+    };
+    assert_eq!(
+        table.to_string(),
+        "Orders (unitPrice: dbl<, >, quantity: int<, >, total: dbl<, > = unitPrice * quantity)"
+    );
+    assert_eq!(
+        error(
+            "table T (a: int, b: int = a + 1);
+            alter table T drop column a;"
+        ),
+        "Computed column b refers to unknown column a!"
+    );
+
+    // migrations recompute the column from existing rows
+    let old = parse_valid_schema_from_str("table T (a: int, b: int);")?;
+    let new = parse_valid_schema_from_str(
+        "table T (a: int, b: int, sum: int64 = a + b);",
+    )?;
+    let mut data = dataset(
+        &old,
+        vec![(
+            "T",
+            vec![vec![Some(Literal::Int(1)), Some(Literal::Int(2))]],
+        )],
+    )?;
+    Migration::between(&old, &new).apply(&mut data)?;
+    assert_eq!(data["T"].get_rows()[0][2], Some(Literal::Int(3)));
+
+    // as they do when the formula changes
+    let changed = parse_valid_schema_from_str(
+        "table T (a: int, b: int, sum: int64 = a - b);",
+    )?;
+    assert_eq!(
+        diff_schemas(&new, &changed).to_string(),
+        "~ table T\n    ~ column sum: formula a + b -> a - b"
+    );
+    Migration::between(&new, &changed).apply(&mut data)?;
+    assert_eq!(data["T"].get_rows()[0][2], Some(Literal::Int(-1)));
+
+    assert!(to_sql(&new).contains(
+        "\"sum\" BIGINT NOT NULL GENERATED ALWAYS AS (\"a\" + \"b\") STORED"
+    ));
+    assert_eq!(
+        to_json_schema(&new)["$defs"]["T"]["properties"]["sum"]["readOnly"],
+        true
+    );
+    assert!(to_ts(&new).contains("readonly sum: number | string;"));
+
+    Ok(())
+}
+
+#[test]
+fn test_recalculation() -> anyhow::Result<()> {
+    let schema = parse_valid_schema_from_str(
+        "table Orders (price: dbl, quantity: int, discount: dbl = 0.0, subtotal: dbl = price * quantity, total: dbl<, 100> = subtotal - discount, label: str = \"order\" + \"\");
+        table Other (a: int, b: int = a * 2);",
+    )?;
+    let graph = DependencyGraph::new(&schema)?;
+    let order: Vec<String> = graph
+        .get_order()
+        .iter()
+        .map(|col| col.to_string())
+        .collect();
+    assert_eq!(
+        order,
+        vec!["Orders.subtotal", "Orders.total", "Orders.label", "Other.b"]
+    );
+    let price = ColumnRef::new("Orders".into(), "price".into());
+    let affected: Vec<String> = graph
+        .get_affected(&[price])
+        .iter()
+        .map(|col| col.to_string())
+        .collect();
+    assert_eq!(affected, vec!["Orders.subtotal", "Orders.total"]);
+
+    let mut data = dataset(
+        &schema,
+        vec![(
+            "Orders",
+            vec![vec![
+                Some(Literal::Dbl(2.0)),
+                Some(Literal::Int(3)),
+                Some(Literal::Dbl(1.0)),
+            ]],
+        )],
+    )?;
+    let recalc = Recalculator::new(&schema)?;
+    let cell = |column: &str| CellRef::new("Orders".into(), 0, column.into());
+
+    // only the cells depending on the edit are recomputed
+    let result =
+        recalc.set_cell(&mut data, &cell("quantity"), Some(Literal::Int(4)))?;
+    assert_eq!(result.get_recomputed(), [cell("subtotal"), cell("total")]);
+    assert_eq!(
+        result.get_changed(),
+        [cell("quantity"), cell("subtotal"), cell("total")]
+    );
+    assert_eq!(data["Orders"].get_rows()[0][4], Some(Literal::Dbl(7.0)));
+
+    let result = recalc.set_cell(
+        &mut data,
+        &cell("discount"),
+        Some(Literal::Dbl(2.0)),
+    )?;
+    assert_eq!(result.get_recomputed(), [cell("total")]);
+
+    // edits that don't fit leave the row unchanged
+    let error = recalc
+        .set_cell(&mut data, &cell("price"), Some(Literal::Dbl(30.0)))
+        .err()
+        .unwrap();
+    assert_eq!(
+        error.to_string(),
+        "In column total: Maximum value 100 (entered 118)"
+    );
+    assert_eq!(data["Orders"].get_rows()[0][0], Some(Literal::Dbl(2.0)));
+    assert!(
+        recalc
+            .set_cell(&mut data, &cell("total"), Some(Literal::Dbl(1.0)))
+            .is_err()
+    );
+
+    // setting a cell to its current value recomputes nothing
+    let result =
+        recalc.set_cell(&mut data, &cell("price"), Some(Literal::Dbl(2.0)))?;
+    assert!(result.get_changed().is_empty());
+
+    // cycles are reported with the columns involved
+    let cyclic =
+        parse_schema_from_str("table T (a: int, b: int = c + a, c: int = b);")?;
+    assert_eq!(
+        DependencyGraph::new(&cyclic).err().unwrap().to_string(),
+        "Computed columns refer to each other in a cycle: T.b -> T.c -> T.b!"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cell_addressing() -> anyhow::Result<()> {
+    assert_eq!(column_letters(0), "A");
+    assert_eq!(column_letters(25), "Z");
+    assert_eq!(column_letters(26), "AA");
+    assert_eq!(column_letters(702), "AAA");
+    for idx in [0, 25, 26, 701, 702, 18277] {
+        assert_eq!(parse_column_letters(&column_letters(idx)), Some(idx));
+    }
+    assert_eq!(parse_column_letters("b"), Some(1));
+    assert_eq!(parse_column_letters("B2"), None);
+
+    let cell = CellAddress::parse("B2")?;
+    assert_eq!(cell, CellAddress::new(1, 1));
+    assert_eq!(cell.to_string(), "B2");
+    assert!(CellAddress::parse("B0").is_err());
+    assert!(CellAddress::parse("2B").is_err());
+
+    // ranges are normalised to their top left and bottom right cells
+    let range = RangeAddress::parse("C3:A1")?;
+    assert_eq!(range.to_string(), "A1:C3");
+    assert_eq!((range.get_num_columns(), range.get_num_rows()), (3, 3));
+    assert!(range.contains(&CellAddress::new(2, 0)));
+    let cells: Vec<String> = RangeAddress::parse("A1:B2")?
+        .cells()
+        .map(|cell| cell.to_string())
+        .collect();
+    assert_eq!(cells, vec!["A1", "B1", "A2", "B2"]);
+    assert_eq!(RangeAddress::parse("D4")?.to_string(), "D4");
+
+    let schema = parse_valid_schema_from_str(
+        "table Users (userId: int, userName: str, bio: str?);",
+    )?;
+    let table = schema.get_table(0).unwrap();
+    assert_eq!(&*cell.get_column(table)?.get_name(), "userName");
+    assert_eq!(
+        RangeAddress::parse("B1:D2")?
+            .get_columns(table)
+            .err()
+            .unwrap()
+            .to_string(),
+        "Table Users has no column D!"
+    );
+
+    let data = TableData::with_rows(
+        table.clone(),
+        vec![
+            vec![Some(Literal::Int(1)), Some(Literal::Str("ann".into()))],
+            vec![Some(Literal::Int(2)), Some(Literal::Str("bob".into()))],
+        ],
+    )?;
+    assert_eq!(cell.get_value(&data)?, Some(&Literal::Str("bob".into())));
+    assert_eq!(CellAddress::parse("C1")?.get_value(&data)?, None);
+    assert!(CellAddress::parse("A3")?.get_value(&data).is_err());
+
+    // structured references name the table and column
+    let reference = Reference::parse("Users[userName][2]")?;
+    assert_eq!(reference.to_string(), "Users[userName][2]");
+    assert_eq!(reference.cells(&data)?, vec![cell]);
+    let reference = Reference::parse("Users[userId]")?;
+    let cells: Vec<String> = reference
+        .cells(&data)?
+        .iter()
+        .map(|cell| cell.to_string())
+        .collect();
+    assert_eq!(cells, vec!["A1", "A2"]);
+    assert_eq!(
+        Reference::parse("Users[email]")?
+            .validate(table)
+            .err()
+            .unwrap()
+            .to_string(),
+        "Table Users has no column email!"
+    );
+    assert!(Reference::parse("Movies[userId]")?.validate(table).is_err());
+    assert!(Reference::parse("Users[userId][0]").is_err());
+    assert!(Reference::parse("Users[]").is_err());
+
+    Ok(())
+}
+
+// Parses the `;`-terminated statements of `src` into a symbol table.
+fn symtable_from_str(src: &str) -> anyhow::Result<SymbolTable> {
+    let mut symtable = HashMap::new();
+    let mut tq = lex(src)?;
+    while !tq.is_consumed() {
+        tq.parse_with_mut(Stmt::parse, &mut symtable)?;
+        tq.consume_eq(Token::Semicolon)?;
+    }
+    Ok(symtable)
+}
+
+fn run_query(
+    symtable: &mut SymbolTable,
+    data: &Dataset,
+    src: &str,
+) -> anyhow::Result<ResultSet> {
+    let Stmt::Select(select) =
+        lex(src)?.parse_with_mut(Stmt::parse, symtable)?
+    else {
+        panic!("Expected a query");
+    };
+    select.execute(symtable, data)
+}
+
+fn int_column(result: &ResultSet, idx: usize) -> Vec<Option<i64>> {
+    result
+        .get_rows()
+        .iter()
+        .map(|row| row[idx].as_ref().and_then(|val| val.get_i64()))
+        .collect()
+}
+
+const USERS_SRC: &str = "table Users (userId: int<0, >, userName: str<2, 32>, bio: str?, rating: dbl?);";
+
+fn users_dataset() -> anyhow::Result<Dataset> {
+    let user = |id: i64, name: &str, bio: Option<&str>, rating: Option<f64>| {
+        vec![
+            Some(Literal::Int(id)),
+            Some(Literal::Str(name.into())),
+            bio.map(|bio| Literal::Str(bio.into())),
+            rating.map(Literal::Dbl),
+        ]
+    };
+    dataset(
+        &parse_valid_schema_from_str(USERS_SRC)?,
+        vec![(
+            "Users",
+            vec![
+                user(1, "ann", None, Some(4.5)),
+                user(2, "bob", Some("hi"), None),
+                user(3, "cat", None, Some(3.0)),
+                user(4, "dan", Some("yo"), Some(4.5)),
+            ],
+        )],
+    )
+}
+
+#[test]
+fn test_select() -> anyhow::Result<()> {
+    let mut symtable = symtable_from_str(USERS_SRC)?;
+    let data = users_dataset()?;
+
+    for stmt in [
+        "select * from Users;",
+        "select userId, userName as name from Users where rating >= 4.0 and not bio is null order by rating desc, userId limit 2;",
+        "select userId * 2 from Users where (userId = 1 or userId = 2) and userName != \"ann\";",
+        "select userId from Users where bio is not null or not (rating < 1 and rating > 0);",
+    ] {
+        let parsed = lex(stmt)?.parse_with_mut(Stmt::parse, &mut symtable)?;
+        assert_eq!(parsed.to_string(), stmt);
+    }
+
+    let result = run_query(
+        &mut symtable,
+        &data,
+        "select userName from Users where rating >= 4.0 order by userId desc;",
+    )?;
+    assert_eq!(
+        result.get_rows(),
+        [
+            vec![Some(Literal::Str("dan".into()))],
+            vec![Some(Literal::Str("ann".into()))]
+        ]
+    );
+
+    // nulls sort after every value, so first when descending
+    let result = run_query(
+        &mut symtable,
+        &data,
+        "select * from Users order by rating desc, userId limit 3;",
+    )?;
+    assert_eq!(int_column(&result, 0), [Some(2), Some(1), Some(4)]);
+    assert_eq!(result.get_columns().len(), 4);
+
+    // comparisons with null are neither true nor false
+    let result = run_query(
+        &mut symtable,
+        &data,
+        "select userId from Users where not rating > 4.0;",
+    )?;
+    assert_eq!(int_column(&result, 0), [Some(3)]);
+
+    let result = run_query(
+        &mut symtable,
+        &data,
+        "select userId, userId * 2 as twice from Users where (userId + 1) * 2 > 6;",
+    )?;
+    assert_eq!(int_column(&result, 1), [Some(6), Some(8)]);
+    let types: Vec<String> = result
+        .get_columns()
+        .iter()
+        .map(|col| format!("{}: {}", col.get_name(), col.get_type()))
+        .collect();
+    assert_eq!(types, ["userId: int<0, >", "twice: int64<, >"]);
+    assert_eq!(result.to_json()["columns"][1]["name"], "twice");
+    assert_eq!(result.to_json()["rows"][0][1], 6);
+
+    // the result's columns can be sorted by
+    let result = run_query(
+        &mut symtable,
+        &data,
+        "select userName as name from Users order by name desc limit 1;",
+    )?;
+    assert_eq!(result.get_rows()[0][0], Some(Literal::Str("dan".into())));
+
+    let mut error = |src: &str| {
+        lex(src)
+            .unwrap()
+            .parse_with_mut(Stmt::parse, &mut symtable)
+            .err()
+            .unwrap()
+            .to_string()
+    };
+    assert_eq!(error("select email from Users;"), "Unknown column email!");
+    assert_eq!(error("select * from Movies;"), "Unknown table Movies!");
+    assert_eq!(
+        error("select * from Users where userName > 1;"),
+        "Can't compare str and int with >!"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_contextual_keywords() -> anyhow::Result<()> {
+    // query keywords can name tables and columns, which then print bare
+    let src = "table order (group: int<, >, desc: str<, >?, view: dbl<, >);";
+    assert_maps_to_stmt(src)?;
+    let schema = parse_valid_schema_from_str(src)?;
+    let mut symtable = symtable_from_str(src)?;
+    let row = |group: i64, desc: Option<&str>, view: f64| {
+        vec![
+            Some(Literal::Int(group)),
+            desc.map(|desc| Literal::Str(desc.into())),
+            Some(Literal::Dbl(view)),
+        ]
+    };
+    let data = dataset(
+        &schema,
+        vec![(
+            "order",
+            vec![row(1, Some("a"), 2.0), row(2, None, 1.0), row(3, None, 3.0)],
+        )],
+    )?;
+
+    for stmt in [
+        "select group, desc as select from order where desc is null and view > 1.0 order by view desc limit 1;",
+        "update order set desc = null where group = 1;",
+        "alter table order rename column view to limit;",
+        "view from as select group from order;",
+    ] {
+        let parsed = lex(stmt)?.parse_with_mut(Stmt::parse, &mut symtable)?;
+        assert_eq!(parsed.to_string(), stmt);
+    }
+
+    let mut symtable = symtable_from_str(src)?;
+    let result = run_query(
+        &mut symtable,
+        &data,
+        "select group from order where desc is null order by view desc;",
+    )?;
+    assert_eq!(int_column(&result, 0), [Some(3), Some(2)]);
+
+    // `type` and `table` stay reserved
+    assert!(parse_schema_from_str("table type (a: int);").is_err());
+    assert_maps_to_stmt("table \"type\" (\"table\": int<, >);")?;
+
+    Ok(())
+}
+
+#[test]
+fn test_aggregates() -> anyhow::Result<()> {
+    let mut symtable = symtable_from_str(USERS_SRC)?;
+    let data = users_dataset()?;
+
+    for stmt in [
+        "select rating, count(*) as n from Users where userId > 0 group by rating having count(distinct bio) >= 1 and max(userId) < 10 order by n desc;",
+        "select count(userId), avg(rating) * 2 from Users;",
+    ] {
+        let parsed = lex(stmt)?.parse_with_mut(Stmt::parse, &mut symtable)?;
+        assert_eq!(parsed.to_string(), stmt);
+    }
+
+    // nulls are ignored, except by `count(*)`
+    let result = run_query(
+        &mut symtable,
+        &data,
+        "select count(*), count(rating), count(distinct rating), sum(userId), avg(userId), min(userName), max(rating) from Users;",
+    )?;
+    assert_eq!(
+        result.get_rows(),
+        [vec![
+            Some(Literal::Int(4)),
+            Some(Literal::Int(3)),
+            Some(Literal::Int(2)),
+            Some(Literal::Int(10)),
+            Some(Literal::Dbl(2.5)),
+            Some(Literal::Str("ann".into())),
+            Some(Literal::Dbl(4.5)),
+        ]]
+    );
+    let columns = result.get_columns();
+    assert_eq!(columns[0].get_name().as_ref(), "count(*)");
+    assert!(!columns[0].get_type().get_nullable());
+    assert!(columns[4].get_type().to_string().starts_with("dbl"));
+    assert!(columns[4].get_type().get_nullable());
+
+    // nulls form a group of their own
+    let result = run_query(
+        &mut symtable,
+        &data,
+        "select rating, count(*) as n from Users group by rating order by rating;",
+    )?;
+    assert_eq!(int_column(&result, 1), [Some(1), Some(2), Some(1)]);
+    assert_eq!(result.get_rows()[2][0], None);
+
+    let result = run_query(
+        &mut symtable,
+        &data,
+        "select rating from Users group by rating having count(*) > 1;",
+    )?;
+    assert_eq!(result.get_rows(), [vec![Some(Literal::Dbl(4.5))]]);
+
+    let result = run_query(
+        &mut symtable,
+        &data,
+        "select rating, avg(userId) from Users where rating is not null group by rating order by avg(userId) desc;",
+    )?;
+    assert_eq!(
+        result.get_rows(),
+        [
+            vec![Some(Literal::Dbl(3.0)), Some(Literal::Dbl(3.0))],
+            vec![Some(Literal::Dbl(4.5)), Some(Literal::Dbl(2.5))],
+        ]
+    );
+
+    // aggregating no rows still gives a row
+    let result = run_query(
+        &mut symtable,
+        &data,
+        "select count(*), sum(rating) from Users where userId > 10;",
+    )?;
+    assert_eq!(result.get_rows(), [vec![Some(Literal::Int(0)), None]]);
+
+    let mut error = |src: &str| {
+        lex(src)
+            .unwrap()
+            .parse_with_mut(Stmt::parse, &mut symtable)
+            .err()
+            .unwrap()
+            .to_string()
+    };
+    assert_eq!(
+        error("select userName, count(*) from Users group by rating;"),
+        "Column userName must be grouped by or aggregated!"
+    );
+    assert_eq!(
+        error("select * from Users group by rating;"),
+        "Can't select * from a query that aggregates!"
+    );
+    assert_eq!(
+        error("select userId from Users where count(*) > 1;"),
+        "Aggregate functions can't be used in where!"
+    );
+    assert_eq!(
+        error("select sum(userName) from Users;"),
+        "Can't apply sum to a str!"
+    );
+    assert_eq!(
+        error("select avg(sum(userId)) from Users;"),
+        "Aggregate functions can't be nested!"
+    );
+    assert_eq!(
+        error("select foo(userId) from Users;"),
+        "Unknown function foo!"
+    );
+    assert_eq!(
+        error("update Users set rating = max(rating);"),
+        "Aggregate max(rating) can't be used when changing rows!"
+    );
+    assert_eq!(
+        parse_valid_schema_from_str("table T (a: int, b: int = sum(a));")
+            .err()
+            .unwrap()
+            .to_string(),
+        "Computed column b can't use aggregate functions!"
+    );
+
+    Ok(())
+}
+
+const MOVIES_SRC: &str = "table Users (userId: int<0, >, userName: str<2, 32>, bio: str?);
+    table Movies (movieId: int<0, >, movieName: str, yearReleased: int<0, >);
+    table MovieRatings (movieId: int<0, >, userId: int<0, >, rating: dbl<0, 5>, review: str?);";
+
+fn movies_dataset() -> anyhow::Result<Dataset> {
+    let int = |val: i64| Some(Literal::Int(val));
+    let str = |val: &str| Some(Literal::Str(val.into()));
+    dataset(
+        &parse_valid_schema_from_str(MOVIES_SRC)?,
+        vec![
+            (
+                "Users",
+                vec![
+                    vec![int(1), str("ann"), None],
+                    vec![int(2), str("bob"), None],
+                ],
+            ),
+            (
+                "Movies",
+                vec![
+                    vec![int(1), str("Heat"), int(1995)],
+                    vec![int(2), str("Up"), int(2009)],
+                    vec![int(3), str("Jaws"), int(1975)],
+                ],
+            ),
+            (
+                "MovieRatings",
+                vec![
+                    vec![int(1), int(1), Some(Literal::Dbl(4.0)), None],
+                    vec![int(1), int(2), Some(Literal::Dbl(5.0)), None],
+                    vec![int(2), int(1), Some(Literal::Dbl(3.0)), None],
+                ],
+            ),
+        ],
+    )
+}
+
+#[test]
+fn test_joins() -> anyhow::Result<()> {
+    let mut symtable = symtable_from_str(MOVIES_SRC)?;
+    let data = movies_dataset()?;
+
+    let stmt = "select Movies.movieName, rating from Movies left join MovieRatings on MovieRatings.movieId = Movies.movieId join Users on Users.userId = MovieRatings.userId where yearReleased > 1990;";
+    let parsed = lex(stmt)?.parse_with_mut(Stmt::parse, &mut symtable)?;
+    assert_eq!(parsed.to_string(), stmt);
+
+    let result = run_query(
+        &mut symtable,
+        &data,
+        "select Movies.movieName, rating from MovieRatings join Movies on Movies.movieId = MovieRatings.movieId order by rating desc;",
+    )?;
+    assert_eq!(result.get_columns()[0].get_name().as_ref(), "movieName");
+    assert_eq!(
+        result.get_rows(),
+        [
+            vec![Some(Literal::Str("Heat".into())), Some(Literal::Dbl(5.0))],
+            vec![Some(Literal::Str("Heat".into())), Some(Literal::Dbl(4.0))],
+            vec![Some(Literal::Str("Up".into())), Some(Literal::Dbl(3.0))],
+        ]
+    );
+
+    // a left join keeps rows without a match, with nulls for the joined
+    // table's columns
+    let result = run_query(
+        &mut symtable,
+        &data,
+        "select movieName, rating from Movies left join MovieRatings on MovieRatings.movieId = Movies.movieId where rating is null;",
+    )?;
+    assert_eq!(
+        result.get_rows(),
+        [vec![Some(Literal::Str("Jaws".into())), None]]
+    );
+    assert!(result.get_columns()[1].get_type().get_nullable());
+    assert_eq!(result.get_columns()[1].get_type().to_string(), "dbl<0, 5>?");
+
+    // conditions other than equality are checked after rows are matched
+    let result = run_query(
+        &mut symtable,
+        &data,
+        "select userName from MovieRatings join Users on Users.userId = MovieRatings.userId and rating < 4.5 order by userName;",
+    )?;
+    assert_eq!(
+        result.get_rows(),
+        [
+            vec![Some(Literal::Str("ann".into()))],
+            vec![Some(Literal::Str("ann".into()))],
+        ]
+    );
+
+    let result = run_query(
+        &mut symtable,
+        &data,
+        "select movieName, avg(rating) as avgRating, count(distinct Users.userId) from Movies join MovieRatings on Movies.movieId = MovieRatings.movieId join Users on Users.userId = MovieRatings.userId group by movieName order by avgRating desc;",
+    )?;
+    assert_eq!(
+        result.get_rows(),
+        [
+            vec![
+                Some(Literal::Str("Heat".into())),
+                Some(Literal::Dbl(4.5)),
+                Some(Literal::Int(2)),
+            ],
+            vec![
+                Some(Literal::Str("Up".into())),
+                Some(Literal::Dbl(3.0)),
+                Some(Literal::Int(1)),
+            ],
+        ]
+    );
+
+    let mut error = |src: &str| {
+        lex(src)
+            .unwrap()
+            .parse_with_mut(Stmt::parse, &mut symtable)
+            .err()
+            .unwrap()
+            .to_string()
+    };
+    assert_eq!(
+        error(
+            "select movieId from Movies join MovieRatings on Movies.movieId = MovieRatings.movieId;"
+        ),
+        "Column movieId is ambiguous, as it's in tables Movies and MovieRatings!"
+    );
+    assert_eq!(
+        error("select Movies.rating from Movies;"),
+        "Unknown column Movies.rating!"
+    );
+    assert_eq!(
+        error("select * from Movies join Movies on movieId = 1;"),
+        "Table Movies is already in the query!"
+    );
+    assert_eq!(
+        error("select * from Movies join Users on userName = 1;"),
+        "Can't compare str and int with =!"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_views() -> anyhow::Result<()> {
+    let view_src = "view TopMovies as select Movies.movieId, movieName, avg(rating) as avgRating from Movies join MovieRatings on MovieRatings.movieId = Movies.movieId group by Movies.movieId, movieName having avg(rating) >= 4;";
+    let src = format!("{MOVIES_SRC}\n{view_src}");
+    let mut symtable = symtable_from_str(&src)?;
+    let data = movies_dataset()?;
+
+    let Some(Symbol::View(view)) = symtable.get("TopMovies") else {
+        panic!("Expected a view");
+    };
+    assert_eq!(format!("view {view};"), view_src);
+    assert_eq!(
+        view.get_table_schema().to_string(),
+        "TopMovies (movieId: int<0, >, movieName: str<, >, avgRating: dbl<, >?)"
+    );
+
+    let schema = parse_valid_schema_from_str(&src)?;
+    assert_eq!(schema.get_num_tables(), 3);
+    assert!(schema.get_view_by_name("TopMovies").is_some());
+    assert!(schema.to_string().ends_with(&format!("{view_src}\n")));
+    let json = schema.to_json();
+    assert_eq!(json["views"][0]["view_name"], "TopMovies");
+    assert_eq!(json["views"][0]["columns"][2]["column_name"], "avgRating");
+
+    // views are queried like tables, and can be joined to them
+    let result = run_query(
+        &mut symtable,
+        &data,
+        "select movieName, avgRating from TopMovies;",
+    )?;
+    assert_eq!(
+        result.get_rows(),
+        [vec![
+            Some(Literal::Str("Heat".into())),
+            Some(Literal::Dbl(4.5))
+        ]]
+    );
+    let result = run_query(
+        &mut symtable,
+        &data,
+        "select userName from TopMovies join MovieRatings on MovieRatings.movieId = TopMovies.movieId join Users on Users.userId = MovieRatings.userId where rating < avgRating;",
+    )?;
+    assert_eq!(result.get_rows(), [vec![Some(Literal::Str("ann".into()))]]);
+
+    let mut error = |src: &str| {
+        lex(src)
+            .unwrap()
+            .parse_with_mut(Stmt::parse, &mut symtable)
+            .err()
+            .unwrap()
+            .to_string()
+    };
+    assert_eq!(
+        error("delete from TopMovies;"),
+        "View TopMovies is read-only!"
+    );
+    assert_eq!(
+        error("view TopMovies as select * from Users;"),
+        "Symbol TopMovies is already assigned!"
+    );
+    assert_eq!(
+        error(
+            "view Ratings as select * from Movies join MovieRatings on MovieRatings.movieId = Movies.movieId;"
+        ),
+        "View Ratings has more than one column movieId!"
+    );
+    assert_eq!(
+        error("view Names as select name from Users;"),
+        "Unknown column name!"
+    );
+
+    // tables a view selects from can't be altered under it
+    assert_eq!(
+        error("drop table Movies;"),
+        "Can't alter table Movies, as view TopMovies selects from it!"
+    );
+    assert_eq!(
+        error("alter table MovieRatings rename column rating to stars;"),
+        "Can't alter table MovieRatings, as view TopMovies selects from it!"
+    );
+    assert!(
+        lex("rename table Users to People;")?
+            .parse_with_mut(Stmt::parse, &mut symtable)
+            .is_ok()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_prepared_queries() -> anyhow::Result<()> {
+    let symtable = symtable_from_str(MOVIES_SRC)?;
+    let data = movies_dataset()?;
+
+    let src = "select movieName, rating from Movies join MovieRatings on MovieRatings.movieId = Movies.movieId where Movies.movieId = $1 and rating >= $2 order by rating";
+    let query = PreparedQuery::prepare(&format!("{src};"), &symtable)?;
+    assert_eq!(query.get_query().to_string(), src);
+    // compared for equality with a column, a parameter takes the column's
+    // type, else the unbounded type of what it's compared with
+    let types: Vec<String> = query
+        .get_params()
+        .iter()
+        .map(|param| format!("{}: {}", param.param, param.data_type))
+        .collect();
+    assert_eq!(types, ["$1: int<0, >", "$2: dbl<, >"]);
+    let result =
+        query.execute(&symtable, &data, &[Literal::Int(1), Literal::Int(5)])?;
+    assert_eq!(
+        result.get_rows(),
+        [vec![
+            Some(Literal::Str("Heat".into())),
+            Some(Literal::Dbl(5.0))
+        ]]
+    );
+    let execute_error = |args: &[Literal]| {
+        query
+            .execute(&symtable, &data, args)
+            .err()
+            .unwrap()
+            .to_string()
+    };
+    assert!(
+        execute_error(&[Literal::Int(-1), Literal::Dbl(4.0)])
+            .starts_with("In parameter $1: ")
+    );
+    assert!(
+        execute_error(&[Literal::Int(1), Literal::Str("4".into())])
+            .starts_with("In parameter $2: ")
+    );
+    assert_eq!(
+        execute_error(&[Literal::Int(1)]),
+        "Expected 2 arguments, got 1!"
+    );
+
+    // selected expressions keep their names once values are bound
+    let query = PreparedQuery::prepare(
+        "select yearReleased - $1 from Movies where movieId = 3;",
+        &symtable,
+    )?;
+    assert_eq!(
+        query.get_columns()[0].get_name().as_ref(),
+        "yearReleased - $1"
+    );
+    let result = query.execute(&symtable, &data, &[Literal::Int(1900)])?;
+    assert_eq!(
+        result.get_columns()[0].get_name().as_ref(),
+        "yearReleased - $1"
+    );
+    assert_eq!(result.get_rows(), [vec![Some(Literal::Int(75))]]);
+
+    let query = PreparedQuery::prepare(
+        "select movieName from Movies where yearReleased > :year and movieName != :name order by movieName;",
+        &symtable,
+    )?;
+    let types: Vec<String> = query
+        .get_params()
+        .iter()
+        .map(|param| format!("{}: {}", param.param, param.data_type))
+        .collect();
+    assert_eq!(types, [":year: int64<, >", ":name: str<, >"]);
+    let mut args = HashMap::from([
+        ("year", Literal::Int(1980)),
+        ("name", Literal::Str("Up".into())),
+    ]);
+    let result = query.execute_named(&symtable, &data, &args)?;
+    assert_eq!(result.get_rows(), [vec![Some(Literal::Str("Heat".into()))]]);
+    args.insert("genre", Literal::Str("drama".into()));
+    assert_eq!(
+        query
+            .execute_named(&symtable, &data, &args)
+            .err()
+            .unwrap()
+            .to_string(),
+        "Unknown parameter :genre!"
+    );
+    args.remove("genre");
+    args.remove("name");
+    assert_eq!(
+        query
+            .execute_named(&symtable, &data, &args)
+            .err()
+            .unwrap()
+            .to_string(),
+        "No value given for parameter :name!"
+    );
+
+    let error = |src: &str| {
+        PreparedQuery::prepare(src, &symtable)
+            .err()
+            .unwrap()
+            .to_string()
+    };
+    assert_eq!(
+        error("select * from Movies where movieId = $1 or movieId = :id;"),
+        "Can't mix positional and named parameters!"
+    );
+    assert_eq!(
+        error("select * from Movies where movieId = $2;"),
+        "Parameter $1 isn't used!"
+    );
+    assert_eq!(
+        error("select * from Movies where $1 = $2;"),
+        "Can't infer the type of parameter $1!"
+    );
+    assert_eq!(
+        error(
+            "select * from Movies where movieName = $1 and yearReleased = $1;"
+        ),
+        "Can't compare int and str with =!"
+    );
+    assert_eq!(
+        error("select * from Movies where name = $1;"),
+        "Unknown column name!"
+    );
+
+    // values can only be bound to prepared queries
+    assert_eq!(
+        lex("select * from Movies where movieId = $1;")?
+            .parse_with_mut(Stmt::parse, &mut symtable.clone())
+            .err()
+            .unwrap()
+            .to_string(),
+        "Parameter $1 can only be used in a prepared query!"
+    );
+
+    Ok(())
+}
+
+// Runs an insert, update or delete statement, returning how many rows it
+// affected.
+fn run_dml(
+    symtable: &mut SymbolTable,
+    data: &mut Dataset,
+    src: &str,
+) -> anyhow::Result<usize> {
+    match lex(src)?.parse_with_mut(Stmt::parse, symtable)? {
+        Stmt::Insert(insert) => insert.execute(symtable, data),
+        Stmt::Update(update) => update.execute(symtable, data),
+        Stmt::Delete(delete) => delete.execute(symtable, data),
+        _ => panic!("Expected an insert, update or delete"),
+    }
+}
+
+#[test]
+fn test_dml() -> anyhow::Result<()> {
+    let mut symtable = symtable_from_str(USERS_SRC)?;
+    let mut data = users_dataset()?;
+
+    for stmt in [
+        "insert into Users (userId, userName) values (5, \"eve\"), (6, \"fay\");",
+        "insert into Users values (7, \"gus\", null, 2 + 2);",
+        "update Users set bio = null, rating = rating + 1 where userId = 1;",
+        "delete from Users where rating is null;",
+        "delete from Users;",
+    ] {
+        let parsed = lex(stmt)?.parse_with_mut(Stmt::parse, &mut symtable)?;
+        assert_eq!(parsed.to_string(), stmt);
+    }
+
+    // only the REPL runs queries and data statements
+    for stmt in ["select * from Users;", "delete from Users;"] {
+        let err = parse_schema_from_str(&format!("{USERS_SRC} {stmt}"))
+            .err()
+            .unwrap();
+        assert!(
+            format!("{err:#}")
+                .contains("Queries and data statements aren't allowed")
+        );
+    }
+
+    let ids = |data: &Dataset| {
+        data["Users"]
+            .get_rows()
+            .iter()
+            .map(|row| row[0].as_ref().and_then(|val| val.get_i64()))
+            .collect::<Vec<_>>()
+    };
+
+    // columns without a value are null, and integers are stored as doubles
+    // in double columns
+    let count = run_dml(
+        &mut symtable,
+        &mut data,
+        "insert into Users (userName, userId) values (\"eve\", 5), (\"fay\", 6);",
+    )?;
+    assert_eq!(count, 2);
+    assert_eq!(data["Users"].get_rows()[4][2], None);
+    run_dml(
+        &mut symtable,
+        &mut data,
+        "insert into Users values (7, \"gus\", null, 4);",
+    )?;
+    assert_eq!(data["Users"].get_rows()[6][3], Some(Literal::Dbl(4.0)));
+
+    // values are computed from the row before the update
+    let count = run_dml(
+        &mut symtable,
+        &mut data,
+        "update Users set rating = rating + 0.5, bio = userName where rating >= 4.0;",
+    )?;
+    assert_eq!(count, 3);
+    assert_eq!(data["Users"].get_rows()[0][3], Some(Literal::Dbl(5.0)));
+    assert_eq!(
+        data["Users"].get_rows()[0][2],
+        Some(Literal::Str("ann".into()))
+    );
+
+    // nothing is written if any row is invalid
+    let before = ids(&data);
+    let error = run_dml(
+        &mut symtable,
+        &mut data,
+        "insert into Users (userId, userName) values (8, \"hal\"), (9, \"x\");",
+    );
+    assert!(error.is_err());
+    assert_eq!(ids(&data), before);
+    let error = run_dml(
+        &mut symtable,
+        &mut data,
+        "update Users set userId = userId - 2;",
+    );
+    assert!(error.is_err());
+    assert_eq!(ids(&data), before);
+    let error = run_dml(
+        &mut symtable,
+        &mut data,
+        "insert into Users (userId) values (8);",
+    );
+    assert!(error.is_err());
+
+    let count = run_dml(
+        &mut symtable,
+        &mut data,
+        "delete from Users where rating is null or userId = 3;",
+    )?;
+    assert_eq!(count, 4);
+    assert_eq!(ids(&data), [Some(1), Some(4), Some(7)]);
+
+    // missing columns take their default, and computed columns are filled
+    let src = "table Posts (postId: int, title: str = \"untitled\", views: int<0, > = 0, score: int = views * 2);";
+    let mut symtable = symtable_from_str(src)?;
+    let mut data = dataset(&parse_valid_schema_from_str(src)?, vec![])?;
+    run_dml(
+        &mut symtable,
+        &mut data,
+        "insert into Posts (postId) values (1), (2);",
+    )?;
+    run_dml(
+        &mut symtable,
+        &mut data,
+        "update Posts set views = 21 where postId = 2;",
+    )?;
+    assert_eq!(
+        data["Posts"].get_rows(),
+        [
+            vec![
+                Some(Literal::Int(1)),
+                Some(Literal::Str("untitled".into())),
+                Some(Literal::Int(0)),
+                Some(Literal::Int(0)),
+            ],
+            vec![
+                Some(Literal::Int(2)),
+                Some(Literal::Str("untitled".into())),
+                Some(Literal::Int(21)),
+                Some(Literal::Int(42)),
+            ],
+        ]
+    );
+    assert_eq!(run_dml(&mut symtable, &mut data, "delete from Posts;")?, 2);
+    assert_eq!(data["Posts"].get_num_rows(), 0);
+
+    let mut error = |src: &str| {
+        lex(src)
+            .unwrap()
+            .parse_with_mut(Stmt::parse, &mut symtable)
+            .err()
+            .unwrap()
+            .to_string()
+    };
+    assert_eq!(
+        error("insert into Posts (score) values (1);"),
+        "Column score is computed, so can't be set!"
+    );
+    assert_eq!(
+        error("insert into Posts (postId, title) values (1);"),
+        "Expected 2 values, got 1!"
+    );
+    assert_eq!(
+        error("insert into Posts (postId) values (views);"),
+        "Unknown column views!"
+    );
+    assert!(
+        error("update Posts set title = 1;")
+            .starts_with("Can't store int in column title")
+    );
+    assert_eq!(
+        error("update Posts set likes = 1;"),
+        "Table Posts has no column likes!"
+    );
+    assert_eq!(
+        error("delete from Posts where title > 1;"),
+        "Can't compare str and int with >!"
+    );
+
+    Ok(())
+}
+
+fn import_error(path: &str, loader: &MemoryLoader) -> String {
+    match parse_schema_with_loader(path, loader) {
+        Ok(_) => panic!("Expected {path} to fail"),
+        Err(e) => format!("{e:#}"),
+    }
+}
+
+#[test]
+fn test_imports() -> anyhow::Result<()> {
+    let mut loader = MemoryLoader::new();
+    loader.add_file(
+        "common.ft",
+        "type uIntType int<0, >; type emailType str<3, 254>;",
+    );
+    loader.add_file("other.ft", "type uIntType int<1, >;");
+    loader.add_file(
+        "main.ft",
+        "import \"common.ft\"; table Users (userId: uIntType, email: emailType);",
+    );
+    loader.add_file(
+        "selective.ft",
+        "import { uIntType } from \"common.ft\"; table T (a: uIntType, b: emailType);",
+    );
+    loader.add_file(
+        "aliased.ft",
+        "import \"common.ft\"; import \"other.ft\" as other;
+        table T (a: uIntType, b: other.uIntType);",
+    );
+    loader.add_file(
+        "clash.ft",
+        "import \"common.ft\"; import \"other.ft\"; table T (a: uIntType);",
+    );
+    loader.add_file("redefined.ft", "import \"common.ft\"; type uIntType int;");
+    loader.add_file(
+        "diamond.ft",
+        "import \"common.ft\"; import { uIntType } from \"common.ft\";
+        table T (a: uIntType);",
+    );
+    loader.add_file("cycle_a.ft", "import \"cycle_b.ft\";");
+    loader.add_file("cycle_b.ft", "import \"cycle_a.ft\";");
+
+    let schema = parse_schema_with_loader("main.ft", &loader)?;
+    assert_eq!(
+        schema.to_string().trim(),
+        "table Users (userId: int<0, >, email: str<3, 254>);"
+    );
+
+    assert!(parse_schema_with_loader("selective.ft", &loader).is_err());
+
+    let schema = parse_schema_with_loader("aliased.ft", &loader)?;
+    assert_eq!(
+        schema.to_string().trim(),
+        "table T (a: int<0, >, b: int<1, >);"
+    );
+
+    assert!(
+        import_error("clash.ft", &loader)
+            .contains("already defined in common.ft")
+    );
+
+    assert!(
+        import_error("redefined.ft", &loader)
+            .contains("already defined in common.ft")
+    );
+
+    parse_schema_with_loader("diamond.ft", &loader)?;
+
+    assert!(
+        import_error("cycle_a.ft", &loader)
+            .contains("Import cycle: cycle_a.ft -> cycle_b.ft -> cycle_a.ft")
+    );
+
+    // imports can't be resolved without a loader
+    assert!(parse_schema_from_str("import \"common.ft\";").is_err());
+    assert_maps_to_stmt("import { a, b } from \"common.ft\";")?;
+    assert_maps_to_stmt("import \"common.ft\" as common;")?;
+
+    // the formatter resolves imports the same way
+    assert_eq!(
+        format_schema_with_loader("main.ft", &loader)?,
+        "import \"common.ft\";\ntable Users (userId: uIntType, email: emailType);\n"
+    );
+    assert!(
+        format_schema("import \"common.ft\"; table T (a: uIntType);").is_err()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_format() -> anyhow::Result<()> {
+    assert_eq!(
+        format_schema(
+            "// header\ntype  a int<0,>;   // trailing\n\n\n\ntable T(x: a,\n  \
+            y: int);\ntable U (z:int?);\n"
+        )?,
+        "// header\ntype a int<0, >; // trailing\n\ntable T(x: a,\n  y: \
+        int);\ntable U (z: int<, >?);\n"
+    );
+
+    // formatting keeps every statement and comment, and is idempotent
+    for path in [
+        "test_artifacts/valid_schemas/input/input_3.txt",
+        "test_artifacts/valid_schemas/input/input_5.txt",
+    ] {
+        let src = fs::read_to_string(path)?;
+        let formatted = format_schema(&src)?;
+        assert_eq!(format_schema(&formatted)?, formatted);
+        assert_eq!(
+            parse_valid_schema_from_str(&formatted)?.to_string(),
+            parse_valid_schema_from_str(&src)?.to_string()
+        );
+        for line in src.lines().filter(|line| line.starts_with("//")) {
+            assert!(formatted.contains(line));
+        }
+        let stmts = |s: &str| -> anyhow::Result<usize> {
+            let mut tq = TokenQueue::from(setup_lexer().lex(s)?);
+            let mut symtable = SymbolTable::new();
+            let mut count = 0;
+            while !tq.is_consumed() {
+                tq.parse_with_mut(Stmt::parse, &mut symtable)?;
+                tq.consume_eq(Token::Semicolon)?;
+                count += 1;
+            }
+            Ok(count)
+        };
+        assert_eq!(stmts(&formatted)?, stmts(&src)?);
+    }
+
+    let src =
+        fs::read_to_string("test_artifacts/valid_schemas/input/input_3.txt")?;
+    assert!(format_schema(&src)?.contains("userId: uIntType"));
+    let src =
+        fs::read_to_string("test_artifacts/valid_schemas/input/input_5.txt")?;
+    assert!(format_schema(&src)?.contains("rename table Users to People;"));
+
+    Ok(())
+}
+
+#[test]
+fn test_export() -> anyhow::Result<()> {
+    let schema = parse_valid_schema_from_str(
+        "table Users (userId: int<0, >, name: str<2, 32> = \"ann\", bio: str?);",
+    )?;
+
+    assert_eq!(
+        to_sql(&schema),
+        "CREATE TABLE \"Users\" (
+    \"userId\" INTEGER NOT NULL CHECK (\"userId\" >= 0),
+    \"name\" TEXT NOT NULL DEFAULT 'ann' CHECK (LENGTH(\"name\") >= 2 AND LENGTH(\"name\") <= 32),
+    \"bio\" TEXT
+);"
+    );
+
+    assert_eq!(
+        to_ts(&schema),
+        "export interface Users {
+    userId: number;
+    name: string;
+    bio: string | null;
+}"
+    );
+
+    let json_schema = to_json_schema(&schema);
+    let users = &json_schema["$defs"]["Users"];
+    assert_eq!(users["required"], serde_json::json!(["userId", "name"]));
+    assert_eq!(users["properties"]["userId"]["minimum"], 0);
+    assert_eq!(
+        users["properties"]["bio"]["type"],
+        serde_json::json!(["string", "null"])
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_repl() -> anyhow::Result<()> {
+    let mut repl = Repl::new();
+
+    assert_eq!(
+        repl.feed_line("type uIntType int<0, >;").unwrap()?,
+        "Defined type uIntType"
+    );
+
+    // multi-line input is buffered until ';'
+    assert!(repl.feed_line("table Users (").is_none());
+    assert!(repl.is_continuation());
+    assert!(repl.feed_line("    userId: uIntType, bio: str?").is_none());
+    assert_eq!(repl.feed_line(");").unwrap()?, "Defined table Users");
+
+    assert_eq!(repl.feed_line(".tables").unwrap()?, "Users");
+    assert_eq!(
+        repl.feed_line(".describe Users").unwrap()?,
+        "table Users (userId: int<0, >, bio: str<, >?);"
+    );
+    assert!(
+        repl.feed_line(".json Users")
+            .unwrap()?
+            .contains("\"userId\"")
+    );
+
+    // duplicate definitions are rejected without clobbering the original
+    assert!(repl.feed_line("table Users (a: int);").unwrap().is_err());
+    assert_eq!(
+        repl.feed_line(".describe Users").unwrap()?,
+        "table Users (userId: int<0, >, bio: str<, >?);"
+    );
+
+    repl.feed_line(".drop Users").unwrap()?;
+    assert_eq!(repl.feed_line(".tables").unwrap()?, "");
+    assert!(repl.feed_line(".describe Users").unwrap().is_err());
+    assert!(repl.feed_line(".bogus").unwrap().is_err());
+
+    Ok(())
+}
+
+fn lsp_request(id: u64, method: &str, params: serde_json::Value) -> String {
+    let body = serde_json::json!({
+        "jsonrpc": "2.0", "id": id, "method": method, "params": params
+    })
+    .to_string();
+    format!("Content-Length: {}\r\n\r\n{body}", body.len())
+}
+
+fn lsp_notification(method: &str, params: serde_json::Value) -> String {
+    let body = serde_json::json!({
+        "jsonrpc": "2.0", "method": method, "params": params
+    })
+    .to_string();
+    format!("Content-Length: {}\r\n\r\n{body}", body.len())
+}
+
+#[test]
+fn test_lsp() -> anyhow::Result<()> {
+    use serde_json::json;
+
+    let uri = "file:///schema.ft";
+    let text = "type uIntType int<0, >;\ntable Users (userId: uIntType, bio: str?);\ntable T (a: int<3, 2>);";
+    let doc = json!({"textDocument": {"uri": uri}});
+    let at = |line: u32, character: u32| {
+        json!({
+            "textDocument": {"uri": uri},
+            "position": {"line": line, "character": character}
+        })
+    };
+
+    // script a client session over an in-memory stream
+    let mut input = String::new();
+    input.push_str(&lsp_request(1, "initialize", json!({})));
+    input.push_str(&lsp_notification(
+        "textDocument/didOpen",
+        json!({"textDocument": {"uri": uri, "languageId": "ftables", "version": 1, "text": text}}),
+    ));
+    input.push_str(&lsp_request(2, "textDocument/definition", at(1, 23)));
+    input.push_str(&lsp_request(3, "textDocument/hover", at(1, 23)));
+    input.push_str(&lsp_request(4, "textDocument/completion", at(1, 21)));
+    input.push_str(&lsp_request(5, "textDocument/documentSymbol", doc));
+    let mut rename = at(0, 7);
+    rename["newName"] = json!("idType");
+    input.push_str(&lsp_request(6, "textDocument/rename", rename));
+    input.push_str(&lsp_request(7, "bogus/method", json!({})));
+    input.push_str(&lsp_request(8, "shutdown", json!(null)));
+    input.push_str(&lsp_notification("exit", json!(null)));
+
+    let mut reader = std::io::BufReader::new(input.as_bytes());
+    let mut output = vec![];
+    let mut server = crate::lsp::Server::new();
+    server.run(&mut reader, &mut output)?;
+    assert!(server.is_shutdown());
+
+    let mut reader = std::io::BufReader::new(output.as_slice());
+    let mut msgs = vec![];
+    while let Some(msg) = crate::lsp::read_message(&mut reader)? {
+        msgs.push(msg);
+    }
+    let response = |id: u64| {
+        msgs.iter()
+            .find(|msg| msg["id"] == id)
+            .unwrap_or_else(|| panic!("missing response {id}"))
+    };
+
+    assert_eq!(response(1)["result"]["capabilities"]["hoverProvider"], true);
+
+    // diagnostics point at the invalid table only
+    let diagnostics = &msgs
+        .iter()
+        .find(|msg| msg["method"] == "textDocument/publishDiagnostics")
+        .unwrap()["params"]["diagnostics"];
+    assert_eq!(diagnostics.as_array().unwrap().len(), 1);
+    assert_eq!(
+        diagnostics[0]["range"]["start"],
+        json!({"line": 2, "character": 0})
+    );
+
+    assert_eq!(
+        response(2)["result"]["range"],
+        json!({"start": {"line": 0, "character": 5}, "end": {"line": 0, "character": 13}})
+    );
+    assert_eq!(
+        response(3)["result"]["contents"]["value"],
+        "type uIntType int<0, >"
+    );
+
+    let labels: Vec<&str> = response(4)["result"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["label"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        labels,
+        vec!["int", "int64", "dbl", "decimal", "str", "uIntType"]
+    );
+
+    let symbols = response(5)["result"].as_array().unwrap();
+    assert_eq!(symbols.len(), 3);
+    assert_eq!(symbols[1]["name"], "Users");
+    assert_eq!(symbols[1]["children"][1]["name"], "bio");
+
+    let edits = response(6)["result"]["changes"][uri].as_array().unwrap();
+    assert_eq!(edits.len(), 2);
+    assert!(edits.iter().all(|edit| edit["newText"] == "idType"));
+
+    assert_eq!(response(7)["error"]["code"], -32601);
+    assert_eq!(response(8)["result"], json!(null));
+
+    // renaming a table rewrites the statements and queries referring to it,
+    // quoting the new name where it's a keyword
+    let analysis = Analysis::new(
+        "table Users (userId: int<0, >);
+alter table Users add column bio: str?;
+view Top as select Users.userId from Users;
+update Users set bio = null;
+rename table Users to People;",
+    );
+    let at = |line: u32, character: u32| Position { line, character };
+    assert_eq!(analysis.definition(at(3, 8)).unwrap().range.start, at(0, 6));
+    let edits = analysis.rename(at(0, 6), "table")?;
+    let starts: Vec<Position> =
+        edits.iter().map(|(range, _)| range.start).collect();
+    assert_eq!(
+        starts,
+        [
+            at(0, 6),
+            at(1, 12),
+            at(2, 19),
+            at(2, 37),
+            at(3, 7),
+            at(4, 13)
+        ]
+    );
+    assert!(edits.iter().all(|(_, text)| text == "\"table\""));
+    assert_eq!(
+        analysis.rename(at(2, 5), "Best")?,
+        [(
+            analysis.name_at(at(2, 5)).unwrap().range,
+            "Best".to_string()
+        )]
+    );
+
+    let symbols = analysis.document_symbols();
+    assert_eq!(symbols.len(), 2);
+    assert_eq!(symbols[1]["name"], "Top");
+
+    Ok(())
+}